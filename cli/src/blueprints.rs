@@ -2,14 +2,13 @@ use std::fmt::Display;
 use std::fs::read_dir;
 use std::path::{Path, PathBuf};
 use std::{
-    collections::HashMap,
     fs::File,
     io::{Read, Write},
 };
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tuecore::graph::{node::Node, Graph, GraphGetters};
+use tuecore::graph::{subgraph::Subgraph, Graph};
 
 pub type BlueprintResult<T> = Result<T, BlueprintError>;
 
@@ -29,13 +28,17 @@ pub enum BlueprintError {
 
     #[error("Failed to access blueprint: {0}")]
     FailedToAccess(String),
-}
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-pub struct BlueprintGraph {
-    pub(crate) nodes: Vec<Node>,
+    #[error(
+        "Blueprint nodes form a cycle: {} (each node is a parent of the next)",
+        .0.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    Cycle(Vec<usize>),
 }
 
+/// The tree stored in a blueprint, its top node first
+pub type BlueprintGraph = Subgraph;
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 pub struct BlueprintDoc {
     /// Author of blueprint.
@@ -48,95 +51,10 @@ pub struct BlueprintDoc {
     pub(crate) parent: usize,
 }
 
-// same algo as core/graph.rs for remapping indices except we use hashmaps here since the remapped
-// indices count is almost definitely gonna be smaller
-fn _insert_idx_recurse(
-    graph: &Graph,
-    map: &mut HashMap<usize, usize>,
-    node: usize,
-    current_idx: &mut usize,
-) {
-    if map.get(&node).is_none() {
-        map.insert(node, *current_idx);
-        *current_idx += 1;
-        true
-    } else {
-        false
-    };
-
-    let node_children = &graph.get_node(node).metadata.children;
-    for child in node_children {
-        _insert_idx_recurse(graph, map, *child, current_idx);
-    }
-}
-
-fn new_bp_indices_map(graph: &Graph, node: usize) -> HashMap<usize, usize> {
-    let mut map = HashMap::new();
-    _insert_idx_recurse(graph, &mut map, node, &mut 0);
-    map
-}
-
-fn _write_node_recurse(
-    graph: &Graph,
-    indices_map: &HashMap<usize, usize>,
-    source_idx: usize,
-    nodes_store: &mut Vec<Node>,
-) {
-    let node = graph.get_node(source_idx);
-
-    let children = node.metadata.children.clone();
-
-    if nodes_store.get(indices_map[&source_idx]).is_none() {
-        nodes_store.push(node);
-    }
-
-    for child in children {
-        _write_node_recurse(graph, indices_map, child, nodes_store);
-    }
-
-    let node_ref = &mut nodes_store[indices_map[&source_idx]];
-    node_ref.metadata.index = indices_map[&source_idx];
-
-    if indices_map[&source_idx] == 0 {
-        node_ref.metadata.parents.clear();
-    }
-}
-
-fn write_node_recurse(graph: &Graph, source_idx: usize, nodes_store: &mut Vec<Node>) {
-    let map = new_bp_indices_map(graph, source_idx);
-    _write_node_recurse(graph, &map, source_idx, nodes_store);
-
-    for node in nodes_store {
-        node.metadata.parents = node
-            .metadata
-            .parents
-            .iter()
-            .filter_map(|i| map.get(i))
-            .copied()
-            .collect();
-        node.metadata.children = node
-            .metadata
-            .children
-            .iter()
-            .filter_map(|i| map.get(i))
-            .copied()
-            .collect();
-    }
-}
-
-impl BlueprintGraph {
-    pub fn from_idx(graph: &Graph, idx: usize) -> Self {
-        let mut nodes = Vec::new();
-        write_node_recurse(graph, idx, &mut nodes);
-
-        Self { nodes }
-    }
-}
-
 impl BlueprintDoc {
     pub fn from_idx(graph: &Graph, version: u32, parent: usize, author: Option<String>) -> Self {
         Self {
-            graph: BlueprintGraph::from_idx(graph, parent),
+            graph: graph.extract(parent),
             // TODO: parent is literally always 0 so why even have it
             parent: 0,
             version,
@@ -200,19 +118,24 @@ pub fn try_get_blueprint_from_save_dir(
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
     use tuecore::{
         doc::get_doc_ver,
         graph::{
-            node::{task::TaskData, Node, NodeMetadata, NodeType},
-            Graph,
+            filter::TraverseFilter,
+            node::{
+                task::{Priority, TaskData},
+                Node, NodeMetadata, NodeType,
+            },
+            Graph, GraphGetters,
         },
     };
 
-    use crate::{blueprints::BlueprintGraph, config::CliConfig, display::Displayer};
+    use crate::{
+        blueprints::BlueprintGraph, config::CliConfig, display::Displayer,
+        graph::graph_from_blueprint, AppError,
+    };
 
-    use super::{new_bp_indices_map, BlueprintDoc};
+    use super::{BlueprintDoc, BlueprintError};
 
     fn example_graph() -> Graph {
         let mut example_graph = Graph::new();
@@ -243,27 +166,13 @@ mod tests {
         let cfg = CliConfig::default();
         let displayer = Displayer::new(&cfg);
 
-        displayer.list_roots(&example_graph, 0, false).unwrap();
+        displayer
+            .list_roots(&example_graph, 0, false, &TraverseFilter::default())
+            .unwrap();
 
         example_graph
     }
 
-    #[test]
-    fn test_indices_mapper() {
-        let example_graph = example_graph();
-
-        let mut map_should_be = HashMap::new();
-        map_should_be.insert(2, 0);
-        map_should_be.insert(3, 1);
-        map_should_be.insert(4, 2);
-        map_should_be.insert(5, 3);
-        map_should_be.insert(6, 4);
-
-        let map = new_bp_indices_map(&example_graph, 2);
-
-        assert_eq!(map, map_should_be);
-    }
-
     #[test]
     fn write_to_blueprint() {
         let graph = example_graph();
@@ -283,6 +192,7 @@ mod tests {
                             children: vec![1, 2, 4],
                             ..Default::default()
                         },
+                        ..Default::default()
                     },
                     Node {
                         title: "child (1) of child (2)".to_string(),
@@ -292,6 +202,7 @@ mod tests {
                             parents: vec![0],
                             ..Default::default()
                         },
+                        ..Default::default()
                     },
                     Node {
                         title: "child (2) of child (2)".to_string(),
@@ -302,6 +213,7 @@ mod tests {
                             parents: vec![0],
                             ..Default::default()
                         },
+                        ..Default::default()
                     },
                     Node {
                         title: "child (1) of child (1) of child (2)".to_string(),
@@ -311,6 +223,7 @@ mod tests {
                             parents: vec![2],
                             ..Default::default()
                         },
+                        ..Default::default()
                     },
                     Node {
                         title: "child (3) of child (2)".to_string(),
@@ -320,6 +233,7 @@ mod tests {
                             parents: vec![0],
                             ..Default::default()
                         },
+                        ..Default::default()
                     },
                 ],
             },
@@ -327,4 +241,108 @@ mod tests {
 
         assert_eq!(doc, should_be);
     }
+
+    #[test]
+    fn blueprint_keeps_priority() {
+        let mut graph = example_graph();
+        graph.set_task_priority(2, Priority::High).unwrap();
+        graph.set_task_priority(5, Priority::Urgent).unwrap();
+
+        let doc = BlueprintDoc::from_idx(&graph, get_doc_ver(), 2, None);
+        let doc = serde_yaml_ng::from_str::<BlueprintDoc>(&doc.to_string()).unwrap();
+        let bp_graph = graph_from_blueprint(&doc).unwrap();
+
+        assert_eq!(bp_graph.priority_of(0), Priority::High);
+        assert_eq!(bp_graph.priority_of(3), Priority::Urgent);
+        assert_eq!(
+            bp_graph.get_node(3).title,
+            "child (1) of child (1) of child (2)"
+        );
+    }
+
+    #[test]
+    fn blueprint_keeps_tags() {
+        let mut graph = example_graph();
+        graph.add_tag(2, "#work").unwrap();
+        graph.add_tag(5, "work").unwrap();
+        graph.add_tag(5, "errand").unwrap();
+
+        let doc = BlueprintDoc::from_idx(&graph, get_doc_ver(), 2, None);
+        let doc = serde_yaml_ng::from_str::<BlueprintDoc>(&doc.to_string()).unwrap();
+        let bp_graph = graph_from_blueprint(&doc).unwrap();
+
+        assert_eq!(bp_graph.get_tagged("work"), &[0, 3]);
+        assert_eq!(bp_graph.get_tagged("errand"), &[3]);
+        assert_eq!(bp_graph.get_node(3).metadata.tags, vec!["work", "errand"]);
+    }
+
+    #[test]
+    fn blueprint_keeps_inner_dependencies() {
+        let mut graph = example_graph();
+        // Inside the blueprint
+        graph.add_dependency(6, 3).unwrap();
+        // Leaving the blueprint
+        graph.add_dependency(3, 1).unwrap();
+
+        let doc = BlueprintDoc::from_idx(&graph, get_doc_ver(), 2, None);
+        let doc = serde_yaml_ng::from_str::<BlueprintDoc>(&doc.to_string()).unwrap();
+        let bp_graph = graph_from_blueprint(&doc).unwrap();
+
+        assert_eq!(bp_graph.get_node(4).metadata.blocked_by, vec![1]);
+        assert_eq!(bp_graph.get_node(1).metadata.blocks, vec![4]);
+        assert!(bp_graph.get_node(1).metadata.blocked_by.is_empty());
+        assert!(bp_graph.is_blocked(4));
+    }
+
+    #[test]
+    fn blueprint_rejects_cycles() {
+        let graph = example_graph();
+        let mut doc = BlueprintDoc::from_idx(&graph, get_doc_ver(), 2, None);
+        doc.graph.nodes[3].metadata.children.push(0);
+
+        match graph_from_blueprint(&doc) {
+            Err(AppError::BlueprintError(BlueprintError::Cycle(path))) => {
+                assert_eq!(path, vec![0, 2, 3, 0])
+            }
+            other => panic!("expected a cycle error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn blueprint_keeps_legacy_alias() {
+        // Blueprints saved before nodes could have several aliases
+        let doc = r#"
+author: null
+version: 18
+graph:
+  nodes:
+  - title: proj
+    data: !Task
+      state: None
+    metadata:
+      archived: false
+      index: 0
+      alias: proj
+      parents: []
+      children:
+      - 1
+  - title: step
+    data: !Task
+      state: None
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      parents:
+      - 0
+      children: []
+parent: 0
+"#;
+        let doc = serde_yaml_ng::from_str::<BlueprintDoc>(doc).unwrap();
+        assert_eq!(doc.graph.nodes[0].metadata.aliases, vec!["proj"]);
+        assert!(doc.graph.nodes[1].metadata.aliases.is_empty());
+
+        let bp_graph = graph_from_blueprint(&doc).unwrap();
+        assert_eq!(bp_graph.get_index("proj").unwrap(), 0);
+    }
 }
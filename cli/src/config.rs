@@ -8,7 +8,9 @@ use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
 use thiserror::Error;
+use tuecore::graph::node::task::{Propagation, Rollup};
 
 use crate::display::Color;
 use crate::paths::get_default_path;
@@ -61,6 +63,8 @@ pub struct GraphConfig {
     pub(crate) auto_clean: bool,
     /// In percentage, how much of the total graph node count should the [None] nodes composite before auto clean is activated
     pub(crate) auto_clean_threshold: u8,
+    /// How nodes without a policy of their own propagate completion
+    pub(crate) propagation: Propagation,
 }
 
 impl Default for GraphConfig {
@@ -68,6 +72,10 @@ impl Default for GraphConfig {
         Self {
             auto_clean: DEFAULT_GRAPH_AUTO_CLEAN,
             auto_clean_threshold: DEFAULT_GRAPH_AUTO_CLEAN_THRESHOLD,
+            propagation: Propagation {
+                rollup: DEFAULT_GRAPH_ROLLUP,
+                cascade: DEFAULT_GRAPH_CASCADE,
+            },
         }
     }
 }
@@ -109,6 +117,10 @@ pub struct DisplayIconConfig {
     pub(crate) node_none: Icon,
     pub(crate) node_checked: Icon,
     pub(crate) node_partial: Icon,
+    pub(crate) node_in_progress: Icon,
+    pub(crate) node_blocked: Icon,
+    pub(crate) node_waiting: Icon,
+    pub(crate) node_cancelled: Icon,
     pub(crate) node_pseudo: Icon,
     pub(crate) node_date: Icon,
 }
@@ -129,6 +141,14 @@ impl Default for DisplayIconConfig {
                 .colorize(DEFAULT_COLOR_NODE_CHECKED),
             node_partial: Icon::from(DEFAULT_ICON_NODE_PARTIAL)
                 .colorize(DEFAULT_COLOR_NODE_PARTIAL),
+            node_in_progress: Icon::from(DEFAULT_ICON_NODE_IN_PROGRESS)
+                .colorize(DEFAULT_COLOR_NODE_IN_PROGRESS),
+            node_blocked: Icon::from(DEFAULT_ICON_NODE_BLOCKED)
+                .colorize(DEFAULT_COLOR_NODE_BLOCKED),
+            node_waiting: Icon::from(DEFAULT_ICON_NODE_WAITING)
+                .colorize(DEFAULT_COLOR_NODE_WAITING),
+            node_cancelled: Icon::from(DEFAULT_ICON_NODE_CANCELLED)
+                .colorize(DEFAULT_COLOR_NODE_CANCELLED),
             node_pseudo: Icon::from(DEFAULT_ICON_NODE_PSEUDO).colorize(DEFAULT_COLOR_NODE_PSEUDO),
             node_date: Icon::from(DEFAULT_ICON_NODE_DATE).colorize(DEFAULT_COLOR_NODE_DATE),
         }
@@ -153,6 +173,7 @@ pub struct DisplayConfig {
     pub(crate) icons: DisplayIconConfig,
     pub(crate) calendar_config: CalendarConfig,
     pub(crate) bar_indent: bool,
+    pub(crate) progress_percent: bool,
 }
 
 impl Default for DisplayConfig {
@@ -160,6 +181,7 @@ impl Default for DisplayConfig {
         Self {
             show_connections: DEFAULT_SHOW_CONNECTIONS,
             bar_indent: DEFAULT_BAR_INDENT,
+            progress_percent: DEFAULT_PROGRESS_PERCENT,
             date_fmt: DEFAULT_DATE_FORMAT.to_string(),
             calendar_config: CalendarConfig::default(),
             icons: DisplayIconConfig::default(),
@@ -198,9 +220,12 @@ pub fn read_file(path: &Path) -> ConfigParseResult<toml::Table> {
 
 const KEY_GRAPH: &str = "graph";
 const KEY_DISPLAY: &str = "display";
+const KEY_ROLLUP: &str = "rollup";
+const KEY_CASCADE: &str = "cascade";
 const KEY_AUTO_CLEAN: &str = "auto_clean";
 const KEY_AUTO_CLEAN_THRESHOLD: &str = "auto_clean_threshold";
 const KEY_BAR_INDENT: &str = "bar_indent";
+const KEY_PROGRESS_PERCENT: &str = "progress_percent";
 const KEY_DATE_FMT: &str = "date_fmt";
 const KEY_SHOW_CONNECTIONS: &str = "show_connections";
 const KEY_DISPLAY_ICONS: &str = "icons";
@@ -214,6 +239,10 @@ const KEY_DISPLAY_ICONS_ARM_BAR: &str = "arm_bar";
 const KEY_DISPLAY_ICONS_NODE_NONE: &str = "node_none";
 const KEY_DISPLAY_ICONS_NODE_CHECKED: &str = "node_checked";
 const KEY_DISPLAY_ICONS_NODE_PARTIAL: &str = "node_partial";
+const KEY_DISPLAY_ICONS_NODE_IN_PROGRESS: &str = "node_in_progress";
+const KEY_DISPLAY_ICONS_NODE_BLOCKED: &str = "node_blocked";
+const KEY_DISPLAY_ICONS_NODE_WAITING: &str = "node_waiting";
+const KEY_DISPLAY_ICONS_NODE_CANCELLED: &str = "node_cancelled";
 const KEY_DISPLAY_ICONS_NODE_PSEUDO: &str = "node_pseudo";
 const KEY_DISPLAY_ICONS_NODE_DATE: &str = "node_date";
 const KEY_DISPLAY_CALENDAR: &str = "calendar";
@@ -238,6 +267,16 @@ pub fn parse_config(toml: &toml::Table) -> ConfigParseResult<CliConfig> {
         {
             conf.graph.auto_clean_threshold = val as u8;
         }
+        if let Some(val) = graph_cfg
+            .get(KEY_ROLLUP)
+            .and_then(toml::Value::as_str)
+            .and_then(|val| Rollup::from_str(val, true).ok())
+        {
+            conf.graph.propagation.rollup = val;
+        }
+        if let Some(val) = graph_cfg.get(KEY_CASCADE).and_then(toml::Value::as_bool) {
+            conf.graph.propagation.cascade = val;
+        }
     }
 
     // Display configuration
@@ -253,6 +292,13 @@ pub fn parse_config(toml: &toml::Table) -> ConfigParseResult<CliConfig> {
             conf.display.bar_indent = val;
         }
 
+        if let Some(val) = display_cfg
+            .get(KEY_PROGRESS_PERCENT)
+            .and_then(toml::Value::as_bool)
+        {
+            conf.display.progress_percent = val;
+        }
+
         if let Some(val) = display_cfg
             .get(KEY_SHOW_CONNECTIONS)
             .and_then(toml::Value::as_bool)
@@ -409,6 +455,86 @@ pub fn parse_config(toml: &toml::Table) -> ConfigParseResult<CliConfig> {
                 }
             }
 
+            if let Some(node_in_progress) = icons.get(KEY_DISPLAY_ICONS_NODE_IN_PROGRESS) {
+                if let Some(val) = node_in_progress
+                    .get(KEY_DISPLAY_ICON)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_in_progress.value = val.to_string();
+                }
+
+                if let Some(val) = node_in_progress
+                    .get(KEY_DISPLAY_COLOR)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_in_progress.color = Color::from_str(val).map_err(|_| {
+                        ConfigReadError::ColorParseErr(format!(
+                            "Invalid color for {KEY_DISPLAY}{KEY_DISPLAY_ICONS}{KEY_DISPLAY_ICONS_NODE_IN_PROGRESS}: {val}"
+                        ))
+                    })?;
+                }
+            }
+
+            if let Some(node_blocked) = icons.get(KEY_DISPLAY_ICONS_NODE_BLOCKED) {
+                if let Some(val) = node_blocked
+                    .get(KEY_DISPLAY_ICON)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_blocked.value = val.to_string();
+                }
+
+                if let Some(val) = node_blocked
+                    .get(KEY_DISPLAY_COLOR)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_blocked.color = Color::from_str(val).map_err(|_| {
+                        ConfigReadError::ColorParseErr(format!(
+                            "Invalid color for {KEY_DISPLAY}{KEY_DISPLAY_ICONS}{KEY_DISPLAY_ICONS_NODE_BLOCKED}: {val}"
+                        ))
+                    })?;
+                }
+            }
+
+            if let Some(node_waiting) = icons.get(KEY_DISPLAY_ICONS_NODE_WAITING) {
+                if let Some(val) = node_waiting
+                    .get(KEY_DISPLAY_ICON)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_waiting.value = val.to_string();
+                }
+
+                if let Some(val) = node_waiting
+                    .get(KEY_DISPLAY_COLOR)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_waiting.color = Color::from_str(val).map_err(|_| {
+                        ConfigReadError::ColorParseErr(format!(
+                            "Invalid color for {KEY_DISPLAY}{KEY_DISPLAY_ICONS}{KEY_DISPLAY_ICONS_NODE_WAITING}: {val}"
+                        ))
+                    })?;
+                }
+            }
+
+            if let Some(node_cancelled) = icons.get(KEY_DISPLAY_ICONS_NODE_CANCELLED) {
+                if let Some(val) = node_cancelled
+                    .get(KEY_DISPLAY_ICON)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_cancelled.value = val.to_string();
+                }
+
+                if let Some(val) = node_cancelled
+                    .get(KEY_DISPLAY_COLOR)
+                    .and_then(toml::Value::as_str)
+                {
+                    conf.display.icons.node_cancelled.color = Color::from_str(val).map_err(|_| {
+                        ConfigReadError::ColorParseErr(format!(
+                            "Invalid color for {KEY_DISPLAY}{KEY_DISPLAY_ICONS}{KEY_DISPLAY_ICONS_NODE_CANCELLED}: {val}"
+                        ))
+                    })?;
+                }
+            }
+
             if let Some(node_pseudo) = icons.get(KEY_DISPLAY_ICONS_NODE_PSEUDO) {
                 if let Some(val) = node_pseudo
                     .get(KEY_DISPLAY_ICON)
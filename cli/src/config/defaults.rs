@@ -1,5 +1,7 @@
 //! Baked-in defaults for the tuecli's configuration.
 
+use tuecore::graph::node::task::Rollup;
+
 use crate::display::{Color, ColorEnum};
 
 pub const DEFAULT_HEATMAP_PALETTE: [Color; 5] = [
@@ -13,11 +15,14 @@ pub const DEFAULT_HEATMAP_PALETTE: [Color; 5] = [
 // Graph section
 pub const DEFAULT_GRAPH_AUTO_CLEAN: bool = false;
 pub const DEFAULT_GRAPH_AUTO_CLEAN_THRESHOLD: u8 = 50;
+pub const DEFAULT_GRAPH_ROLLUP: Rollup = Rollup::Auto;
+pub const DEFAULT_GRAPH_CASCADE: bool = true;
 
 // Display section
 pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
 pub const DEFAULT_SHOW_CONNECTIONS: bool = true;
 pub const DEFAULT_BAR_INDENT: bool = false;
+pub const DEFAULT_PROGRESS_PERCENT: bool = false;
 
 // Icons - arms
 pub const DEFAULT_ICON_ARM: &str = "+--";
@@ -45,6 +50,18 @@ pub const DEFAULT_COLOR_NODE_CHECKED: ColorEnum = ColorEnum::Green;
 pub const DEFAULT_ICON_NODE_PARTIAL: &str = "[~]";
 pub const DEFAULT_COLOR_NODE_PARTIAL: ColorEnum = ColorEnum::Orange;
 
+pub const DEFAULT_ICON_NODE_IN_PROGRESS: &str = "[>]";
+pub const DEFAULT_COLOR_NODE_IN_PROGRESS: ColorEnum = ColorEnum::Blue;
+
+pub const DEFAULT_ICON_NODE_BLOCKED: &str = "[!]";
+pub const DEFAULT_COLOR_NODE_BLOCKED: ColorEnum = ColorEnum::Red;
+
+pub const DEFAULT_ICON_NODE_WAITING: &str = "[?]";
+pub const DEFAULT_COLOR_NODE_WAITING: ColorEnum = ColorEnum::Magenta;
+
+pub const DEFAULT_ICON_NODE_CANCELLED: &str = "[-]";
+pub const DEFAULT_COLOR_NODE_CANCELLED: ColorEnum = ColorEnum::DarkGrey;
+
 pub const DEFAULT_ICON_NODE_PSEUDO: &str = "[*]";
 pub const DEFAULT_COLOR_NODE_PSEUDO: ColorEnum = ColorEnum::Yellow;
 
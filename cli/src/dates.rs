@@ -1,5 +1,8 @@
-use chrono::{DateTime, Datelike, FixedOffset, Local};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveTime};
 use parse_datetime::{parse_datetime, ParseDateTimeError};
+use tuecore::graph::node::task::DueDate;
+
+use crate::{AppError, AppResult};
 
 /// Wrapper for parse_datetime that also allows parses months.
 pub fn parse_datetime_extended(input: &str) -> Result<DateTime<FixedOffset>, ParseDateTimeError> {
@@ -22,3 +25,17 @@ pub fn parse_datetime_extended(input: &str) -> Result<DateTime<FixedOffset>, Par
 
     parse_datetime(&extended_result)
 }
+
+/// Parses a due date and an optional time of day (`HH:MM` or `HH:MM:SS`) into a [`DueDate`].
+pub fn parse_due(date: &str, time: Option<&str>) -> AppResult<DueDate> {
+    let date = parse_datetime_extended(date)?.date_naive();
+    let time = match time {
+        Some(time) => Some(
+            NaiveTime::parse_from_str(time, "%H:%M")
+                .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+                .map_err(|_| AppError::ParseError(format!("Invalid time of day: '{time}'")))?,
+        ),
+        None => None,
+    };
+    Ok(DueDate::new(date, time))
+}
@@ -1,7 +1,15 @@
-use chrono::{Datelike, NaiveDate};
+use std::collections::HashSet;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta};
 use colored::Colorize;
-use tuecore::graph::node::task::{TaskData, TaskState};
+use tuecore::doc::merge::Merge;
+use tuecore::graph::filter::TraverseFilter;
+use tuecore::graph::node::task::{DueDate, Priority, TaskData, TaskState};
 use tuecore::graph::node::{Node, NodeType};
+use tuecore::graph::recur::{RecurTarget, Recurrence};
+use tuecore::graph::search::SearchHit;
+use tuecore::graph::time::Interval;
+use tuecore::graph::validate::Problem;
 use tuecore::graph::{Graph, GraphGetters};
 
 use crate::config::{CliConfig, DEFAULT_CONFIG};
@@ -127,6 +135,28 @@ impl<'a> Displayer<'a> {
         format!("{}", "Aliases:".bold())
     }
 
+    pub fn tags_title(&self) -> String {
+        format!("{}", "Tags:".bold())
+    }
+
+    pub fn display_tag(&self, tag: &str) -> String {
+        format!("#{tag}").magenta().to_string()
+    }
+
+    fn display_tags(&self, node: &Node) -> Option<String> {
+        if node.metadata.tags.is_empty() {
+            return None;
+        }
+        Some(
+            node.metadata
+                .tags
+                .iter()
+                .map(|tag| self.display_tag(tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
     pub fn parents_title(&self) -> String {
         format!(
             "Node has more than one parents, please specify the parent!\n{}",
@@ -166,6 +196,39 @@ impl<'a> Displayer<'a> {
         println!("{} -> {}", from.bright_blue(), to.to_string().bright_blue());
     }
 
+    pub fn display_recurrence(&self, idx: usize, recurrence: &Recurrence) {
+        let target = match recurrence.target {
+            RecurTarget::Task(ref title) => title.clone(),
+            RecurTarget::Blueprint(ref name) => format!("blueprint {}", name.green()),
+        };
+        println!(
+            " {}: {} {} (from {})",
+            idx.to_string().bright_blue(),
+            recurrence.rule.to_string().cyan(),
+            target,
+            recurrence.start.format(&self.config.display.date_fmt)
+        );
+    }
+
+    pub fn list_recurrences(&self, recurrences: &[Recurrence]) {
+        println!("{}", "Recurrences:".bold());
+
+        if recurrences.is_empty() {
+            println!("No recurring tasks.");
+        }
+
+        for (idx, recurrence) in recurrences.iter().enumerate() {
+            self.display_recurrence(idx, recurrence);
+        }
+    }
+
+    pub fn display_recur_synced(&self, count: usize) {
+        println!(
+            "Created {} recurring task(s).",
+            count.to_string().bright_blue()
+        );
+    }
+
     pub fn list_blueprints(&self, files: &[String]) {
         println!("{}", "Blueprints:".bold());
 
@@ -196,6 +259,38 @@ impl<'a> Displayer<'a> {
                 .to_string()
                 .custom_color(self.config.display.icons.node_partial.color.tup())
                 .to_string(),
+            TaskState::InProgress => self
+                .config
+                .display
+                .icons
+                .node_in_progress
+                .to_string()
+                .custom_color(self.config.display.icons.node_in_progress.color.tup())
+                .to_string(),
+            TaskState::Blocked => self
+                .config
+                .display
+                .icons
+                .node_blocked
+                .to_string()
+                .custom_color(self.config.display.icons.node_blocked.color.tup())
+                .to_string(),
+            TaskState::Waiting => self
+                .config
+                .display
+                .icons
+                .node_waiting
+                .to_string()
+                .custom_color(self.config.display.icons.node_waiting.color.tup())
+                .to_string(),
+            TaskState::Cancelled => self
+                .config
+                .display
+                .icons
+                .node_cancelled
+                .to_string()
+                .custom_color(self.config.display.icons.node_cancelled.color.tup())
+                .to_string(),
             TaskState::Done => self
                 .config
                 .display
@@ -207,6 +302,18 @@ impl<'a> Displayer<'a> {
         }
     }
 
+    /// Formats a task's priority as a row of exclamation marks. Returns [`None`] for tasks
+    /// without a priority.
+    fn display_priority(&self, priority: Priority) -> Option<String> {
+        match priority {
+            Priority::None => None,
+            Priority::Low => Some("!".blue().to_string()),
+            Priority::Medium => Some("!!".yellow().to_string()),
+            Priority::High => Some("!!!".bright_red().to_string()),
+            Priority::Urgent => Some("!!!!".red().bold().to_string()),
+        }
+    }
+
     fn display_nodetype(&self, node_type: &NodeType) -> String {
         match node_type {
             NodeType::Task(data) => self.display_task_data(data),
@@ -229,14 +336,77 @@ impl<'a> Displayer<'a> {
         }
     }
 
-    fn fmt_node(&self, node: &Node) -> String {
-        let index = if let Some(ref alias) = node.metadata.alias {
-            format!("({}:{})", node.metadata.index, alias)
+    fn fmt_due(&self, due: &DueDate) -> String {
+        match due.time {
+            Some(time) => format!(
+                "{} {}",
+                due.date.format(&self.config.display.date_fmt),
+                time.format("%H:%M")
+            ),
+            None => due.date.format(&self.config.display.date_fmt).to_string(),
+        }
+    }
+
+    /// Formats a task's due date, highlighting it if the task is overdue.
+    fn display_due(&self, task_data: &TaskData) -> Option<String> {
+        let due = task_data.due.as_ref()?;
+        let text = format!("(due {})", self.fmt_due(due));
+        if task_data.is_overdue(Local::now().naive_local()) {
+            Some(text.red().bold().to_string())
+        } else {
+            Some(text.yellow().to_string())
+        }
+    }
+
+    /// Formats the tasks a task still waits on. Returns [`None`] if it is not blocked.
+    fn display_blockers(&self, graph: &Graph, node: &Node) -> Option<String> {
+        let blockers = graph.pending_blockers(node.metadata.index);
+        if blockers.is_empty() {
+            return None;
+        }
+        let blockers = blockers
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(format!("(blocked by {blockers})").red().to_string())
+    }
+
+    /// Displays the progress of a node with subtasks, e.g. `[3/5]`
+    fn display_progress(&self, graph: &Graph, node: &Node) -> Option<String> {
+        if node.metadata.children.is_empty() {
+            return None;
+        }
+        let progress = graph.progress(node.metadata.index)?;
+        if self.config.display.progress_percent {
+            Some(format!("[{}%]", (progress.fraction() * 100.0).floor()))
+        } else {
+            Some(format!("[{}/{}]", progress.done.floor(), progress.total))
+        }
+    }
+
+    fn fmt_node(&self, graph: &Graph, node: &Node) -> String {
+        let index = if !node.metadata.aliases.is_empty() {
+            format!(
+                "({}:{})",
+                node.metadata.index,
+                node.metadata.aliases.join(",")
+            )
         } else {
             format!("({})", node.metadata.index)
         }
         .bright_blue();
-        let state = self.display_nodetype(&node.data);
+        let mut state = self.display_nodetype(&node.data);
+        if let Some(priority) = node
+            .data
+            .as_task()
+            .and_then(|data| self.display_priority(data.priority))
+        {
+            state = format!("{state} {priority}");
+        }
+        if let Some(progress) = self.display_progress(graph, node) {
+            state = format!("{state} {}", progress.dimmed());
+        }
 
         let dim = node.metadata.archived;
 
@@ -263,16 +433,36 @@ impl<'a> Displayer<'a> {
                     index
                 )
             }
-        } else if dim {
-            format!("{} {} {}", state, node.title.dimmed(), index)
         } else {
-            format!("{} {} {}", state, node.title, index)
+            let title = if dim {
+                node.title.dimmed().to_string()
+            } else {
+                node.title.clone()
+            };
+            let mut line = format!("{state} {title}");
+            if let Some(due) = node.data.as_task().and_then(|data| self.display_due(data)) {
+                line = format!("{line} {due}");
+            }
+            if let Some(blockers) = self.display_blockers(graph, node) {
+                line = format!("{line} {blockers}");
+            }
+            if let Some(tags) = self.display_tags(node) {
+                line = format!("{line} {tags}");
+            }
+            format!("{line} {index}")
         }
     }
 
-    pub fn display_node(&self, node: &Node, depth: u32, last: bool, skipped_depths: &[u32]) {
+    pub fn display_node(
+        &self,
+        graph: &Graph,
+        node: &Node,
+        depth: u32,
+        last: bool,
+        skipped_depths: &[u32],
+    ) {
         self.print_tree_indent(depth, node.metadata.parents.len() > 1, last, skipped_depths);
-        println!("{}", self.fmt_node(node));
+        println!("{}", self.fmt_node(graph, node));
     }
 
     pub fn print_tree_indent(&self, depth: u32, dots: bool, last: bool, skipped_depths: &[u32]) {
@@ -355,19 +545,28 @@ impl<'a> Displayer<'a> {
         }
     }
 
-    pub fn list_roots(&self, graph: &Graph, max_depth: u32, show_archived: bool) -> AppResult<()> {
+    pub fn list_roots(
+        &self,
+        graph: &Graph,
+        max_depth: u32,
+        show_archived: bool,
+        filter: &TraverseFilter,
+    ) -> AppResult<()> {
         // TODO: wonky
-        let indices = graph
+        let mut indices: Vec<usize> = graph
             .get_root_nodes_indices()
             .iter()
-            .filter(|idx| !graph.get_node(**idx).metadata.archived || show_archived);
+            .filter(|idx| !graph.get_node(**idx).metadata.archived || show_archived)
+            .copied()
+            .collect();
+        filter.apply(graph, &mut indices);
         if max_depth == 1 {
             for i in indices {
-                graph.with_node(*i, &mut |node| self.display_node(node, 0, false, &[]));
+                graph.with_node(i, &mut |node| self.display_node(graph, node, 0, false, &[]));
             }
         } else {
             for i in indices {
-                self.list_children(graph, *i, max_depth.saturating_sub(1), show_archived)?
+                self.list_children(graph, i, max_depth.saturating_sub(1), show_archived, filter)?
             }
         }
         Ok(())
@@ -379,24 +578,84 @@ impl<'a> Displayer<'a> {
         let indices = graph.get_archived_node_indices();
 
         for i in indices {
-            graph.with_node(*i, &mut |node| self.display_node(node, 0, false, &[]));
+            graph.with_node(*i, &mut |node| {
+                self.display_node(graph, node, 0, false, &[])
+            });
         }
         Ok(())
     }
 
-    pub fn list_dates(&self, graph: &Graph, show_archived: bool) -> AppResult<()> {
+    pub fn list_matches(&self, graph: &Graph, indices: &[usize]) {
+        if indices.is_empty() {
+            println!("No matching nodes.");
+        }
+        for i in indices {
+            graph.with_node(*i, &mut |node| {
+                self.display_node(graph, node, 0, false, &[])
+            });
+        }
+    }
+
+    /// Prints search hits, best first, each after the path (see [`Graph::path_of`]) of its parent.
+    pub fn list_search_hits(&self, graph: &Graph, hits: &[SearchHit]) {
+        if hits.is_empty() {
+            println!("No matching nodes.");
+        }
+        for hit in hits {
+            let path = graph
+                .ancestor_path(hit.index)
+                .last()
+                .map(|parent| format!("{}/", graph.path_of(*parent)))
+                .unwrap_or_default();
+            graph.with_node(hit.index, &mut |node| {
+                println!("{}{}", path.dimmed(), self.fmt_node(graph, node));
+            });
+        }
+    }
+
+    pub fn list_completed(&self, graph: &Graph, indices: &[usize]) {
+        if indices.is_empty() {
+            println!("No completed tasks.");
+        }
+        for i in indices {
+            graph.with_node(*i, &mut |node| {
+                let completed = node
+                    .metadata
+                    .completed
+                    .map(|time| self.fmt_timestamp(&time))
+                    .unwrap_or_default();
+                println!("{} {}", completed.dimmed(), self.fmt_node(graph, node));
+            });
+        }
+    }
+
+    fn fmt_timestamp(&self, time: &NaiveDateTime) -> String {
+        format!(
+            "{} {}",
+            time.format(&self.config.display.date_fmt),
+            time.format("%H:%M")
+        )
+    }
+
+    pub fn list_dates(
+        &self,
+        graph: &Graph,
+        show_archived: bool,
+        filter: &TraverseFilter,
+    ) -> AppResult<()> {
         let dates: Vec<usize> = graph
             .get_date_nodes_indices()
             .iter()
             .filter(|idx| !graph.get_node(**idx).metadata.archived || show_archived)
             .copied()
             .collect();
-        graph.traverse_recurse(
+        graph.traverse_recurse_filtered(
             dates.as_slice(),
             false,
             1,
+            filter,
             &mut |node, depth, last, depth_of_last| {
-                self.display_node(node, depth - 1, last, depth_of_last)
+                self.display_node(graph, node, depth - 1, last, depth_of_last)
             },
         )?;
         Ok(())
@@ -408,9 +667,12 @@ impl<'a> Displayer<'a> {
         target: usize,
         max_depth: u32,
         show_archived: bool,
+        filter: &TraverseFilter,
     ) -> AppResult<()> {
         // Display self as well
-        graph.with_node(target, &mut |node| self.display_node(node, 0, false, &[]));
+        graph.with_node(target, &mut |node| {
+            self.display_node(graph, node, 0, false, &[])
+        });
 
         // TODO: everything about listing nodes is strange (blame Daringcuteseal); we should be
         // able to just pass the node we want to a single graph method.
@@ -424,12 +686,13 @@ impl<'a> Displayer<'a> {
             .copied()
             .collect();
 
-        graph.traverse_recurse(
+        graph.traverse_recurse_filtered(
             &children,
             show_archived,
             max_depth,
+            filter,
             &mut |node, depth, last, depth_of_last| {
-                self.display_node(node, depth, last, depth_of_last)
+                self.display_node(graph, node, depth, last, depth_of_last)
             },
         )?;
         Ok(())
@@ -438,12 +701,12 @@ impl<'a> Displayer<'a> {
     pub fn print_stats(&self, graph: &Graph, target: Option<usize>) -> AppResult<()> {
         // If a specific node is specified
         if let Some(target) = target {
-            let node = graph.get_nodes()[target].as_ref().unwrap().borrow();
+            let node = graph.get_node(target);
             println!("ID      : {target}");
             println!("Message : {}", &node.title);
             println!("Parents :");
             for i in &node.metadata.parents {
-                let parent = graph.get_nodes()[*i].as_ref().unwrap().borrow();
+                let parent = graph.get_node(*i);
                 println!(
                     "({}) {} [{}]",
                     parent.metadata.index,
@@ -453,7 +716,7 @@ impl<'a> Displayer<'a> {
             }
             println!("Children:");
             for i in &node.metadata.children {
-                let child = graph.get_nodes()[*i].as_ref().unwrap().borrow();
+                let child = graph.get_node(*i);
                 println!(
                     "({}) {} [{}]",
                     child.metadata.index,
@@ -461,11 +724,101 @@ impl<'a> Displayer<'a> {
                     self.display_nodetype(&child.data)
                 );
             }
-            if let Some(ref alias) = node.metadata.alias {
-                println!("Alias   : {alias}");
+            if !node.metadata.blocked_by.is_empty() {
+                println!("Waits on:");
+                for i in &node.metadata.blocked_by {
+                    let blocker = graph.get_node(*i);
+                    println!(
+                        "({}) {} [{}]",
+                        blocker.metadata.index,
+                        blocker.title,
+                        self.display_nodetype(&blocker.data)
+                    );
+                }
+            }
+            if !node.metadata.blocks.is_empty() {
+                println!("Blocks  :");
+                for i in &node.metadata.blocks {
+                    let blocked = graph.get_node(*i);
+                    println!(
+                        "({}) {} [{}]",
+                        blocked.metadata.index,
+                        blocked.title,
+                        self.display_nodetype(&blocked.data)
+                    );
+                }
+            }
+            if !node.metadata.aliases.is_empty() {
+                println!("Aliases : {}", node.metadata.aliases.join(", "));
+            }
+            if let Some(tags) = self.display_tags(&node) {
+                println!("Tags    : {tags}");
             }
             println!("Archived: {}", node.metadata.archived);
             println!("Status  : [{}]", self.display_nodetype(&node.data));
+            if let Some(data) = node.data.as_task() {
+                println!("Priority: {:?}", data.priority);
+                if let Some(estimate) = data.estimate {
+                    println!("Estimate: {estimate}");
+                }
+                if let Some(ref due) = data.due {
+                    println!("Due     : {}", self.fmt_due(due));
+                }
+                if let Some(propagation) = node.metadata.propagation {
+                    println!(
+                        "Policy  : {} rollup, {}",
+                        format!("{:?}", propagation.rollup).to_lowercase(),
+                        if propagation.cascade {
+                            "cascades"
+                        } else {
+                            "doesn't cascade"
+                        }
+                    );
+                }
+            }
+            if let Some(ref created) = node.metadata.created {
+                println!("Created : {}", self.fmt_timestamp(created));
+            }
+            if let Some(ref modified) = node.metadata.modified {
+                println!("Modified: {}", self.fmt_timestamp(modified));
+            }
+            if let Some(ref completed) = node.metadata.completed {
+                println!("Finished: {}", self.fmt_timestamp(completed));
+            }
+            if let Some(progress) = graph.progress(target) {
+                println!(
+                    "Progress: {}/{} ({}%)",
+                    progress.done.floor(),
+                    progress.total,
+                    (progress.fraction() * 100.0).floor()
+                );
+            }
+            let total = graph.total_time(target);
+            if total > TimeDelta::zero() {
+                println!(
+                    "Time    : {} (own: {})",
+                    self.fmt_duration(total),
+                    self.fmt_duration(graph.time_spent(target))
+                );
+                for i in &node.metadata.children {
+                    let time = graph.total_time(*i);
+                    if time > TimeDelta::zero() {
+                        let child = graph.get_node(*i);
+                        println!(
+                            "  ({}) {}: {}",
+                            child.metadata.index,
+                            child.title,
+                            self.fmt_duration(time)
+                        );
+                    }
+                }
+            }
+            if let Some(ref note) = node.note {
+                println!("Note    :");
+                for line in note.lines() {
+                    println!("  {line}");
+                }
+            }
 
         // Else, list out stats for the whole graph
         } else {
@@ -483,7 +836,7 @@ impl<'a> Displayer<'a> {
                     .get_nodes()
                     .iter()
                     .fold(0, |acc, x| if let Some(x) = x {
-                        acc + x.borrow().metadata.parents.len()
+                        acc + x.metadata.parents.len()
                     } else {
                         acc
                     })
@@ -492,7 +845,17 @@ impl<'a> Displayer<'a> {
             println!("Roots   : {}", graph.get_roots().len());
             println!("Dates   : {}", graph.get_dates().len());
             println!("Aliases : {}", graph.get_aliases().len());
+            println!("Tags    : {}", graph.tag_count());
             println!("Archived: {}", graph.get_archived().len());
+            let tracked: TimeDelta = graph
+                .node_ids()
+                .into_iter()
+                .map(|i| graph.time_spent(i))
+                .sum();
+            println!("Tracked : {}", self.fmt_duration(tracked));
+            if let Some(timer) = graph.get_timer() {
+                println!("Timer   : running on ({})", timer.index);
+            }
         }
         Ok(())
     }
@@ -542,39 +905,11 @@ impl<'a> Displayer<'a> {
                 if let Ok(idx) = graph.get_date_index(
                     &NaiveDate::from_ymd_opt(date.year(), date.month(), curr_date).unwrap(),
                 ) {
-                    let node = graph.get_node(idx);
-                    let finished = node
-                        .metadata
-                        .children
-                        .iter()
-                        .filter(|idx| {
-                            let node = graph.get_node(**idx);
-                            if let NodeType::Task(data) = node.data {
-                                data.state == TaskState::Done
-                            } else {
-                                false
-                            }
-                        })
-                        .count();
-                    let total_nodes = node
-                        .metadata
-                        .children
-                        .iter()
-                        .filter(|idx| {
-                            let node = graph.get_node(**idx);
-                            if let NodeType::Task(_) = node.data {
-                                true
-                            } else {
-                                false
-                            }
-                        })
-                        .count();
-
-                    let range_finished = if total_nodes == 0 {
-                        0
-                    } else {
-                        finished * 4 / total_nodes
-                    };
+                    let fraction = graph
+                        .progress(idx)
+                        .map(|progress| progress.fraction())
+                        .unwrap_or(0.0);
+                    let range_finished = ((fraction * 4.0) as usize).min(4);
 
                     let color =
                         self.config.display.calendar_config.heatmap_palette[range_finished].tup();
@@ -636,6 +971,130 @@ impl<'a> Displayer<'a> {
         }
     }
 
+    /// Prints the changes that were undone (or redone)
+    /// Formats a duration as hours and minutes, e.g. `1h 05m`
+    fn fmt_duration(&self, duration: TimeDelta) -> String {
+        let minutes = duration.num_minutes();
+        if minutes < 60 {
+            format!("{minutes}m")
+        } else {
+            format!("{}h {:02}m", minutes / 60, minutes % 60)
+        }
+    }
+
+    pub fn print_timer_started(&self, graph: &Graph, index: usize) {
+        graph.with_node(index, &mut |node| {
+            println!("Started timer on {}", self.fmt_node(graph, node));
+        });
+    }
+
+    pub fn print_timer_stopped(&self, graph: &Graph, index: usize, interval: &Interval) {
+        graph.with_node(index, &mut |node| {
+            println!(
+                "Stopped timer on {} after {}",
+                self.fmt_node(graph, node),
+                self.fmt_duration(interval.duration()).bold()
+            );
+        });
+    }
+
+    /// Lists work intervals in the order they started, followed by the running timer if it is
+    /// on one of `indices`, and the total time.
+    pub fn list_time_log(
+        &self,
+        graph: &Graph,
+        log: &[(usize, Interval)],
+        indices: Option<&HashSet<usize>>,
+    ) {
+        let running = graph
+            .get_timer()
+            .filter(|timer| indices.is_none_or(|indices| indices.contains(&timer.index)))
+            .map(|timer| (timer.index, timer.until(Local::now().naive_local())));
+        if log.is_empty() && running.is_none() {
+            println!("No time logged.");
+            return;
+        }
+
+        let mut total = TimeDelta::zero();
+        for (index, interval) in log.iter().chain(running.iter()) {
+            let end = if Some((*index, *interval)) == running {
+                "now".to_string()
+            } else if interval.end.date() == interval.start.date() {
+                interval.end.format("%H:%M").to_string()
+            } else {
+                self.fmt_timestamp(&interval.end)
+            };
+            graph.with_node(*index, &mut |node| {
+                println!(
+                    "{} - {} {} {}",
+                    self.fmt_timestamp(&interval.start).dimmed(),
+                    end.dimmed(),
+                    format!("({})", self.fmt_duration(interval.duration())).bold(),
+                    self.fmt_node(graph, node)
+                );
+            });
+            total += interval.duration();
+        }
+        println!("Total: {}", self.fmt_duration(total).bold());
+    }
+
+    /// Lists the problems found in the graph, with whether each was repaired if any repair was
+    /// attempted.
+    pub fn print_problems(&self, problems: &[(Problem, Option<bool>)]) {
+        if problems.is_empty() {
+            println!("{}", "No problems found.".green());
+            return;
+        }
+        for (problem, repaired) in problems {
+            match repaired {
+                Some(true) => println!(" * {problem} {}", "[fixed]".green()),
+                Some(false) => println!(" * {problem} {}", "[not fixed]".red()),
+                None => println!(" * {problem}"),
+            }
+        }
+        let fixed = problems
+            .iter()
+            .filter(|(_, repaired)| *repaired == Some(true))
+            .count();
+        println!(
+            "Found {} problem(s), fixed {}.",
+            problems.len().to_string().bright_blue(),
+            fixed.to_string().bright_blue()
+        );
+    }
+
+    pub fn print_merge(&self, merged: &Merge) {
+        for conflict in &merged.conflicts {
+            println!("{} {conflict}", "Conflict:".red());
+        }
+        for problem in &merged.repaired {
+            println!("{} {problem}", "Repaired:".yellow());
+        }
+        println!(
+            "Merged {} node(s) with {} conflict(s).",
+            merged.graph.node_count().to_string().bright_blue(),
+            merged.conflicts.len().to_string().bright_blue()
+        );
+    }
+
+    pub fn print_journal_steps(&self, labels: &[String], undo: bool) {
+        let verb = if undo { "Undid" } else { "Redid" };
+        for label in labels {
+            println!("{verb}: {}", label.bright_blue());
+        }
+    }
+
+    pub fn print_dependency(&self, index: usize, blocker: usize, connect: bool) {
+        if connect {
+            println!("{index} waits on {}", blocker.to_string().bright_blue());
+        } else {
+            println!(
+                "{index} no longer waits on {}",
+                blocker.to_string().bright_blue()
+            );
+        }
+    }
+
     pub fn print_link(&self, from: usize, to: usize, connect: bool) {
         let from = format!("({from})").bright_blue();
         let to = format!("({to})").bright_blue();
@@ -0,0 +1,75 @@
+//! Editing text through the user's preferred editor.
+
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+use crate::{AppError, AppResult};
+
+/// Returns the editor command to use, in order of preference: `$VISUAL`, `$EDITOR`, then `vi`.
+fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|cmd| !cmd.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// Creates a new temporary file for a note that only the current user can read. The name is
+/// random and the file must not exist yet, so a file or symlink planted at a guessable path
+/// can't be written through.
+fn create_temp_file() -> io::Result<(PathBuf, File)> {
+    loop {
+        let mut path = env::temp_dir();
+        path.push(format!("tuesday-note-{:016x}.md", rand::random::<u64>()));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Opens `initial` in the user's editor on a temporary file and returns the edited text once the
+/// editor exits.
+pub fn edit_text(initial: &str) -> AppResult<String> {
+    let editor = editor_command();
+    // Allow editor commands with arguments, e.g. `code --wait`
+    let mut args = editor.split_whitespace();
+    let program = args.next().expect("editor command should not be empty");
+
+    let (path, mut file) = create_temp_file()?;
+    // Files conventionally end with a newline; editors may otherwise append one themselves
+    let written = if initial.is_empty() {
+        Ok(())
+    } else {
+        writeln!(file, "{initial}")
+    };
+    drop(file);
+    if let Err(err) = written {
+        let _ = fs::remove_file(&path);
+        return Err(err.into());
+    }
+
+    let result = match Command::new(program).args(args).arg(&path).status() {
+        Ok(status) if status.success() => fs::read_to_string(&path).map_err(AppError::from),
+        Ok(status) => Err(AppError::EditorError(format!(
+            "'{editor}' exited with {status}"
+        ))),
+        Err(err) => Err(AppError::EditorError(format!(
+            "failed to run '{editor}': {err}"
+        ))),
+    };
+    let _ = fs::remove_file(&path);
+    result
+}
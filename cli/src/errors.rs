@@ -46,8 +46,14 @@ pub(crate) enum AppError {
     #[error("Configuration error: {0}")]
     ConfigError(#[from] ConfigReadError),
 
+    #[error("Editor error: {0}")]
+    EditorError(String),
+
     #[error("Blueprint error: {0}")]
     BlueprintError(#[from] BlueprintError),
+
+    #[error("{0} conflicting change(s) were resolved in favor of our side, please review them")]
+    MergeConflicts(usize),
 }
 
 // The default Debug implementation displays the enum like so:
@@ -1,14 +1,33 @@
 //! This module contains graph operations that are only used by the CLI front-end.
 
-use std::collections::HashMap;
-
-use crate::blueprints::{BlueprintDoc, BlueprintGraph};
+use crate::blueprints::{BlueprintDoc, BlueprintError};
 use crate::{dates::parse_datetime_extended, AppError, AppResult};
-use tuecore::graph::{node::NodeType, Graph, GraphGetters};
+use tuecore::graph::{
+    errors::ErrorType,
+    node::{Node, NodeType},
+    query::Query,
+    Graph, GraphGetters,
+};
 
 pub trait CLIGraphOps {
+    /// Resolves a node ID, which may be an index, an alias, a path or a date. Bare IDs are
+    /// guessed at, while sigils say what an ID is: `#12` is an index, `:proj` an alias and
+    /// `@tomorrow` a date.
     fn get_index_cli(&self, id: &str, assume_date: bool) -> AppResult<usize>;
 
+    /// Resolves a node ID like [`CLIGraphOps::get_index_cli`], except that a date without a node
+    /// yet gets one inserted.
+    fn get_or_insert_index_cli(&mut self, id: &str, assume_date: bool) -> AppResult<usize>;
+
+    /// Resolves a list of node IDs. Besides IDs, entries may be queries (see
+    /// [`tuecore::graph::query`]) such as `"tag:work state:partial"`, which stand for every node
+    /// they match. Nodes are listed once, in the order they were first given.
+    fn get_indices_cli<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a String>,
+        assume_date: bool,
+    ) -> AppResult<Vec<usize>>;
+
     /// Hard copies a node. The source node will be the child of the target node.
     ///
     /// # Arguments
@@ -19,50 +38,56 @@ pub trait CLIGraphOps {
     /// The index of the newly created node.
     fn copy(&mut self, from: usize, to: usize) -> AppResult<usize>;
 
-    /// Recursively hard copies a node. The source node will be the child of the target node.
-    ///
-    /// # Arguments
-    /// - `from`: source node
-    /// - `to`: target node
+    fn mv(&mut self, from: usize, to: usize) -> AppResult<()>;
+
+    /// Gives a node another alias, taking it from the node that has it if `force` is set. Besides
+    /// what [`Graph::validate_alias`] rejects, aliases that would be read as a date are rejected.
+    fn set_alias_cli(&mut self, index: usize, alias: &str, force: bool) -> AppResult<()>;
+
+    /// Renames an alias, see [`Graph::rename_alias`]. The new alias is checked like in
+    /// [`CLIGraphOps::set_alias_cli`].
     ///
     /// # Returns
-    /// The index of the newly created node.
-    fn copy_recurse(&mut self, from: usize, to: usize) -> AppResult<()>;
+    /// The index of the node with the alias.
+    fn rename_alias_cli(&mut self, old: &str, new: &str, force: bool) -> AppResult<usize>;
 
-    fn mv(&mut self, from: usize, to: usize) -> AppResult<()>;
+    /// Copies the attributes of a node (its note, tags, and for tasks everything except their
+    /// state) onto another node, keeping the graph's indices in sync.
+    fn copy_attributes(&mut self, source: &Node, to: usize) -> AppResult<()>;
 
-    fn insert_blueprint_recurse(
+    /// Inserts a copy of a blueprint's tree.
+    ///
+    /// # Arguments
+    /// - `parent`: node to insert the tree under, or `None` to insert it as a root
+    /// - `title`: title for the top node, defaulting to the blueprint's own
+    /// - `aliases`: whether to keep the blueprint's aliases that are not taken yet
+    ///
+    /// # Returns
+    /// The index of the top node of the inserted tree.
+    fn insert_blueprint(
         &mut self,
-        map: &HashMap<usize, usize>,
         blueprint: &BlueprintDoc,
-        blueprint_from: usize,
-        node_parent: usize,
+        parent: Option<usize>,
+        title: Option<&str>,
+        aliases: bool,
     ) -> AppResult<usize>;
-
-    fn _insert_blueprint_recurse(
-        &mut self,
-        map: &HashMap<usize, usize>,
-        blueprint: &BlueprintDoc,
-        blueprint_from: usize,
-        node_parent: usize,
-    ) -> AppResult<()>;
-
-    fn update_node_metadata_on_blueprint(
-        &mut self,
-        parent: usize,
-        map: &HashMap<usize, usize>,
-        blueprint: &BlueprintDoc,
-    );
 }
 
 impl CLIGraphOps for Graph {
     fn get_index_cli(&self, id: &str, assume_date: bool) -> AppResult<usize> {
-        // When user forces the ID to be interpreted as a date, just search through the dates hashmap.
-        if assume_date {
-            let date = parse_datetime_extended(id)?.date_naive();
+        // When the ID is marked (or the user forces it) to be a date, just search through the dates
+        // hashmap.
+        let date = id.strip_prefix('@').or(assume_date.then_some(id));
+        if let Some(date) = date {
+            let date = parse_datetime_extended(date)?.date_naive();
             return Ok(self.get_date_index(&date)?);
         }
 
+        // Indices and aliases with a sigil are never mistaken for anything else
+        if id.starts_with(['#', ':']) {
+            return Ok(self.get_index(id)?);
+        }
+
         // Normally, any number below the amount of dates from the current month can also be
         // interpreted as a date. However, when the user is just writing arbitrary number, it's most
         // likely that they're working with node indices. With this assumption, we parse any valid
@@ -73,9 +98,12 @@ impl CLIGraphOps for Graph {
             return Ok(self.get_index(id)?);
         }
 
-        // The second priority to our ID matching are aliases.
-        if let Ok(idx) = self.get_index(id) {
-            return Ok(idx);
+        // The second priority to our ID matching are aliases and paths.
+        let found = self.get_index(id);
+        match found {
+            Ok(idx) => return Ok(idx),
+            Err(err @ ErrorType::AmbiguousPath(..)) => return Err(err.into()),
+            _ => {}
         }
 
         // If none of those worked, then interpret the ID as a date.
@@ -84,147 +112,225 @@ impl CLIGraphOps for Graph {
             return Ok(idx);
         }
 
+        // Paths that lead nowhere tell where they stopped
+        if let Err(err @ (ErrorType::PathNotFound(_) | ErrorType::UnknownAlias(_))) = found {
+            return Err(err.into());
+        }
+
         // If that didn't work as well then the ID is invalid.
         Err(AppError::IndexRetrievalError(
             "Failed to match index with node".to_string(),
         ))
     }
 
+    fn get_or_insert_index_cli(&mut self, id: &str, assume_date: bool) -> AppResult<usize> {
+        match self.get_index_cli(id, assume_date) {
+            // The ID was read as a date, which has no node yet
+            Err(AppError::GraphError(ErrorType::DateNodeIndexRetrievalError(_))) => {
+                let date = parse_datetime_extended(id.strip_prefix('@').unwrap_or(id))?;
+                Ok(self.get_or_insert_date(date.date_naive()))
+            }
+            result => result,
+        }
+    }
+
+    fn get_indices_cli<'a>(
+        &self,
+        ids: impl IntoIterator<Item = &'a String>,
+        assume_date: bool,
+    ) -> AppResult<Vec<usize>> {
+        let mut indices = vec![];
+        for id in ids {
+            // Only entries with a field condition count as queries, so that aliases and dates are
+            // never mistaken for title searches
+            let query = match id.parse::<Query>() {
+                Ok(query) if !assume_date && !self.get_aliases().contains_key(id) => {
+                    Some(query).filter(Query::has_fields)
+                }
+                _ => None,
+            };
+            let found = match query {
+                Some(query) => query.eval(self)?,
+                None => vec![self.get_index_cli(id, assume_date)?],
+            };
+            for index in found {
+                if !indices.contains(&index) {
+                    indices.push(index);
+                }
+            }
+        }
+        Ok(indices)
+    }
+
     fn copy(&mut self, from: usize, to: usize) -> AppResult<usize> {
         // TODO: this requires copying `Node`s! Would it be better if we can get whichever field we
         // need instead, like in core/graph where node accesses are done using its index from
         // the nodes vector directly? (the nodes vector is private so we can't use it here)
         let source_node = self.get_node(from);
 
-        let new_node = self.insert_child(source_node.title, to, source_node.data.is_pseudo())?;
+        let new_node =
+            self.insert_child(source_node.title.clone(), to, source_node.data.is_pseudo())?;
 
-        if let NodeType::Task(data) = source_node.data {
+        if let NodeType::Task(ref data) = source_node.data {
             self.set_task_state(new_node, data.state, true)?;
         };
+        self.copy_attributes(&source_node, new_node)?;
 
         Ok(new_node)
     }
 
-    fn copy_recurse(&mut self, from: usize, to: usize) -> AppResult<()> {
-        let new = self.copy(from, to)?;
-
-        // this function will stop recursing when there's no children left.
-        for child in self.get_node(from).metadata.children {
-            self.copy_recurse(child, new)?
-        }
-
-        Ok(())
-    }
-
     fn mv(&mut self, from: usize, to: usize) -> AppResult<()> {
+        if let Some(path) = self.link_cycle(to, from) {
+            return Err(ErrorType::LinkCycle(path).into());
+        }
         self.clean_parents(from)?;
         self.link(to, from)?;
         Ok(())
     }
 
-    fn _insert_blueprint_recurse(
-        &mut self,
-        map: &HashMap<usize, usize>,
-        blueprint: &BlueprintDoc,
-        blueprint_from: usize,
-        node_parent: usize,
-    ) -> AppResult<()> {
-        let node = &blueprint.graph.nodes[blueprint_from];
-
-        let children = node.metadata.children.clone();
-
-        if self.get_node_checked(map[&blueprint_from]).is_none() {
-            let new_id =
-                self.insert_child(node.title.clone(), node_parent, node.data.is_pseudo())?;
-
-            for child in children {
-                self._insert_blueprint_recurse(map, blueprint, child, new_id)?;
-            }
-        };
+    fn set_alias_cli(&mut self, index: usize, alias: &str, force: bool) -> AppResult<()> {
+        check_alias_cli(alias)?;
+        if force {
+            self.steal_alias(index, alias.to_string())?;
+        } else {
+            self.set_alias(index, alias.to_string())?;
+        }
         Ok(())
     }
 
-    /// Note: Also call `self.update_node_metadata` at the end to properly update the indices
-    /// inside the newly created nodes' metadata.
-    fn insert_blueprint_recurse(
-        &mut self,
-        map: &HashMap<usize, usize>,
-        blueprint: &BlueprintDoc,
-        blueprint_from: usize,
-        node_parent: usize,
-    ) -> AppResult<usize> {
-        let new_id = self.get_nodes().len();
-
-        self._insert_blueprint_recurse(map, blueprint, blueprint_from, node_parent)?;
+    fn rename_alias_cli(&mut self, old: &str, new: &str, force: bool) -> AppResult<usize> {
+        check_alias_cli(new)?;
+        Ok(self.rename_alias(old, new.to_string(), force)?)
+    }
 
-        Ok(new_id)
+    fn copy_attributes(&mut self, source: &Node, to: usize) -> AppResult<()> {
+        if let Some(data) = source.data.as_task() {
+            self.set_task_due(to, data.due)?;
+            self.set_task_priority(to, data.priority)?;
+        }
+        for tag in &source.metadata.tags {
+            self.add_tag(to, tag)?;
+        }
+        self.set_note(to, source.note.clone())?;
+        Ok(())
     }
 
-    fn update_node_metadata_on_blueprint(
+    fn insert_blueprint(
         &mut self,
-        parent: usize,
-        map: &HashMap<usize, usize>,
         blueprint: &BlueprintDoc,
-    ) {
-        // TODO: inconsistent with main's behavior
-        for node in &blueprint.graph.nodes {
-            // except the parent
-            let mut mut_node = self.get_node_mut(map[&node.metadata.index]);
-            if node.metadata.index != parent {
-                mut_node.metadata.parents = node.metadata.parents.iter().map(|i| map[i]).collect();
+        parent: Option<usize>,
+        title: Option<&str>,
+        aliases: bool,
+    ) -> AppResult<usize> {
+        self.transaction(|graph| {
+            let top = graph
+                .graft(&blueprint.graph, parent, aliases)
+                .map_err(|err| match err {
+                    ErrorType::SubgraphCycle(path) => BlueprintError::Cycle(path).into(),
+                    err => AppError::from(err),
+                })?;
+            if let Some(title) = title {
+                graph.rename_node(top, title.to_string())?;
             }
-            mut_node.metadata.children = node.metadata.children.iter().map(|i| map[i]).collect();
-        }
+            Ok(top)
+        })
     }
 }
 
-// same algo as core/graph.rs for remapping indices except we use hashmaps here since the remapped
-// indices count is almost definitely gonna be smaller
-fn _insert_idx_recurse(
-    blueprint_graph: &BlueprintGraph,
-    graph: &Graph,
-    map: &mut HashMap<usize, usize>,
-    node: usize,
-    current_idx: &mut usize,
-) {
-    if map.get(&node).is_none() {
-        map.insert(node, *current_idx);
-        *current_idx += 1;
-        true
-    } else {
-        false
-    };
-
-    let node_children = &blueprint_graph.nodes[node].metadata.children;
-    for child in node_children {
-        _insert_idx_recurse(blueprint_graph, graph, map, *child, current_idx);
+/// Rejects aliases that [`CLIGraphOps::get_index_cli`] would read as a date, which the core's
+/// checks don't cover as they don't know about every date format the CLI accepts
+fn check_alias_cli(alias: &str) -> AppResult<()> {
+    if parse_datetime_extended(alias).is_ok() {
+        return Err(ErrorType::InvalidAlias(alias.to_string()).into());
     }
-}
-
-pub fn new_graph_indices_map(
-    blueprint: &BlueprintDoc,
-    graph: &Graph,
-    new_index: usize,
-) -> HashMap<usize, usize> {
-    let mut map = HashMap::new();
-    _insert_idx_recurse(
-        &blueprint.graph,
-        graph,
-        &mut map,
-        blueprint.parent,
-        &mut new_index.clone(),
-    );
-    map
+    Ok(())
 }
 
 pub fn graph_from_blueprint(blueprint: &BlueprintDoc) -> AppResult<Graph> {
     let mut graph = Graph::new();
-    let map = new_graph_indices_map(blueprint, &graph, 0);
-    let new_parent = &blueprint.graph.nodes[blueprint.parent];
-    let parent_idx = graph.insert_root(new_parent.title.clone(), new_parent.data.is_pseudo());
-    for child in &new_parent.metadata.children {
-        graph.insert_blueprint_recurse(&map, blueprint, *child, parent_idx)?;
-    }
-    graph.update_node_metadata_on_blueprint(blueprint.parent, &map, blueprint);
+    graph.insert_blueprint(blueprint, None, None, true)?;
     Ok(graph)
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use tuecore::graph::errors::ErrorType;
+    use tuecore::graph::{Graph, GraphGetters};
+
+    use super::CLIGraphOps;
+    use crate::AppError;
+
+    #[test]
+    fn sigils_say_what_an_id_is() {
+        let mut graph = Graph::new();
+        let day = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let date = graph.insert_date(String::new(), day);
+        let proj = graph.insert_root("proj".to_string(), false);
+        let child = graph.insert_child("12".to_string(), proj, false).unwrap();
+        graph.set_alias(proj, "proj".to_string()).unwrap();
+
+        assert_eq!(graph.get_index_cli("@2025-03-05", false).unwrap(), date);
+        assert_eq!(graph.get_index_cli("2025-03-05", true).unwrap(), date);
+        assert_eq!(
+            graph.get_index_cli(&format!("#{child}"), false).unwrap(),
+            child
+        );
+        assert_eq!(graph.get_index_cli(":proj", false).unwrap(), proj);
+        assert_eq!(graph.get_index_cli(":proj/12", false).unwrap(), child);
+
+        // Sigils are never read as anything else
+        assert!(matches!(
+            graph.get_index_cli("@proj", false),
+            Err(AppError::DateParseError(_))
+        ));
+        assert!(matches!(
+            graph.get_index_cli(":2025-03-05", false),
+            Err(AppError::GraphError(ErrorType::UnknownAlias(_)))
+        ));
+
+        // Dates without a node are only inserted when asked to
+        assert!(matches!(
+            graph.get_index_cli("@2025-03-06", false),
+            Err(AppError::GraphError(
+                ErrorType::DateNodeIndexRetrievalError(_)
+            ))
+        ));
+        let next = graph.get_or_insert_index_cli("@2025-03-06", false).unwrap();
+        assert_eq!(
+            graph.get_node(next).data.as_date().unwrap().date,
+            day.succ_opt().unwrap()
+        );
+        assert_eq!(
+            graph.get_or_insert_index_cli("@2025-03-06", false).unwrap(),
+            next
+        );
+        assert_eq!(graph.get_or_insert_index_cli(":proj", false).unwrap(), proj);
+    }
+
+    #[test]
+    fn mv_rejects_moving_into_descendants() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        let grandchild = graph
+            .insert_child("grandchild".to_string(), child, false)
+            .unwrap();
+
+        for (from, to) in [(root, grandchild), (child, child)] {
+            assert!(matches!(
+                graph.mv(from, to),
+                Err(AppError::GraphError(ErrorType::LinkCycle(_)))
+            ));
+        }
+        // The node keeps its place
+        assert_eq!(graph.get_roots(), [root]);
+        assert_eq!(graph.get_node(child).metadata.parents, [root]);
+
+        graph.mv(grandchild, root).unwrap();
+        assert_eq!(graph.get_node_children(root), [child, grandchild]);
+        assert!(graph.get_node_children(child).is_empty());
+    }
+}
@@ -2,6 +2,7 @@ mod blueprints;
 mod config;
 mod dates;
 mod display;
+mod editor;
 mod errors;
 mod graph;
 mod paths;
@@ -13,18 +14,25 @@ use std::path::PathBuf;
 use blueprints::{
     get_blueprints_listing, try_get_blueprint_from_save_dir, BlueprintDoc, BlueprintError,
 };
-use chrono::Local;
+use chrono::{Datelike, Local};
 use clap::{arg, value_parser, Arg, ArgMatches, Command};
 
 use config::{get_config, CliConfig};
-use dates::parse_datetime_extended;
+use dates::{parse_datetime_extended, parse_due};
 use display::Displayer;
+use editor::edit_text;
 use errors::AppError;
-use graph::{graph_from_blueprint, new_graph_indices_map, CLIGraphOps};
+use graph::{graph_from_blueprint, CLIGraphOps};
 use rand::rng;
 use rand::seq::IndexedRandom;
+use tuecore::doc::merge::merge;
 use tuecore::doc::{self, get_doc_ver, Doc};
-use tuecore::graph::node::task::TaskState;
+use tuecore::graph::filter::TraverseFilter;
+use tuecore::graph::journal::Journal;
+use tuecore::graph::node::task::{Priority, Rollup, TaskState};
+use tuecore::graph::query::Query;
+use tuecore::graph::recur::{RecurRule, RecurTarget, Recurrence};
+use tuecore::graph::validate::ProblemKind;
 use tuecore::graph::{Graph, GraphGetters};
 
 type AppResult<T> = Result<T, AppError>;
@@ -35,6 +43,135 @@ fn get_bp_path(save_dir: PathBuf, name: &str) -> PathBuf {
     path
 }
 
+/// Opens a blueprint by path, or by name from the save directory.
+fn open_blueprint(config: &CliConfig, name: &str) -> AppResult<BlueprintDoc> {
+    let path = if PathBuf::from(name).exists() {
+        PathBuf::from(name)
+    } else {
+        get_bp_path(config.blueprints.store_path.clone(), name)
+    };
+
+    Ok(blueprints::get_doc(&mut File::open(&path)?).map_err(|_| {
+        BlueprintError::FailedToAccess("Failed to match to any existing blueprint!".to_string())
+    })?)
+}
+
+/// Inserts recurring blueprints when materializing recurrences.
+fn insert_recurring_blueprint(
+    graph: &mut Graph,
+    config: &CliConfig,
+    name: &str,
+    parent: usize,
+) -> AppResult<bool> {
+    let bp = open_blueprint(config, name)?;
+    graph.insert_blueprint(&bp, Some(parent), None, false)?;
+    Ok(true)
+}
+
+/// Materializes the recurrences pending on the date nodes about to be listed, see
+/// [`Graph::view_dates`].
+fn view_dates(
+    graph: &mut Graph,
+    config: &CliConfig,
+    indices: &[usize],
+    depth: u32,
+) -> AppResult<()> {
+    graph.view_dates(indices, depth, &mut |graph, name, parent| {
+        insert_recurring_blueprint(graph, config, name, parent)
+    })?;
+    Ok(())
+}
+
+/// Materializes the recurrences pending on the date that `id` refers to, if it refers to a date
+/// that has no node yet. The node is inserted if anything is pending; existing date nodes are
+/// handled by [`view_dates`].
+fn materialize_viewed_date(
+    graph: &mut Graph,
+    config: &CliConfig,
+    id: &str,
+    assume_date: bool,
+) -> AppResult<()> {
+    let date = match graph.get_index_cli(id, assume_date) {
+        Ok(_) => return Ok(()),
+        // Node indices never refer to dates that do not exist yet
+        Err(_) if !assume_date && id.parse::<u64>().is_ok() => return Ok(()),
+        Err(_) => match parse_datetime_extended(id.strip_prefix('@').unwrap_or(id)) {
+            Ok(date) => date.date_naive(),
+            Err(_) => return Ok(()),
+        },
+    };
+    graph.materialize_recurrences(date, &mut |graph, name, parent| {
+        insert_recurring_blueprint(graph, config, name, parent)
+    })?;
+    Ok(())
+}
+
+fn handle_recur_command(
+    subcommand: Option<(&str, &ArgMatches)>,
+    graph: &mut Graph,
+    config: &CliConfig,
+    displayer: &Displayer,
+) -> AppResult<()> {
+    match subcommand {
+        Some(("add", sub_matches)) => {
+            let rule = sub_matches
+                .get_one::<String>("rule")
+                .ok_or(AppError::InvalidSubcommand)?
+                .parse::<RecurRule>()?;
+            let target = match (
+                sub_matches.get_one::<String>("message"),
+                sub_matches.get_one::<String>("blueprint"),
+            ) {
+                (_, Some(name)) => RecurTarget::Blueprint(name.clone()),
+                (Some(title), None) => RecurTarget::Task(title.clone()),
+                (None, None) => return Err(AppError::MissingArgument("message".to_string())),
+            };
+            let start = parse_datetime_extended(
+                sub_matches
+                    .get_one::<String>("start")
+                    .expect("start should have a default value"),
+            )?
+            .date_naive();
+
+            let idx = graph.add_recurrence(Recurrence::new(rule, target, start));
+            displayer.display_recurrence(idx, &graph.get_recurrences()[idx]);
+        }
+        Some(("ls", _)) => {
+            displayer.list_recurrences(graph.get_recurrences());
+        }
+        Some(("rm", sub_matches)) => {
+            let mut indices = sub_matches
+                .get_many::<usize>("index")
+                .ok_or(AppError::InvalidSubcommand)?
+                .copied()
+                .collect::<Vec<_>>();
+            // Remove from the back so the remaining indices stay valid
+            indices.sort_unstable();
+            indices.dedup();
+            graph.transaction(|graph| -> AppResult<()> {
+                for idx in indices.into_iter().rev() {
+                    graph.remove_recurrence(idx)?;
+                }
+                Ok(())
+            })?;
+        }
+        Some(("sync", sub_matches)) => {
+            let until = parse_datetime_extended(
+                sub_matches
+                    .get_one::<String>("until")
+                    .expect("until should have a default value"),
+            )?
+            .date_naive();
+            let count = graph.sync_recurrences(until, &mut |graph, name, parent| {
+                insert_recurring_blueprint(graph, config, name, parent)
+            })?;
+            displayer.display_recur_synced(count);
+        }
+        _ => return Err(AppError::InvalidSubcommand),
+    };
+    Ok(())
+}
+
 fn handle_blueprints_command(
     subcommand: Option<(&str, &ArgMatches)>,
     graph: &mut Graph,
@@ -102,7 +239,7 @@ fn handle_blueprints_command(
             let graph = graph_from_blueprint(&bp)?;
 
             println!("{}", displayer.display_bp_title(bp.author.as_deref(), name));
-            displayer.list_roots(&graph, 0, false)?;
+            displayer.list_roots(&graph, 0, false, &TraverseFilter::default())?;
         }
         Some(("ls", _)) => {
             let bps = get_blueprints_listing(&config.blueprints.store_path)?;
@@ -129,47 +266,22 @@ fn handle_blueprints_command(
             let name = sub_matches
                 .get_one::<String>("name")
                 .ok_or(AppError::InvalidSubcommand)?;
-            let title = sub_matches.get_one::<String>("title");
+            let title = sub_matches.get_one::<String>("message");
             let id = sub_matches.get_one::<String>("ID");
             let root = sub_matches.get_flag("root");
             let assumedate = sub_matches.get_flag("assumedate");
+            let aliases = sub_matches.get_flag("aliases");
 
-            let path = if PathBuf::from(name).exists() {
-                PathBuf::from(name)
-            } else {
-                get_bp_path(config.blueprints.store_path.clone(), name)
-            };
-
-            let bp = blueprints::get_doc(&mut File::open(&path)?).map_err(|_| {
-                BlueprintError::FailedToAccess(
-                    "Failed to match to any existing blueprint!".to_string(),
-                )
-            })?;
+            let bp = open_blueprint(config, name)?;
 
-            let map = new_graph_indices_map(&bp, graph, graph.get_nodes().len());
-
-            // TODO: send help
-            let new_parent = &bp.graph.nodes[bp.parent];
-            let parent_id = if root {
-                graph.insert_root(
-                    title.unwrap_or(&new_parent.title).to_string(),
-                    new_parent.data.is_pseudo(),
-                )
+            let parent = if root {
+                None
             } else {
                 // id shouldn't be None here since !root implies id being Some(..)
-                let id = graph.get_index_cli(id.unwrap(), assumedate)?;
-                graph.insert_child(
-                    title.unwrap_or(&new_parent.title).to_string(),
-                    id,
-                    new_parent.data.is_pseudo(),
-                )?
+                Some(graph.get_index_cli(id.unwrap(), assumedate)?)
             };
-
-            for child in &new_parent.metadata.children {
-                graph.insert_blueprint_recurse(&map, &bp, *child, parent_id)?;
-            }
-
-            graph.update_node_metadata_on_blueprint(bp.parent, &map, &bp);
+            let parent_id =
+                graph.insert_blueprint(&bp, parent, title.map(|s| s.as_str()), aliases)?;
 
             if config.display.show_connections {
                 displayer.display_bp_inserted(name, parent_id);
@@ -243,6 +355,14 @@ fn handle_graph_command(
             let root = sub_matches.get_flag("root");
             let date = sub_matches.get_one::<String>("date");
             let pseudo = sub_matches.get_flag("pseudo");
+            let priority = sub_matches.get_one::<Priority>("priority").copied();
+            let due = match sub_matches.get_one::<String>("due") {
+                Some(due) => Some(parse_due(
+                    due,
+                    sub_matches.get_one::<String>("at").map(String::as_str),
+                )?),
+                None => None,
+            };
 
             if (root || date.is_some()) && is_bp_graph {
                 return Err(AppError::InvalidArg(
@@ -263,6 +383,12 @@ fn handle_graph_command(
                             "adding root node requires message to be given".to_string(),
                         ))?;
                 let idx = graph.insert_root(message.to_string(), pseudo);
+                if due.is_some() {
+                    graph.set_task_due(idx, due)?;
+                }
+                if let Some(priority) = priority {
+                    graph.set_task_priority(idx, priority)?;
+                }
 
                 if config.display.show_connections {
                     displayer.print_link_root(idx, true);
@@ -289,8 +415,15 @@ fn handle_graph_command(
                 } else {
                     return Err(AppError::InvalidArg("Parent ID required!".to_string()));
                 };
-                let parent = graph.get_index_cli(idx, false)?;
+                // A date without a node yet gets one, e.g. `add standup @tomorrow`
+                let parent = graph.get_or_insert_index_cli(idx, false)?;
                 let to = graph.insert_child(message.to_string(), parent, pseudo)?;
+                if due.is_some() {
+                    graph.set_task_due(to, due)?;
+                }
+                if let Some(priority) = priority {
+                    graph.set_task_priority(to, priority)?;
+                }
 
                 if config.display.show_connections {
                     displayer.print_link(to, parent, true);
@@ -300,23 +433,33 @@ fn handle_graph_command(
         Some(("rm", sub_matches)) => {
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
             let assume_date = sub_matches.get_flag("assumedate");
-            for id in ids {
-                let recursive = sub_matches.get_flag("recursive");
-                let node_id = graph.get_index_cli(id, assume_date)?;
-
-                // do not remove the root of a blueprint
-                if node_id == 0 && is_bp_graph {
-                    return Err(AppError::InvalidArg(
-                        "Cannot remove parent from blueprint!".to_string(),
-                    ));
-                }
+            let recursive = sub_matches.get_flag("recursive");
+            let removed = graph.transaction(|graph| -> AppResult<_> {
+                let mut removed = vec![];
+                for node_id in graph.get_indices_cli(ids, assume_date)? {
+                    // Already removed along with an earlier node
+                    if !graph.node_at_exists(node_id) {
+                        continue;
+                    }
 
-                if recursive {
-                    graph.remove_children_recursive(node_id)?;
-                } else {
-                    graph.remove(node_id)?;
+                    // do not remove the root of a blueprint
+                    if node_id == 0 && is_bp_graph {
+                        return Err(AppError::InvalidArg(
+                            "Cannot remove parent from blueprint!".to_string(),
+                        ));
+                    }
+
+                    if recursive {
+                        graph.remove_children_recursive(node_id)?;
+                    } else {
+                        graph.remove(node_id)?;
+                    }
+                    removed.push(node_id);
                 }
-                if config.display.show_connections {
+                Ok(removed)
+            })?;
+            if config.display.show_connections {
+                for node_id in removed {
                     displayer.print_removal(node_id, recursive);
                 }
             }
@@ -333,17 +476,54 @@ fn handle_graph_command(
             let ids = sub_matches
                 .get_many::<String>("child")
                 .expect("Node children required");
-            for id in ids {
-                let node_id = graph.get_index_cli(id, assume_date)?;
-
-                graph.link(parent, node_id)?;
-
-                if config.display.show_connections {
+            let children = graph.transaction(|graph| -> AppResult<_> {
+                let children = graph.get_indices_cli(ids, assume_date)?;
+                for &node_id in &children {
+                    graph.link(parent, node_id)?;
+                }
+                Ok(children)
+            })?;
+            if config.display.show_connections {
+                for node_id in children {
                     displayer.print_link(node_id, parent, true);
                 }
             }
         }
 
+        Some(("dep", sub_matches)) => {
+            let (connect, sub_matches) = match sub_matches.subcommand() {
+                Some(("add", sub_matches)) => (true, sub_matches),
+                Some(("rm", sub_matches)) => (false, sub_matches),
+                _ => return Err(AppError::InvalidSubcommand),
+            };
+            let assume_date = sub_matches.get_flag("assumedate");
+            let node_id = graph.get_index_cli(
+                sub_matches
+                    .get_one::<String>("ID")
+                    .expect("ID should be required"),
+                assume_date,
+            )?;
+            let ids = sub_matches
+                .get_many::<String>("blocker")
+                .expect("blockers should be required");
+            let blockers = graph.transaction(|graph| -> AppResult<_> {
+                let blockers = graph.get_indices_cli(ids, assume_date)?;
+                for &blocker in &blockers {
+                    if connect {
+                        graph.add_dependency(node_id, blocker)?;
+                    } else {
+                        graph.remove_dependency(node_id, blocker)?;
+                    }
+                }
+                Ok(blockers)
+            })?;
+            if config.display.show_connections {
+                for blocker in blockers {
+                    displayer.print_dependency(node_id, blocker, connect);
+                }
+            }
+        }
+
         Some(("unlink", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let parent = graph.get_index_cli(
@@ -355,12 +535,15 @@ fn handle_graph_command(
             let ids = sub_matches
                 .get_many::<String>("child")
                 .expect("Node children required");
-            for id in ids {
-                let node_id = graph.get_index_cli(id, assume_date)?;
-
-                graph.unlink(parent, node_id)?;
-
-                if config.display.show_connections {
+            let children = graph.transaction(|graph| -> AppResult<_> {
+                let children = graph.get_indices_cli(ids, assume_date)?;
+                for &node_id in &children {
+                    graph.unlink(parent, node_id)?;
+                }
+                Ok(children)
+            })?;
+            if config.display.show_connections {
+                for node_id in children {
                     displayer.print_link(node_id, parent, false);
                 }
             }
@@ -379,10 +562,15 @@ fn handle_graph_command(
                 assume_date_2,
             )?;
 
-            for node in nodes {
-                let node = graph.get_index_cli(node, assume_date_1)?;
-                graph.mv(node, parent)?;
-                if config.display.show_connections {
+            let moved = graph.transaction(|graph| -> AppResult<_> {
+                let moved = graph.get_indices_cli(nodes, assume_date_1)?;
+                for &node in &moved {
+                    graph.mv(node, parent)?;
+                }
+                Ok(moved)
+            })?;
+            if config.display.show_connections {
+                for node in moved {
                     displayer.print_link(node, parent, true);
                 }
             }
@@ -396,7 +584,88 @@ fn handle_graph_command(
             let state = sub_matches
                 .get_one::<TaskState>("state")
                 .expect("node state required");
-            graph.set_task_state(id, *state, true)?;
+            let propagate = !sub_matches.get_flag("no_propagate");
+            graph.set_task_state(id, *state, propagate)?;
+        }
+        Some(("set-due", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            let due = parse_due(
+                sub_matches
+                    .get_one::<String>("due")
+                    .expect("due date required"),
+                sub_matches.get_one::<String>("at").map(String::as_str),
+            )?;
+            graph.set_task_due(id, Some(due))?;
+        }
+        Some(("set-priority", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            let priority = sub_matches
+                .get_one::<Priority>("priority")
+                .expect("priority required");
+            graph.set_task_priority(id, *priority)?;
+        }
+        Some(("set-estimate", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            let estimate = sub_matches
+                .get_one::<u32>("estimate")
+                .expect("estimate required");
+            graph.set_task_estimate(id, Some(*estimate))?;
+        }
+        Some(("unset-estimate", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let ids = sub_matches.get_many::<String>("ID").expect("ID required");
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_task_estimate(id, None)?;
+                }
+                Ok(())
+            })?;
+        }
+        Some(("policy", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let reset = sub_matches.get_flag("reset");
+            let rollup = sub_matches.get_one::<Rollup>("rollup");
+            let cascade = sub_matches.get_one::<bool>("cascade");
+            let ids = sub_matches.get_many::<String>("ID").expect("ID required");
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    if reset {
+                        graph.set_node_propagation(id, None)?;
+                        continue;
+                    }
+                    let mut propagation = graph.propagation_of(id);
+                    if let Some(rollup) = rollup {
+                        propagation.rollup = *rollup;
+                    }
+                    if let Some(cascade) = cascade {
+                        propagation.cascade = *cascade;
+                    }
+                    graph.set_node_propagation(id, Some(propagation))?;
+                }
+                Ok(())
+            })?;
+        }
+        Some(("unset-due", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let ids = sub_matches.get_many::<String>("ID").expect("ID required");
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_task_due(id, None)?;
+                }
+                Ok(())
+            })?;
         }
         Some(("check", sub_matches)) => {
             if is_bp_graph {
@@ -405,11 +674,14 @@ fn handle_graph_command(
                 ));
             }
             let assume_date = sub_matches.get_flag("assumedate");
+            let propagate = !sub_matches.get_flag("no_propagate");
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
-            for id in ids {
-                let id = graph.get_index_cli(id, assume_date)?;
-                graph.set_task_state(id, TaskState::Done, true)?;
-            }
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_task_state(id, TaskState::Done, propagate)?;
+                }
+                Ok(())
+            })?;
         }
         Some(("uncheck", sub_matches)) => {
             if is_bp_graph {
@@ -418,27 +690,34 @@ fn handle_graph_command(
                 ));
             }
             let assume_date = sub_matches.get_flag("assumedate");
+            let propagate = !sub_matches.get_flag("no_propagate");
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
-            for id in ids {
-                let id = graph.get_index_cli(id, assume_date)?;
-                graph.set_task_state(id, TaskState::None, true)?;
-            }
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_task_state(id, TaskState::None, propagate)?;
+                }
+                Ok(())
+            })?;
         }
         Some(("arc", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
-            for id in ids {
-                let id = graph.get_index_cli(id, assume_date)?;
-                graph.set_archived(id, true)?;
-            }
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_archived(id, true)?;
+                }
+                Ok(())
+            })?;
         }
         Some(("unarc", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
-            for id in ids {
-                let id = graph.get_index_cli(id, assume_date)?;
-                graph.set_archived(id, false)?;
-            }
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in graph.get_indices_cli(ids, assume_date)? {
+                    graph.set_archived(id, false)?;
+                }
+                Ok(())
+            })?;
         }
         Some(("alias", sub_matches)) => {
             if is_bp_graph {
@@ -446,23 +725,44 @@ fn handle_graph_command(
                     "Aliases are not supported in blueprints!".to_string(),
                 ));
             }
-            let assume_date = sub_matches.get_flag("assumedate");
-            let id = graph.get_index_cli(
-                sub_matches.get_one::<String>("ID").expect("ID required"),
-                assume_date,
-            )?;
-            let alias = sub_matches
-                .get_one::<String>("alias")
-                .expect("alias required");
-            graph.set_alias(id, alias.clone())?;
+            let force = sub_matches.get_flag("force");
+            if let Some(mut names) = sub_matches.get_many::<String>("rename") {
+                let (old, new) = (names.next().unwrap(), names.next().unwrap());
+                graph.rename_alias_cli(old, new, force)?;
+            } else {
+                let assume_date = sub_matches.get_flag("assumedate");
+                let id = graph.get_index_cli(
+                    sub_matches.get_one::<String>("ID").expect("ID required"),
+                    assume_date,
+                )?;
+                let aliases = sub_matches
+                    .get_many::<String>("alias")
+                    .expect("alias required");
+                graph.transaction(|graph| -> AppResult<()> {
+                    for alias in aliases {
+                        graph.set_alias_cli(id, alias, force)?;
+                    }
+                    Ok(())
+                })?;
+            }
         }
         Some(("unalias", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let ids = sub_matches.get_many::<String>("ID").expect("ID required");
-            for id in ids {
-                let id = graph.get_index_cli(id, assume_date)?;
-                graph.unset_alias(id)?;
-            }
+            graph.transaction(|graph| -> AppResult<()> {
+                for id in ids {
+                    // An alias only loses itself, any other ID loses every alias
+                    match graph.get_aliases().get(id) {
+                        Some(&index) if !assume_date => graph.unset_alias(index, id)?,
+                        _ => {
+                            for index in graph.get_indices_cli([id], assume_date)? {
+                                graph.clear_aliases(index)?;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            })?;
         }
         Some(("aliases", _)) => {
             let aliases = graph.get_aliases();
@@ -475,6 +775,49 @@ fn handle_graph_command(
                 println!("No added alias.");
             }
         }
+        Some(("tag", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            let tags = sub_matches.get_many::<String>("tag").expect("tag required");
+            graph.transaction(|graph| -> AppResult<()> {
+                for tag in tags {
+                    graph.add_tag(id, tag)?;
+                }
+                Ok(())
+            })?;
+        }
+        Some(("untag", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            match sub_matches.get_many::<String>("tag") {
+                Some(tags) => graph.transaction(|graph| -> AppResult<()> {
+                    for tag in tags {
+                        graph.remove_tag(id, tag)?;
+                    }
+                    Ok(())
+                })?,
+                None => graph.clear_tags(id)?,
+            }
+        }
+        Some(("tags", _)) => {
+            let tags = graph.get_tags();
+            println!("{}", displayer.tags_title());
+            if !tags.is_empty() {
+                let mut tags: Vec<_> = tags.iter().collect();
+                tags.sort_by(|a, b| a.0.cmp(b.0));
+                for (tag, indices) in tags {
+                    println!(" * {} ({})", displayer.display_tag(tag), indices.len());
+                }
+            } else {
+                println!("No added tag.");
+            }
+        }
         Some(("rename", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let id = graph.get_index_cli(
@@ -486,6 +829,22 @@ fn handle_graph_command(
                 .expect("ID required");
             graph.rename_node(id, message.to_string())?;
         }
+        Some(("note", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            let note = if sub_matches.get_flag("clear") {
+                None
+            } else if let Some(message) = sub_matches.get_one::<String>("message") {
+                Some(message.clone())
+            } else {
+                let current = graph.get_node(id).note.unwrap_or_default();
+                Some(edit_text(&current)?.trim_end().to_string())
+            };
+            graph.set_note(id, note)?;
+        }
         Some(("ls", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let depth = if sub_matches.get_flag("recurse") {
@@ -498,23 +857,92 @@ fn handle_graph_command(
             };
 
             let show_archived = sub_matches.get_flag("archived");
+            let filter = traverse_filter_from_matches(sub_matches)?;
             match sub_matches.get_one::<String>("ID") {
-                None => displayer.list_roots(graph, depth, show_archived)?,
-                Some(id) => displayer.list_children(
-                    graph,
-                    graph.get_index_cli(id, assume_date)?,
-                    depth,
-                    show_archived,
-                )?,
+                None => {
+                    let roots = graph.get_root_nodes_indices().to_vec();
+                    view_dates(graph, config, &roots, depth)?;
+                    displayer.list_roots(graph, depth, show_archived, &filter)?
+                }
+                Some(id) => {
+                    materialize_viewed_date(graph, config, id, assume_date)?;
+                    let idx = graph.get_index_cli(id, assume_date)?;
+                    // The listed node comes on top of `depth` levels of children
+                    let view_depth = if depth == 0 { 0 } else { depth + 1 };
+                    view_dates(graph, config, &[idx], view_depth)?;
+                    displayer.list_children(graph, idx, depth, show_archived, &filter)?
+                }
             }
         }
         Some(("lsd", sub_matches)) => {
             let show_archived = sub_matches.get_flag("archived");
-            displayer.list_dates(graph, show_archived)?;
+            let filter = traverse_filter_from_matches(sub_matches)?;
+            view_dates(graph, config, &graph.get_date_nodes_indices(), 1)?;
+            displayer.list_dates(graph, show_archived, &filter)?;
         }
         Some(("lsa", _)) => {
             displayer.list_archived(graph)?;
         }
+        Some(("q", sub_matches)) => {
+            let query = sub_matches
+                .get_many::<String>("query")
+                .expect("query required")
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let indices = query.parse::<Query>()?.eval(graph)?;
+            displayer.list_matches(graph, &indices);
+        }
+        Some(("start", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = graph.get_index_cli(
+                sub_matches.get_one::<String>("ID").expect("ID required"),
+                assume_date,
+            )?;
+            if let Some((stopped, interval)) = graph.start_timer(id)? {
+                displayer.print_timer_stopped(graph, stopped, &interval);
+            }
+            displayer.print_timer_started(graph, id);
+        }
+        Some(("stop", _)) => {
+            let (stopped, interval) = graph.stop_timer()?;
+            displayer.print_timer_stopped(graph, stopped, &interval);
+        }
+        Some(("timelog", sub_matches)) => {
+            let assume_date = sub_matches.get_flag("assumedate");
+            let id = sub_matches
+                .get_one::<String>("ID")
+                .map(|id| graph.get_index_cli(id, assume_date))
+                .transpose()?;
+            let subtree = id.map(|id| graph.subtree(id));
+            displayer.list_time_log(graph, &graph.time_log(id), subtree.as_ref());
+        }
+        Some(("find", sub_matches)) => {
+            let text = sub_matches
+                .get_many::<String>("text")
+                .expect("text required")
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let show_archived = sub_matches.get_flag("archived");
+            let hits: Vec<_> = graph
+                .search(&text)
+                .into_iter()
+                .filter(|hit| show_archived || !graph.get_node(hit.index).metadata.archived)
+                .collect();
+            displayer.list_search_hits(graph, &hits);
+        }
+        Some(("done", sub_matches)) => {
+            let since = sub_matches
+                .get_one::<String>("since")
+                .expect("since should have a default value");
+            // Count from the start of the day so that e.g. "today" includes this morning
+            let since = parse_datetime_extended(since)?
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight should be a valid time");
+            displayer.list_completed(graph, &graph.completed_since(since));
+        }
         Some(("rand", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             let id = sub_matches
@@ -538,7 +966,7 @@ fn handle_graph_command(
                         .get_node(*x)
                         .data
                         .as_task()
-                        .map(|task| task.state != TaskState::Done)
+                        .map(|task| !task.state.is_closed())
                         .unwrap_or(true)
                 });
                 item = nodes.choose(&mut rng());
@@ -566,7 +994,10 @@ fn handle_graph_command(
         Some(("stats", sub_matches)) => {
             let assume_date = sub_matches.get_flag("assumedate");
             if let Some(id) = sub_matches.get_one::<String>("ID") {
-                displayer.print_stats(graph, Some(graph.get_index_cli(id, assume_date)?))?;
+                materialize_viewed_date(graph, config, id, assume_date)?;
+                let idx = graph.get_index_cli(id, assume_date)?;
+                view_dates(graph, config, &[idx], 1)?;
+                displayer.print_stats(graph, Some(idx))?;
             } else {
                 displayer.print_stats(graph, None)?;
             };
@@ -574,15 +1005,40 @@ fn handle_graph_command(
         Some(("clean", _)) => {
             graph.clean();
         }
+        Some(("fsck", sub_matches)) => {
+            // `--fix` without any kinds repairs everything
+            let kinds: Option<Vec<ProblemKind>> = sub_matches
+                .get_many::<ProblemKind>("fix")
+                .map(|kinds| kinds.copied().collect());
+            let problems = graph
+                .validate()
+                .into_iter()
+                .map(|problem| {
+                    let repaired = kinds
+                        .as_ref()
+                        .filter(|kinds| kinds.is_empty() || kinds.contains(&problem.kind()))
+                        .map(|_| graph.repair(&problem));
+                    (problem, repaired)
+                })
+                .collect::<Vec<_>>();
+            displayer.print_problems(&problems);
+        }
         Some(("cal", sub_matches)) => {
-            let date_str = sub_matches.get_one::<String>("date");
-            if let Some(date) = date_str {
-                let date = parse_datetime_extended(date)?;
-                return displayer.print_calendar(graph, &date.date_naive());
-            } else {
-                let today = Local::now();
-                return displayer.print_calendar(graph, &today.date_naive());
-            }
+            let date = match sub_matches.get_one::<String>("date") {
+                Some(date) => parse_datetime_extended(date)?.date_naive(),
+                None => Local::now().date_naive(),
+            };
+            let month: Vec<usize> = graph
+                .get_date_nodes_indices()
+                .into_iter()
+                .filter(|idx| {
+                    graph.get_node(*idx).data.as_date().is_some_and(|data| {
+                        (data.date.year(), data.date.month()) == (date.year(), date.month())
+                    })
+                })
+                .collect();
+            view_dates(graph, config, &month, 1)?;
+            return displayer.print_calendar(graph, &date);
         }
         Some(("cp", sub_matches)) => {
             // FIXME: weird logic idk?
@@ -596,52 +1052,56 @@ fn handle_graph_command(
                     "parent node ID is required!".to_string(),
                 ))?;
 
-            let target_exists;
-
-            // if user gives a nonexistent date as a target, make a new date node.
-            let parent_idx = if let Ok(idx) = graph.get_index_cli(parent_id, assume_date_2) {
-                target_exists = true;
-                idx
-            } else if let Ok(date) = parse_datetime_extended(parent_id) {
-                if !recursive {
-                    return Err(AppError::InvalidArg(
-                        "Copying a date node to a nonexistent date requires --recursive"
-                            .to_string(),
-                    ));
-                }
-                target_exists = false;
-                graph.insert_date("".to_string(), date.date_naive())
-            } else {
-                return Err(AppError::IndexRetrievalError(
-                    "Target node not found!".to_string(),
-                ));
-            };
-
             let from_ids = sub_matches
                 .get_many::<String>("source")
                 .ok_or(AppError::InvalidArg(
                     "source node ID(s) is required!".to_string(),
                 ))?;
 
-            for id in from_ids {
-                let from = graph.get_index_cli(id, assume_date_1)?;
-
-                // we make special treatment for date -> date copying, when the target date used
-                // to not exist. because the graph.copy method doesn't really care about the type
-                // of the node it's copying (everything will turn into normal nodes), we make the
-                // target manually then copy the children from the date node.
-                // also, recursion is guaranteed because of the logic above.
-                if !target_exists {
-                    let node = graph.get_node(from);
-                    for idx in node.metadata.children {
-                        graph.copy_recurse(idx, parent_idx)?;
+            // the date node made for the target is undone too if copying fails
+            graph.transaction(|graph| -> AppResult<()> {
+                let target_exists;
+
+                // if user gives a nonexistent date as a target, make a new date node.
+                let parent_idx = if let Ok(idx) = graph.get_index_cli(parent_id, assume_date_2) {
+                    target_exists = true;
+                    idx
+                } else if let Ok(date) =
+                    parse_datetime_extended(parent_id.strip_prefix('@').unwrap_or(parent_id))
+                {
+                    if !recursive {
+                        return Err(AppError::InvalidArg(
+                            "Copying a date node to a nonexistent date requires --recursive"
+                                .to_string(),
+                        ));
                     }
-                } else if recursive {
-                    graph.copy_recurse(from, parent_idx)?;
+                    target_exists = false;
+                    graph.insert_date("".to_string(), date.date_naive())
                 } else {
-                    graph.copy(from, parent_idx)?;
+                    return Err(AppError::IndexRetrievalError(
+                        "Target node not found!".to_string(),
+                    ));
+                };
+
+                for from in graph.get_indices_cli(from_ids, assume_date_1)? {
+                    // we make special treatment for date -> date copying, when the target date used
+                    // to not exist. because copies of date nodes turn into pseudo nodes (there is only
+                    // one node per date), we make the target manually then copy the children from
+                    // the date node.
+                    // also, recursion is guaranteed because of the logic above.
+                    if !target_exists {
+                        let node = graph.get_node(from);
+                        for idx in node.metadata.children {
+                            graph.copy_subgraph(idx, parent_idx)?;
+                        }
+                    } else if recursive {
+                        graph.copy_subgraph(from, parent_idx)?;
+                    } else {
+                        graph.copy(from, parent_idx)?;
+                    }
                 }
-            }
+                Ok(())
+            })?;
         }
         Some(("ord", sub_matches)) => {
             let assume_date_1 = sub_matches.get_flag("assumedate1");
@@ -679,10 +1139,10 @@ fn handle_graph_command(
 
                     for id in &parents {
                         let node = graph.get_node(*id);
-                        if let Some(alias) = node.metadata.alias {
+                        if let Some(alias) = node.metadata.aliases.first() {
                             println!(
                                 "* {} ({})",
-                                displayer.display_id(*id, Some(&alias)),
+                                displayer.display_id(*id, Some(alias)),
                                 node.title
                             );
                         } else {
@@ -721,16 +1181,99 @@ fn handle_graph_command(
     Ok(())
 }
 
+/// Builds the children filter shared by the listing subcommands.
+fn traverse_filter_from_matches(sub_matches: &ArgMatches) -> AppResult<TraverseFilter> {
+    let tag = match sub_matches.get_one::<String>("tag") {
+        Some(tag) => Some(Graph::normalize_tag(tag)?),
+        None => None,
+    };
+    Ok(TraverseFilter {
+        sort_by_priority: sub_matches.get_flag("sort"),
+        min_priority: sub_matches.get_one::<Priority>("priority").copied(),
+        tag,
+        ready: sub_matches.get_flag("ready"),
+    })
+}
+
+/// Merges the save files given to `merge`, writing the result over ours unless told otherwise.
+/// Fails if anything conflicted, so that version control systems can flag the merge for review.
+fn merge_files(
+    sub_matches: &ArgMatches,
+    config: &CliConfig,
+    displayer: &Displayer,
+) -> AppResult<()> {
+    let path = |name| {
+        sub_matches
+            .get_one::<PathBuf>(name)
+            .expect("merge paths are required")
+    };
+    let base = doc::load(&mut File::open(path("base"))?)?;
+    let mut ours = doc::load(&mut File::open(path("ours"))?)?;
+    let theirs = doc::load(&mut File::open(path("theirs"))?)?;
+    ours.set_propagation(config.graph.propagation);
+
+    let merged = merge(&base, &ours, &theirs);
+    let output = sub_matches
+        .get_one::<PathBuf>("output")
+        .unwrap_or(path("ours"));
+    doc::save(&mut File::create(output)?, &Doc::new(&merged.graph))?;
+
+    displayer.print_merge(&merged);
+    if merged.conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::MergeConflicts(merged.conflicts.len()))
+    }
+}
+
+/// Describes the invoked command for the journal, e.g. `rm -r 3`
+fn command_label(matches: &ArgMatches) -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let name = matches.subcommand_name().unwrap_or_default();
+    let start = args.iter().position(|arg| arg == name).unwrap_or(0);
+    args[start..]
+        .iter()
+        .map(|arg| {
+            if arg.contains(char::is_whitespace) {
+                format!("\"{arg}\"")
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn handle_command(
     matches: &ArgMatches,
     graph: &mut Graph,
+    journal: &mut Journal,
     config: &CliConfig,
     displayer: &Displayer,
 ) -> AppResult<()> {
     match matches.subcommand() {
+        Some(("undo", sub_matches)) => {
+            let count = *sub_matches
+                .get_one::<usize>("count")
+                .expect("count should have a default value");
+            let labels = journal.undo(graph, count)?;
+            displayer.print_journal_steps(&labels, true);
+            Ok(())
+        }
+        Some(("redo", sub_matches)) => {
+            let count = *sub_matches
+                .get_one::<usize>("count")
+                .expect("count should have a default value");
+            let labels = journal.redo(graph, count)?;
+            displayer.print_journal_steps(&labels, false);
+            Ok(())
+        }
         Some(("bp", sub_matches)) => {
             handle_blueprints_command(sub_matches.subcommand(), graph, config, displayer)
         }
+        Some(("recur", sub_matches)) => {
+            handle_recur_command(sub_matches.subcommand(), graph, config, displayer)
+        }
         Some((_, _)) => handle_graph_command(matches.subcommand(), graph, config, displayer, false),
         _ => Err(AppError::InvalidSubcommand),
     }
@@ -768,39 +1311,65 @@ fn cli() -> AppResult<Command> {
             .arg(arg!(-d --date <date> "Makes this a date node")
                 .value_parser(value_parser!(String))
                 .conflicts_with_all(["parent", "root"]))
+            .arg(arg!(--due <date> "Sets a due date for this task")
+                .value_parser(value_parser!(String))
+                .conflicts_with_all(["date", "pseudo"]))
+            .arg(arg!(--at <time> "Time of day the task is due (HH:MM)")
+                .value_parser(value_parser!(String))
+                .requires("due"))
+            .arg(arg!(-p --priority <priority> "Sets a priority for this task")
+                .value_parser(value_parser!(Priority))
+                .conflicts_with_all(["date", "pseudo"]))
         )
         .subcommand(Command::new("rm")
             .about("Removes nodes from the graph")
             .arg(arg!(<ID>... "Which nodes to remove"))
             .arg(arg!(-r --recursive "Whether to remove child nodes recursively"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("link")
             .about("Creates a parent-child edge connection between 2 nodes")
             .arg(arg!(parent: <ID1> "Which node should be the parent in this connection"))
             .arg(arg!(child: <ID2>... "Which node should be the child in this connection"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("unlink")
             .about("Removes a parent-child edge connection between 2 nodes")
             .arg(arg!(parent: <ID1> "Which node should be the parent in this connection"))
             .arg(arg!(child: <ID2>... "Which node should be the child in this connection"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("dep")
+            .subcommand_required(true)
+            .about("Manages dependencies between tasks")
+            .long_about("Manages dependencies between tasks. A task is blocked until every task it depends on is done. Dependencies do not affect completion")
+            .subcommand(Command::new("add")
+                .about("Makes a task wait on other tasks")
+                .arg(arg!(<ID> "Which task is blocked"))
+                .arg(arg!(<blocker>... "Which task(s) have to be done first"))
+                .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+            )
+            .subcommand(Command::new("rm")
+                .about("Removes dependencies of a task")
+                .arg(arg!(<ID> "Which task is blocked"))
+                .arg(arg!(<blocker>... "Which task(s) it should no longer wait on"))
+                .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+            )
         )
         .subcommand(Command::new("mv")
             .about("Unlink nodes from all current parents, then link to a new parent")
             .arg(arg!(node: <ID1>... "Which nodes to unlink"))
             .arg(arg!(parent: <ID2> "New parent for node"))
-            .arg(arg!(--assumedate1 "Force ID1 (all when provided more than one) to be interpreted as a date"))
-            .arg(arg!(--assumedate2 "Force ID2 to be interpreted as a date"))
+            .arg(arg!(--assumedate1 "Force ID1 (all when provided more than one) to be interpreted as a date").hide(true))
+            .arg(arg!(--assumedate2 "Force ID2 to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("cp")
             .about("Copy a node to a parent")
             .arg(arg!(source: <ID1>... "Which node to copy from"))
             .arg(arg!(parent: <ID2> "Which node to copy to"))
             .arg(arg!(-r --recursive "Whether to copy nodes recursively"))
-            .arg(arg!(--assumedate1 "Force IDs 1 to be interpreted as dates"))
-            .arg(arg!(--assumedate2 "Force ID 2 to be interpreted as a date"))
+            .arg(arg!(--assumedate1 "Force IDs 1 to be interpreted as dates").hide(true))
+            .arg(arg!(--assumedate2 "Force ID 2 to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("ord")
             .about("Reorder a node")
@@ -808,55 +1377,129 @@ fn cli() -> AppResult<Command> {
             .arg(arg!(<order> "Which direction to reorder node").value_parser(value_parser!(OrderingDirection)))
             .arg(arg!([count] "How many times to move up/down").value_parser(value_parser!(u32)).default_value("1"))
             .arg(arg!(parent: -p --parent <ID2> "Parent of node (can be omitted when there's only one parent)").required(false))
-            .arg(arg!(--assumedate1 "Force ID1 to be interpreted as a date"))
-            .arg(arg!(--assumedate2 "Force ID2 to be interpreted as a date"))
+            .arg(arg!(--assumedate1 "Force ID1 to be interpreted as a date").hide(true))
+            .arg(arg!(--assumedate2 "Force ID2 to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("set")
             .about("Sets a node's state")
             .arg(arg!(<ID> "Which node to modify"))
             .arg(arg!(<state> "What state to set the node").value_parser(value_parser!(TaskState)))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(no_propagate: --"no-propagate" "Leave the node's subtasks and parents as they are"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("set-due")
+            .about("Sets a task's due date")
+            .arg(arg!(<ID> "Which task to modify"))
+            .arg(arg!(<due> "When the task is due"))
+            .arg(arg!(--at <time> "Time of day the task is due (HH:MM)")
+                .value_parser(value_parser!(String)))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("set-priority")
+            .about("Sets a task's priority")
+            .arg(arg!(<ID> "Which task to modify"))
+            .arg(arg!(<priority> "What priority to give the task").value_parser(value_parser!(Priority)))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("set-estimate")
+            .about("Sets how much effort a task takes, weighing it in its parents' progress")
+            .arg(arg!(<ID> "Which task to modify"))
+            .arg(arg!(<estimate> "How much effort the task takes, in any unit").value_parser(value_parser!(u32)))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("unset-estimate")
+            .about("Removes tasks' effort estimate")
+            .arg(arg!(<ID>... "Which task(s) to remove the estimate from"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("policy")
+            .about("Sets how tasks' completion propagates, overriding the configured policy")
+            .arg(arg!(<ID>... "Which task(s) to modify"))
+            .arg(arg!(-r --rollup <rollup> "How the tasks follow their subtasks").value_parser(value_parser!(Rollup)))
+            .arg(arg!(-c --cascade <cascade> "Whether checking the tasks also checks their subtasks").value_parser(value_parser!(bool)))
+            .arg(arg!(--reset "Go back to the configured policy").conflicts_with_all(["rollup", "cascade"]))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("unset-due")
+            .about("Removes tasks' due date")
+            .arg(arg!(<ID>... "Which task(s) to remove the due date from"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("check")
             .about("Marks nodes as completed")
             .arg(arg!(<ID>... "Which node(s) to mark as completed"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(no_propagate: --"no-propagate" "Leave the nodes' subtasks and parents as they are"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("uncheck")
             .about("Marks nodes as incomplete")
             .arg(arg!(<ID>... "Which node(s) to mark as incomplete"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(no_propagate: --"no-propagate" "Leave the nodes' subtasks and parents as they are"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("arc")
             .about("Archives (hides) nodes from view")
             .arg(arg!(<ID>... "Which node(s) to archive"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("unarc")
             .about("Unarchives (unhides) nodes from view")
             .arg(arg!(<ID>... "Which node(s) to archive"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("alias")
-            .about("Adds an alias for a node")
-            .arg(arg!(<ID> "Which node to alias"))
-            .arg(arg!(<alias> "What alias to give this node"))
-            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+            .about("Adds aliases for a node")
+            .long_about("Adds aliases for a node, after the ones it already has. Aliases may not contain whitespace or look like a node ID or a date.")
+            .arg(Arg::new("ID").help("Which node to alias").required_unless_present("rename"))
+            .arg(Arg::new("alias").help("What alias(es) to give this node").num_args(1..).required_unless_present("rename"))
+            .arg(arg!(-f --force "Take the alias(es) from the node(s) that have them"))
+            .arg(Arg::new("rename")
+                .long("rename")
+                .help("Renames an alias instead")
+                .num_args(2)
+                .value_names(["old", "new"])
+                .conflicts_with_all(["ID", "alias"]))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("unalias")
-            .about("Removes nodes' alias")
-            .long_about("Removes all aliases of nodes")
-            .arg(arg!(<ID>... "Which node(s) to remove aliases"))
-            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date"))
+            .about("Removes nodes' aliases")
+            .long_about("Removes aliases. Giving an alias removes just that alias, giving any other ID removes all aliases of the node(s).")
+            .arg(arg!(<ID>... "Which alias(es) or node(s) to remove aliases from"))
+            .arg(arg!(-D --assumedate "Force the IDs to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("aliases")
             .about("Lists all aliases")
         )
+        .subcommand(Command::new("tag")
+            .about("Tags a node")
+            .arg(arg!(<ID> "Which node to tag"))
+            .arg(arg!(<tag>... "What tag(s) to give this node, with or without a leading '#'"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("untag")
+            .about("Removes tags from a node")
+            .long_about("Removes the given tags from a node, or all of its tags if none are given")
+            .arg(arg!(<ID> "Which node to untag"))
+            .arg(arg!([tag]... "Which tag(s) to remove"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("tags")
+            .about("Lists all tags")
+        )
         .subcommand(Command::new("rename")
             .about("Edit a node's message")
             .arg(arg!(<ID> "Which node to edit"))
             .arg(arg!(<message> "What new message to give it"))
-            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("note")
+            .about("Edits a node's note")
+            .long_about("Edits a node's note in $VISUAL or $EDITOR, unless given with --message")
+            .arg(arg!(<ID> "Which node's note to edit"))
+            .arg(arg!(-m --message <note> "Set the note directly instead of opening an editor")
+                .conflicts_with("clear"))
+            .arg(arg!(--clear "Remove the note"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("ls")
             .about("Lists root nodes or children nodes")
@@ -867,30 +1510,86 @@ fn cli() -> AppResult<Command> {
                 .value_parser(value_parser!(u32))
             )
             .arg(arg!(-r --recurse "Whether to recursively display at infinite depth"))
-            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+            .arg(arg!(-s --sort "Sort children by priority, most important first"))
+            .arg(arg!(-p --priority <priority> "Only display tasks of at least this priority")
+                .value_parser(value_parser!(Priority)))
+            .arg(arg!(-t --tag <tag> "Only display nodes with this tag"))
+            .arg(arg!(--ready "Only display tasks that are not done and not blocked"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("lsd")
             .about("Lists all date nodes")
             .arg(arg!(-a --archived "Display archived nodes"))
+            .arg(arg!(-s --sort "Sort children by priority, most important first"))
+            .arg(arg!(-p --priority <priority> "Only display tasks of at least this priority")
+                .value_parser(value_parser!(Priority)))
+            .arg(arg!(-t --tag <tag> "Only display nodes with this tag"))
+            .arg(arg!(--ready "Only display tasks that are not done and not blocked"))
         )
         .subcommand(Command::new("lsa")
             .about("Lists all archived nodes")
         )
+        .subcommand(Command::new("q")
+            .about("Lists nodes matching a query, e.g. 'state:partial tag:work due<7d under:project depth<=2'")
+            .arg(arg!(query: <QUERY>... "Query terms: words to find in titles, or field conditions (title, state, type, tag, due, priority, under, depth, archived, ready, blocked)")
+                .allow_hyphen_values(true))
+        )
+        .subcommand(Command::new("start")
+            .about("Starts timing work on a node, stopping the timer already running")
+            .arg(arg!(<ID> "Which node to work on"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("stop")
+            .about("Stops the running timer, logging the time worked")
+        )
+        .subcommand(Command::new("timelog")
+            .about("Lists the time worked on a node and its descendants, or on every node")
+            .arg(arg!([ID] "Which node to list the time of"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
+        )
+        .subcommand(Command::new("find")
+            .about("Searches node titles, aliases and notes, listing the best matches first")
+            .arg(arg!(text: <TEXT>... "Words to search for"))
+            .arg(arg!(-a --archived "Include archived nodes"))
+        )
+        .subcommand(Command::new("done")
+            .about("Lists tasks completed since a date, in order of completion")
+            .arg(arg!([since] "Date to list completed tasks from")
+                .default_value("1 week ago"))
+        )
         .subcommand(Command::new("rand")
             .about("Picks a random child node")
             .arg(arg!(<ID> "Which parent node to randomly pick a child from"))
             .arg(arg!(-u --unchecked "Only pick among unchecked tasks"))
             .arg(arg!(-c --checked "Only pick among checked tasks"))
-            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("stats")
             .about("Displays statistics of a node")
             .arg(arg!([ID] "Which node to display stats"))
-            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+            .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
         )
         .subcommand(Command::new("clean")
             .about("Compresses and cleans up the graph")
         )
+        .subcommand(Command::new("merge")
+            .about("Merges two copies of a save file changed since a common base, e.g. as a git merge driver")
+            .long_about("Merges two copies of a save file changed since a common base. Changes that clash keep our \
+side, and are reported. To use this as a git merge driver, add `.tuesday merge=tuesday` to \
+.gitattributes and the following to your git config:\n\n\
+[merge \"tuesday\"]\n    name = tuesday save file merge\n    driver = tuecli merge %O %A %B")
+            .arg(arg!(<base> "The common base").value_parser(value_parser!(PathBuf)))
+            .arg(arg!(<ours> "Our copy, which the result replaces by default").value_parser(value_parser!(PathBuf)))
+            .arg(arg!(<theirs> "Their copy").value_parser(value_parser!(PathBuf)))
+            .arg(arg!(-o --output <FILE> "Where to write the result instead of our copy").value_parser(value_parser!(PathBuf)))
+        )
+        .subcommand(Command::new("fsck")
+            .about("Checks the graph for broken links and indices, without changing anything unless told to")
+            .arg(arg!(--fix [KIND] "Repair the problems found, or only those of the given kinds")
+                .value_parser(value_parser!(ProblemKind))
+                .num_args(0..)
+                .value_delimiter(','))
+        )
         .subcommand(Command::new("cal")
             .about("Print calendar to display date nodes")
             .arg(arg!(date: [date] "Date to use (only the month will be considered)")
@@ -913,7 +1612,7 @@ fn cli() -> AppResult<Command> {
                 .arg(arg!(to_file: -f --file "Write to file with name <name> instead of to your save directory"))
                 .arg(arg!(preserve: -p --preserve "Preserve node after the conversion"))
                 .arg(arg!(overwrite: -o --overwrite "Overwrite existing blueprint"))
-                .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+                .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
             )
             .subcommand(Command::new("rm")
                 .about("Remove a blueprint")
@@ -925,7 +1624,8 @@ fn cli() -> AppResult<Command> {
                 .arg(Arg::new("ID").help("Parent of blueprint tree").required_unless_present("root"))
                 .arg(arg!([message] "Title of the new blueprint node"))
                 .arg(arg!(root: -r --root "Insert the blueprint to root"))
-                .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date"))
+                .arg(arg!(-a --aliases "Keep the blueprint's aliases, unless they are already taken"))
+                .arg(arg!(-D --assumedate "Force the ID to be interpreted as a date").hide(true))
             )
             .subcommand(Command::new("show")
                 .about("Show a blueprint file in tree/graph form")
@@ -943,6 +1643,44 @@ fn cli() -> AppResult<Command> {
                 .arg(arg!(args: <args>... "Edit arguments"))
             )
         )
+        .subcommand(Command::new("recur")
+            .subcommand_required(true)
+            .about("Recurring tasks, materialized under date nodes")
+            .subcommand(Command::new("add")
+                .about("Adds a recurrence rule")
+                .long_about("Adds a recurrence rule. Rules are written as daily, weekdays, every:<N> (every N days), or monthly:<day>")
+                .arg(arg!(<rule> "When the task recurs"))
+                .arg(Arg::new("message").help("Title of the recurring task").required_unless_present("blueprint"))
+                .arg(arg!(-b --blueprint <name> "Insert this blueprint (name or path) instead of a single task")
+                    .conflicts_with("message"))
+                .arg(arg!(-s --start <date> "First date the task may recur on")
+                    .default_value("today"))
+            )
+            .subcommand(Command::new("ls")
+                .about("Lists recurrence rules")
+            )
+            .subcommand(Command::new("rm")
+                .about("Removes recurrence rules. Tasks they already created are kept")
+                .arg(arg!(<index>... "Which rule(s) to remove").value_parser(value_parser!(usize)))
+            )
+            .subcommand(Command::new("sync")
+                .about("Creates every recurring task up to a date")
+                .arg(arg!([until] "Date to create recurring tasks up to")
+                    .default_value("today"))
+            )
+        )
+        .subcommand(Command::new("undo")
+            .about("Undoes the last changes made to the graph")
+            .arg(arg!([count] "How many changes to undo")
+                .value_parser(value_parser!(usize))
+                .default_value("1"))
+        )
+        .subcommand(Command::new("redo")
+            .about("Redoes the last undone changes")
+            .arg(arg!([count] "How many changes to redo")
+                .value_parser(value_parser!(usize))
+                .default_value("1"))
+        )
         .subcommand(Command::new("new-cfg")
             .about("Dump a default configuration file. Recommended: run then redirect and save to ~/.tueconf.toml")
         )
@@ -959,6 +1697,12 @@ fn main() -> AppResult<()> {
     }
 
     let config = get_config(matches.get_one::<PathBuf>("config"))?;
+    let displayer = Displayer::new(&config);
+
+    // Merging works on the given files rather than the usual save file
+    if let Some(("merge", sub_matches)) = matches.subcommand() {
+        return merge_files(sub_matches, &config, &displayer);
+    }
 
     let (mut graph, local) = match (
         matches.get_one::<String>("local").is_some(),
@@ -983,22 +1727,41 @@ fn main() -> AppResult<()> {
         }
     };
 
-    let displayer = Displayer::new(&config);
+    // Default to current directory if --local is not specified
+    let local_path = PathBuf::from(
+        matches
+            .get_one::<String>("local")
+            .map(|s| s.as_str())
+            .unwrap_or("."),
+    );
+    let mut journal = if local {
+        doc::load_local_journal(local_path.clone())
+    } else {
+        doc::load_global_journal()?
+    };
 
-    handle_command(&matches, &mut graph, &config, &displayer)?;
+    graph.set_propagation(config.graph.propagation);
+
+    let before = graph.clone();
+    handle_command(&matches, &mut graph, &mut journal, &config, &displayer)?;
+    match matches.subcommand_name() {
+        Some("undo" | "redo") => {}
+        // Viewing dates only changes the graph by materializing recurring tasks, which shouldn't
+        // be undone on their own
+        Some("ls" | "lsd" | "cal" | "stats") => {
+            journal.amend(&before, &graph);
+        }
+        _ => {
+            journal.record(command_label(&matches), &before, &graph);
+        }
+    }
 
     if local {
-        doc::save_local(
-            // Default to current directory if --local is not specified
-            PathBuf::from(
-                matches
-                    .get_one::<String>("local")
-                    .unwrap_or(&".".to_string()),
-            ),
-            &Doc::new(&graph),
-        )?;
+        doc::save_local(local_path.clone(), &Doc::new(&graph))?;
+        doc::save_local_journal(local_path, &journal)?;
     } else {
         doc::save_global(&Doc::new(&graph))?;
+        doc::save_global_journal(&journal)?;
     }
 
     Ok(())
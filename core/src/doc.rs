@@ -1,10 +1,12 @@
 pub mod compat;
 pub mod errors;
+pub mod merge;
 
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
+use crate::graph::journal::Journal;
 use crate::graph::Graph;
 
 use serde::{Deserialize, Serialize};
@@ -12,13 +14,14 @@ use serde::{Deserialize, Serialize};
 use errors::ErrorType;
 
 /// Update this whenever the structure of Config or Graph changes
-const VERSION: u32 = 6;
+const VERSION: u32 = 19;
 
 pub const fn get_doc_ver() -> u32 {
     VERSION
 }
 
 const FILENAME: &str = ".tuesday";
+const JOURNAL_FILENAME: &str = ".tuesday.journal";
 
 /// Result of save file operation.
 type DocResult<T> = Result<T, ErrorType>;
@@ -38,6 +41,22 @@ impl Doc {
     }
 }
 
+/// Undo/redo journal, stored next to the save file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JournalDoc {
+    pub version: u32,
+    pub journal: Journal,
+}
+
+impl JournalDoc {
+    pub fn new(journal: &Journal) -> Self {
+        Self {
+            version: VERSION,
+            journal: journal.clone(),
+        }
+    }
+}
+
 pub fn save_global(config: &Doc) -> DocResult<()> {
     save(&mut get_global_save()?, config)?;
     Ok(())
@@ -135,6 +154,55 @@ pub fn local_exists(mut path: PathBuf) -> bool {
     path.exists()
 }
 
+/// Loads a journal from `path`. Journals that are missing, unreadable, or were written for another
+/// document version start out empty, as undoing is best-effort.
+fn load_journal(path: PathBuf) -> Journal {
+    let Ok(bytes) = std::fs::read(path) else {
+        return Journal::new();
+    };
+    match serde_yaml_ng::from_slice::<JournalDoc>(&bytes) {
+        Ok(doc) if doc.version == VERSION => doc.journal,
+        _ => Journal::new(),
+    }
+}
+
+fn save_journal(path: PathBuf, journal: &Journal) -> DocResult<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    serde_yaml_ng::to_writer(&mut file, &JournalDoc::new(journal))?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Loads the journal kept next to the local save file in `path`
+pub fn load_local_journal(mut path: PathBuf) -> Journal {
+    path.push(JOURNAL_FILENAME);
+    load_journal(path)
+}
+
+pub fn save_local_journal(mut path: PathBuf, journal: &Journal) -> DocResult<()> {
+    path.push(JOURNAL_FILENAME);
+    save_journal(path, journal)
+}
+
+/// Loads the journal kept next to the global save file
+pub fn load_global_journal() -> DocResult<Journal> {
+    Ok(load_journal(get_global_journal_path()?))
+}
+
+pub fn save_global_journal(journal: &Journal) -> DocResult<()> {
+    save_journal(get_global_journal_path()?, journal)
+}
+
+fn get_global_journal_path() -> DocResult<PathBuf> {
+    let mut path = home::home_dir().ok_or(ErrorType::NoHome)?;
+    path.push(JOURNAL_FILENAME);
+    Ok(path)
+}
+
 pub fn get_global_save() -> DocResult<File> {
     let mut path = if let Some(x) = home::home_dir() {
         x
@@ -1,11 +1,11 @@
 use core::str;
-use std::cell::RefCell;
 use std::collections::HashMap;
 
+use chrono::NaiveDateTime;
 use serde_yaml_ng::{Mapping, Value};
 
 use crate::graph::node::{Node, NodeMetadata};
-use crate::graph::Graph;
+use crate::graph::{Graph, GraphGetters};
 
 use super::{errors::ErrorType, Doc, DocResult, VERSION};
 
@@ -82,11 +82,20 @@ pub fn parse_yaml(doc: Value) -> DocResult<Doc> {
             )
         })
         .collect::<HashMap<_, _>>();
+    let next_id = graph_doc["next_id"].as_u64().unwrap_or(0) as usize;
+    let recurrences = serde_yaml_ng::from_value(graph_doc["recurrences"].clone())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    let timer = serde_yaml_ng::from_value(graph_doc["timer"].clone())
+        .ok()
+        .flatten();
 
     // Parse nodes
     // Provide default values if any are missing
     // ~ Maps are funky, functional programming go brrrr
     let mut nodes = vec![];
+    let mut tags: HashMap<String, Vec<usize>> = HashMap::new();
     for node_doc in graph_doc["nodes"].as_sequence().unwrap_or(&vec![]) {
         if node_doc.is_null() {
             nodes.push(None);
@@ -116,49 +125,117 @@ pub fn parse_yaml(doc: Value) -> DocResult<Doc> {
             ))? as usize);
         }
 
-        // Add local node alias to root doc aliases if not already added
-        let alias = metadata["alias"].as_str();
-        if let Some(ref alias) = alias {
-            aliases.insert(alias.to_string(), index);
+        let mut blocked_by = vec![];
+        for blocker_doc in metadata["blocked_by"].as_sequence().unwrap_or(&vec![]) {
+            blocked_by.push(blocker_doc.as_i64().ok_or(ErrorType::ParseError(
+                "Blocker index must be an integer".to_string(),
+            ))? as usize);
+        }
+        let mut blocks = vec![];
+        for blocked_doc in metadata["blocks"].as_sequence().unwrap_or(&vec![]) {
+            blocks.push(blocked_doc.as_i64().ok_or(ErrorType::ParseError(
+                "Blocked index must be an integer".to_string(),
+            ))? as usize);
+        }
+
+        // Add local node aliases to root doc aliases if not already added
+        let mut node_aliases = vec![];
+        for alias_doc in metadata["aliases"].as_sequence().unwrap_or(&vec![]) {
+            let alias = alias_doc
+                .as_str()
+                .ok_or(ErrorType::ParseError("Alias must be a string".to_string()))?
+                .to_string();
+            aliases.insert(alias.clone(), index);
+            node_aliases.push(alias);
+        }
+
+        // The tag index is rebuilt from the nodes' own tags
+        let mut node_tags = vec![];
+        for tag_doc in metadata["tags"].as_sequence().unwrap_or(&vec![]) {
+            let tag = tag_doc
+                .as_str()
+                .ok_or(ErrorType::ParseError("Tag must be a string".to_string()))?
+                .to_string();
+            tags.entry(tag.clone()).or_default().push(index);
+            node_tags.push(tag);
         }
 
-        nodes.push(Some(RefCell::new(Node {
+        nodes.push(Some(Node {
             title: node_doc["title"].as_str().unwrap_or("No Title").to_string(),
             data: serde_yaml_ng::from_value(node_doc["data"].clone())?,
             metadata: NodeMetadata {
                 archived: metadata["archived"].as_bool().unwrap_or(false),
                 index,
-                alias: alias.map(|s| s.to_string()),
+                aliases: node_aliases,
                 parents,
                 children,
+                tags: node_tags,
+                created: parse_timestamp(&metadata["created"]),
+                modified: parse_timestamp(&metadata["modified"]),
+                completed: parse_timestamp(&metadata["completed"]),
+                blocked_by,
+                blocks,
+                time_log: serde_yaml_ng::from_value(metadata["time_log"].clone())
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default(),
+                propagation: serde_yaml_ng::from_value(metadata["propagation"].clone())
+                    .ok()
+                    .flatten(),
             },
-        })));
+            note: node_doc["note"].as_str().map(|s| s.to_string()),
+        }));
     }
 
+    let mut graph = Graph {
+        nodes,
+        slots: HashMap::new(),
+        next_id,
+        roots,
+        archived,
+        dates,
+        aliases: HashMap::new(),
+        tags,
+        recurrences,
+        timer,
+        propagation: Default::default(),
+        observers: Default::default(),
+    };
+    graph.reindex();
+
     // Remove aliases pointing to invalid nodes
-    aliases.retain(|_, v| nodes[*v].is_some());
+    aliases.retain(|_, v| graph.node_at_exists(*v));
 
     // Fix any node aliases that may be desynchronized with the root doc's aliases
     for (k, v) in aliases.iter() {
-        nodes[*v].as_ref().unwrap().borrow_mut().metadata.alias = Some(k.clone());
+        let node_aliases = &mut graph.get_node_mut(*v).metadata.aliases;
+        if !node_aliases.contains(k) {
+            node_aliases.push(k.clone());
+        }
     }
+    for node in graph.nodes.iter_mut().flatten() {
+        let index = node.metadata.index;
+        node.metadata
+            .aliases
+            .retain(|alias| aliases.get(alias) == Some(&index));
+    }
+    graph.aliases = aliases;
 
     // Unify everything
     let result_doc = Doc {
         version: doc_use["version"]
             .as_i64()
             .expect("Version should be integer") as u32,
-        graph: Graph {
-            nodes,
-            roots,
-            archived,
-            dates,
-            aliases,
-        },
+        graph,
     };
     Ok(result_doc)
 }
 
+/// Parses an optional node timestamp, ignoring missing or malformed ones
+fn parse_timestamp(value: &Value) -> Option<NaiveDateTime> {
+    serde_yaml_ng::from_value(value.clone()).ok().flatten()
+}
+
 fn parse_old_yaml(doc: &Value) -> DocResult<Value> {
     let mut doc_modified: Value = doc.clone();
     loop {
@@ -169,8 +246,21 @@ fn parse_old_yaml(doc: &Value) -> DocResult<Value> {
             ))?;
         if current_ver < VERSION as u64 {
             match current_ver {
-                4 => doc_modified = old_yaml::v4_to_v5(doc)?,
-                5 => doc_modified = old_yaml::v5_to_v6(doc)?,
+                4 => doc_modified = old_yaml::v4_to_v5(&doc_modified)?,
+                5 => doc_modified = old_yaml::v5_to_v6(&doc_modified)?,
+                6 => doc_modified = old_yaml::v6_to_v7(&doc_modified)?,
+                7 => doc_modified = old_yaml::v7_to_v8(&doc_modified)?,
+                8 => doc_modified = old_yaml::v8_to_v9(&doc_modified)?,
+                9 => doc_modified = old_yaml::v9_to_v10(&doc_modified)?,
+                10 => doc_modified = old_yaml::v10_to_v11(&doc_modified)?,
+                11 => doc_modified = old_yaml::v11_to_v12(&doc_modified)?,
+                12 => doc_modified = old_yaml::v12_to_v13(&doc_modified)?,
+                13 => doc_modified = old_yaml::v13_to_v14(&doc_modified)?,
+                14 => doc_modified = old_yaml::v14_to_v15(&doc_modified)?,
+                15 => doc_modified = old_yaml::v15_to_v16(&doc_modified)?,
+                16 => doc_modified = old_yaml::v16_to_v17(&doc_modified)?,
+                17 => doc_modified = old_yaml::v17_to_v18(&doc_modified)?,
+                18 => doc_modified = old_yaml::v18_to_v19(&doc_modified)?,
                 _ => {
                     return Err(ErrorType::ParseError(format!(
                         "Oops, no available parsers to parse this document version: {current_ver}"
@@ -187,31 +277,18 @@ fn parse_old_yaml(doc: &Value) -> DocResult<Value> {
 /// Docs-parser for older version of the save file. This is done incrementally (e.g v4 -> v5, v5 ->
 /// v6, etc up until the current version).
 mod old_yaml {
-    #[derive(PartialEq, Eq, Deserialize, Debug)]
-    struct Empty;
-
-    #[derive(PartialEq, Eq, Deserialize, Debug)]
-    enum V5nodeType {
-        Date(Empty),
-        Task(TaskData),
-        Pseudo,
-    }
-
     use chrono::NaiveDate;
-    use serde::Deserialize;
     use serde_yaml_ng::value::{Tag, TaggedValue};
     use serde_yaml_ng::Mapping;
 
-    use crate::graph::node::task::TaskData;
-
     use super::*;
 
     /// The v4 to v5 update introduced some major breaking structure changes:
     /// - `message` is renamed to `title`.
     /// - `type` & `state` are merged into the `data` field which is now tagged based on the node
-    /// type.
+    ///   type.
     /// - `archived`, `index`, `alias`, `parents`, and `children` fields are now moved under
-    /// `metadata`.
+    ///   `metadata`.
     pub fn v4_to_v5(doc: &Value) -> DocResult<Value> {
         let mut cloned_doc = doc.clone();
         let version = &mut cloned_doc["version"];
@@ -324,13 +401,284 @@ mod old_yaml {
         }
         Ok(cloned_doc)
     }
+
+    /// The v6 to v7 update added an optional due date to task nodes, stored as `due` inside the
+    /// task's data field.
+    pub fn v6_to_v7(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(7.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Tagged(val) = &mut node_doc["data"] {
+                if val.tag == "!Task" {
+                    if let Value::Mapping(data) = &mut val.value {
+                        data.insert("due".into(), Value::Null);
+                    }
+                }
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v7 to v8 update added a priority to task nodes, stored as `priority` inside the task's
+    /// data field.
+    pub fn v7_to_v8(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(8.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Tagged(val) = &mut node_doc["data"] {
+                if val.tag == "!Task" {
+                    if let Value::Mapping(data) = &mut val.value {
+                        data.insert("priority".into(), "None".into());
+                    }
+                }
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v8 to v9 update added tags to nodes, stored as `tags` under the node's metadata, and
+    /// a tag index under the graph.
+    pub fn v8_to_v9(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(9.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                metadata.insert("tags".into(), Value::Sequence(vec![]));
+            }
+        }
+        if let Value::Mapping(graph) = graph_doc {
+            graph.insert("tags".into(), Value::Mapping(Mapping::new()));
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v9 to v10 update added an optional long-form `note` to every node.
+    pub fn v9_to_v10(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(10.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if let Value::Mapping(node) = node_doc {
+                node.insert("note".into(), Value::Null);
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v10 to v11 update added `created`, `modified`, and `completed` timestamps under the
+    /// node's metadata. They are unknown for existing nodes and left empty.
+    pub fn v10_to_v11(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(11.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                metadata.insert("created".into(), Value::Null);
+                metadata.insert("modified".into(), Value::Null);
+                metadata.insert("completed".into(), Value::Null);
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v11 to v12 update added recurrence rules to the graph.
+    pub fn v11_to_v12(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(12.into());
+
+        if let Value::Mapping(graph) = &mut cloned_doc["graph"] {
+            graph.insert("recurrences".into(), Value::Sequence(vec![]));
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v12 to v13 update added dependency edges between tasks, stored under the node's
+    /// metadata as `blocked_by` and its inverse, `blocks`.
+    pub fn v12_to_v13(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(13.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                metadata.insert("blocked_by".into(), Value::Sequence(vec![]));
+                metadata.insert("blocks".into(), Value::Sequence(vec![]));
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v13 to v14 update separated node IDs from where nodes are stored, so that cleaning
+    /// the graph no longer renumbers them. The graph now records the next ID to hand out, which
+    /// starts past every slot, including those of removed nodes.
+    pub fn v13_to_v14(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(14.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let next_id = graph_doc["nodes"].as_sequence().map_or(0, Vec::len);
+        if let Value::Mapping(graph) = graph_doc {
+            graph.insert("next_id".into(), Value::Number(next_id.into()));
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v14 to v15 update added time tracking: intervals worked on a node are stored under
+    /// its metadata as `time_log`, and the running timer, if any, under the graph as `timer`.
+    pub fn v14_to_v15(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(15.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                metadata.insert("time_log".into(), Value::Sequence(vec![]));
+            }
+        }
+        if let Value::Mapping(graph) = graph_doc {
+            graph.insert("timer".into(), Value::Null);
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v15 to v16 update added an optional effort estimate to task nodes, stored as
+    /// `estimate` inside the task's data field.
+    pub fn v15_to_v16(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(16.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Tagged(val) = &mut node_doc["data"] {
+                if val.tag == "!Task" {
+                    if let Value::Mapping(data) = &mut val.value {
+                        data.insert("estimate".into(), Value::Null);
+                    }
+                }
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v16 to v17 update added per-node completion propagation policies, stored under the
+    /// node's metadata as `propagation`. Nodes without one follow the graph's.
+    pub fn v16_to_v17(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(17.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                metadata.insert("propagation".into(), Value::Null);
+            }
+        }
+        Ok(cloned_doc)
+    }
+
+    /// The v17 to v18 update added the `InProgress`, `Blocked`, `Waiting` and `Cancelled` task
+    /// states. Existing states keep their meaning, so only the version changes; the bump keeps
+    /// older versions from loading states they don't know.
+    pub fn v17_to_v18(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(18.into());
+        Ok(cloned_doc)
+    }
+
+    /// The v18 to v19 update allowed several aliases per node, so the node's `alias` became a
+    /// list of `aliases`.
+    pub fn v18_to_v19(doc: &Value) -> DocResult<Value> {
+        let mut cloned_doc = doc.clone();
+        let version = &mut cloned_doc["version"];
+        *version = Value::Number(19.into());
+
+        let graph_doc = &mut cloned_doc["graph"];
+        let nodes = graph_doc["nodes"].as_sequence_mut().unwrap();
+
+        for node_doc in nodes {
+            if node_doc.is_null() {
+                continue;
+            }
+            if let Value::Mapping(metadata) = &mut node_doc["metadata"] {
+                let aliases = match metadata.shift_remove("alias") {
+                    Some(alias @ Value::String(_)) => vec![alias],
+                    _ => vec![],
+                };
+                metadata.insert("aliases".into(), Value::Sequence(aliases));
+            }
+        }
+        Ok(cloned_doc)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use chrono::{NaiveDate, NaiveTime};
     use serde_yaml_ng::Value;
 
     use super::old_yaml;
+    use crate::graph::node::task::{DueDate, TaskData, TaskState};
 
     #[test]
     fn test_v4_v5() {
@@ -508,4 +856,1151 @@ graph:
             serde_yaml_ng::to_string(&new_should_be).unwrap()
         );
     }
+
+    #[test]
+    fn test_v6_v7() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 6
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: None
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+  - title: date
+    data: !Date
+      date: 2025-01-01
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 2
+      alias: null
+      children: []
+      parents: []
+  roots:
+  - 0
+  - 2
+  archived: []
+  dates:
+    2025-01-01: 1
+  aliases: {}
+",
+        );
+
+        let new = old_yaml::v6_to_v7(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 7
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: None
+      due: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+  - title: date
+    data: !Date
+      date: 2025-01-01
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 2
+      alias: null
+      children: []
+      parents: []
+  roots:
+  - 0
+  - 2
+  archived: []
+  dates:
+    2025-01-01: 1
+  aliases: {}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn due_dates_and_overdue_tasks() {
+        let day = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let at = |d: NaiveDate, h, m, s| d.and_hms_opt(h, m, s).unwrap();
+        let next_day = day.succ_opt().unwrap();
+
+        // Without a time, a task is due by the end of the day
+        let due = DueDate::new(day, None);
+        assert_eq!(due.deadline(), at(day, 23, 59, 59));
+        assert!(!due.is_passed(at(day, 23, 59, 59)));
+        assert!(due.is_passed(at(next_day, 0, 0, 0)));
+
+        let due = DueDate::new(day, NaiveTime::from_hms_opt(9, 30, 0));
+        assert_eq!(due.deadline(), at(day, 9, 30, 0));
+        assert!(!due.is_passed(at(day, 9, 30, 0)));
+        assert!(due.is_passed(at(day, 9, 30, 1)));
+
+        let mut task = TaskData {
+            due: Some(DueDate::new(day, None)),
+            ..Default::default()
+        };
+        assert!(!task.is_overdue(at(day, 12, 0, 0)));
+        assert!(task.is_overdue(at(next_day, 12, 0, 0)));
+
+        // Closed tasks are never overdue, while other states still are
+        for (state, overdue) in [
+            (TaskState::Partial, true),
+            (TaskState::Blocked, true),
+            (TaskState::Done, false),
+            (TaskState::Cancelled, false),
+        ] {
+            task.state = state;
+            assert_eq!(task.is_overdue(at(next_day, 12, 0, 0)), overdue);
+        }
+
+        // Tasks without a due date are never overdue
+        let task = TaskData::default();
+        assert!(!task.is_overdue(at(next_day, 12, 0, 0)));
+    }
+
+    #[test]
+    fn test_v7_v8() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 7
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+  roots:
+  - 0
+  - 1
+  archived: []
+  dates: {}
+  aliases: {}
+",
+        );
+
+        let new = old_yaml::v7_to_v8(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 8
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+  roots:
+  - 0
+  - 1
+  archived: []
+  dates: {}
+  aliases: {}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v8_v9() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 8
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+",
+        );
+
+        let new = old_yaml::v8_to_v9(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 9
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v9_v10() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 9
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        );
+
+        let new = old_yaml::v9_to_v10(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 10
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v10_v11() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 10
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        );
+
+        let new = old_yaml::v10_to_v11(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 11
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v11_v12() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 11
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+",
+        );
+
+        let new = old_yaml::v11_to_v12(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 12
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v12_v13() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 12
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+",
+        );
+
+        let new = old_yaml::v12_to_v13(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 13
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v13_v14() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 13
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+",
+        );
+
+        let new = old_yaml::v13_to_v14(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 14
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v14_v15() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 14
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+",
+        );
+
+        let new = old_yaml::v14_to_v15(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 15
+graph:
+  nodes:
+  - title: root
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v15_v16() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 15
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  roots:
+  - 0
+  - 1
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        );
+
+        let new = old_yaml::v15_to_v16(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 16
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  - title: pseudo
+    data: !Pseudo
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  roots:
+  - 0
+  - 1
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v16_v17() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 16
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: None
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        );
+
+        let new = old_yaml::v16_to_v17(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 17
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: None
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children: []
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  - null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v17_v18() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 17
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Partial
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children:
+      - 1
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  - title: child
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents:
+      - 0
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        );
+
+        let new = old_yaml::v17_to_v18(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 18
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Partial
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: null
+      children:
+      - 1
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  - title: child
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents:
+      - 0
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases: {}
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_v18_v19() {
+        let old = serde_yaml_ng::from_str::<Value>(
+            "
+version: 18
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Partial
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      alias: proj
+      children:
+      - 1
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  - title: child
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 1
+      alias: null
+      children: []
+      parents:
+      - 0
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases:
+    proj: 0
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        );
+
+        let new = old_yaml::v18_to_v19(&old.unwrap()).unwrap();
+        let new_should_be = serde_yaml_ng::from_str::<Value>(
+            "
+version: 19
+graph:
+  nodes:
+  - title: root
+    data: !Task
+      state: Partial
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 0
+      children:
+      - 1
+      parents: []
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+      aliases:
+      - proj
+    note: null
+  - title: child
+    data: !Task
+      state: Done
+      due: null
+      priority: None
+      estimate: null
+    metadata:
+      archived: false
+      index: 1
+      children: []
+      parents:
+      - 0
+      tags: []
+      created: null
+      modified: null
+      completed: null
+      blocked_by: []
+      blocks: []
+      time_log: []
+      propagation: null
+      aliases: []
+    note: null
+  roots:
+  - 0
+  archived: []
+  dates: {}
+  aliases:
+    proj: 0
+  tags: {}
+  recurrences: []
+  next_id: 2
+  timer: null
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            serde_yaml_ng::to_string(&new).unwrap(),
+            serde_yaml_ng::to_string(&new_should_be).unwrap()
+        );
+    }
 }
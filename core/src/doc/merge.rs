@@ -0,0 +1,555 @@
+//! Three-way merge of two copies of a save document that diverged from a common base.
+//!
+//! Nodes are matched across the copies by their IDs, which are never reused. Nodes added on both
+//! sides may have been handed the same IDs, so the ones added on their side are renumbered past
+//! ours. Every field changed on only one side is taken from that side, lists of links and tags
+//! keep the additions and removals of both, and fields changed differently on both sides keep
+//! our version and are reported as a [`Conflict`].
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::graph::node::{Node, NodeType};
+use crate::graph::recur::Recurrence;
+use crate::graph::validate::Problem;
+use crate::graph::Graph;
+
+/// A change made differently on both sides, resolved without asking
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Conflict {
+    /// Both sides changed the same field of a node. Ours was kept.
+    Field { index: usize, field: &'static str },
+    /// We removed a node they changed. Theirs was kept.
+    RemovedByUs { index: usize },
+    /// They removed a node we changed. Ours was kept.
+    RemovedByThem { index: usize },
+    /// Both sides gave the same alias to different nodes. The first node kept it.
+    Alias {
+        alias: String,
+        kept: usize,
+        dropped: usize,
+    },
+    /// Both sides started a different timer. Ours was kept.
+    Timer,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Field { index, field } => {
+                write!(
+                    f,
+                    "Both sides changed the {field} of node {index}, kept ours"
+                )
+            }
+            Conflict::RemovedByUs { index } => {
+                write!(
+                    f,
+                    "We removed node {index} but they changed it, kept theirs"
+                )
+            }
+            Conflict::RemovedByThem { index } => {
+                write!(f, "They removed node {index} but we changed it, kept ours")
+            }
+            Conflict::Alias {
+                alias,
+                kept,
+                dropped,
+            } => write!(
+                f,
+                "Both nodes {kept} and {dropped} were aliased '{alias}', kept it on {kept}"
+            ),
+            Conflict::Timer => write!(f, "Both sides started a timer, kept ours"),
+        }
+    }
+}
+
+/// The outcome of [`merge`]
+#[derive(Debug)]
+pub struct Merge {
+    pub graph: Graph,
+    /// Changes that clashed, in the order they were resolved
+    pub conflicts: Vec<Conflict>,
+    /// Inconsistencies the merged changes left behind, which were repaired
+    pub repaired: Vec<Problem>,
+}
+
+/// Merges a value changed on either side, or returns [`None`] if both changed it differently.
+fn merge_value<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours.clone())
+    } else if ours == base {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+/// Merges a list, keeping what either side added and dropping what either side removed. Our
+/// order is kept, followed by their additions.
+fn merge_list<T: PartialEq + Clone>(base: &[T], ours: &[T], theirs: &[T]) -> Vec<T> {
+    if let Some(list) = merge_value(&base, &ours, &theirs) {
+        return list.to_vec();
+    }
+    ours.iter()
+        .filter(|item| theirs.contains(item) || !base.contains(item))
+        .chain(
+            theirs
+                .iter()
+                .filter(|item| !ours.contains(item) && !base.contains(item)),
+        )
+        .cloned()
+        .collect()
+}
+
+/// Merges the recurrences, matching them by their rule, target and start. Recurrences kept on
+/// both sides count as materialized wherever either side materialized them.
+fn merge_recurrences(
+    base: &[Recurrence],
+    ours: &[Recurrence],
+    theirs: &[Recurrence],
+) -> Vec<Recurrence> {
+    let same = |a: &Recurrence, b: &Recurrence| {
+        a.rule == b.rule && a.target == b.target && a.start == b.start
+    };
+    let mut merged: Vec<Recurrence> = vec![];
+    for recurrence in ours.iter().chain(theirs) {
+        let kept = |side: &[Recurrence]| side.iter().any(|other| same(other, recurrence));
+        if kept(base) && !(kept(ours) && kept(theirs)) {
+            continue;
+        }
+        match merged.iter_mut().find(|other| same(other, recurrence)) {
+            Some(other) => {
+                other.synced_until = other.synced_until.max(recurrence.synced_until);
+                other.generated.extend(recurrence.generated.iter().copied());
+            }
+            None => merged.push(recurrence.clone()),
+        }
+    }
+    merged
+}
+
+/// Returns a copy of `graph` with the nodes in `ids` renumbered, along with every reference to
+/// them.
+fn renumber(graph: &Graph, ids: &HashMap<usize, usize>) -> Graph {
+    let id = |index: &mut usize| {
+        if let Some(new) = ids.get(index) {
+            *index = *new;
+        }
+    };
+
+    let mut graph = graph.clone();
+    for node in graph.nodes.iter_mut().flatten() {
+        let metadata = &mut node.metadata;
+        id(&mut metadata.index);
+        let lists = [
+            &mut metadata.parents,
+            &mut metadata.children,
+            &mut metadata.blocked_by,
+            &mut metadata.blocks,
+        ];
+        lists.into_iter().flatten().for_each(id);
+    }
+    graph.roots.iter_mut().for_each(id);
+    graph.archived.iter_mut().for_each(id);
+    graph.dates.values_mut().for_each(id);
+    graph.aliases.values_mut().for_each(id);
+    graph.tags.values_mut().flatten().for_each(id);
+    if let Some(timer) = &mut graph.timer {
+        id(&mut timer.index);
+    }
+    graph.next_id = graph
+        .next_id
+        .max(ids.values().max().map_or(0, |last| last + 1));
+    graph.reindex();
+    graph
+}
+
+fn node_of(graph: &Graph, index: usize) -> Option<Node> {
+    graph.node_checked(index).cloned()
+}
+
+/// Merges a node changed on both sides.
+fn merge_node(base: &Node, ours: &Node, theirs: &Node, conflicts: &mut Vec<Conflict>) -> Node {
+    let index = ours.metadata.index;
+    let mut field = |field: &'static str| {
+        conflicts.push(Conflict::Field { index, field });
+    };
+    macro_rules! merge {
+        ($name:literal, $($field:tt)+) => {
+            merge_value(&base.$($field)+, &ours.$($field)+, &theirs.$($field)+).unwrap_or_else(|| {
+                field($name);
+                ours.$($field)+.clone()
+            })
+        };
+    }
+
+    let mut node = ours.clone();
+    node.title = merge!("title", title);
+    node.note = merge!("note", note);
+    node.data = match (&base.data, &ours.data, &theirs.data) {
+        (NodeType::Task(b), NodeType::Task(o), NodeType::Task(t)) => {
+            let mut data = o.clone();
+            // A state derived from subtasks gets recomputed once they are merged
+            data.state = merge_value(&b.state, &o.state, &t.state).unwrap_or_else(|| {
+                if ours.metadata.children.is_empty() {
+                    field("state");
+                }
+                o.state
+            });
+            data.due = merge_value(&b.due, &o.due, &t.due).unwrap_or_else(|| {
+                field("due date");
+                o.due
+            });
+            data.priority =
+                merge_value(&b.priority, &o.priority, &t.priority).unwrap_or_else(|| {
+                    field("priority");
+                    o.priority
+                });
+            data.estimate =
+                merge_value(&b.estimate, &o.estimate, &t.estimate).unwrap_or_else(|| {
+                    field("estimate");
+                    o.estimate
+                });
+            NodeType::Task(data)
+        }
+        _ => merge!("type", data),
+    };
+
+    let metadata = &mut node.metadata;
+    metadata.archived = merge!("archival", metadata.archived);
+    metadata.propagation = merge!("completion policy", metadata.propagation);
+    metadata.parents = merge_list(
+        &base.metadata.parents,
+        &ours.metadata.parents,
+        &theirs.metadata.parents,
+    );
+    metadata.children = merge_list(
+        &base.metadata.children,
+        &ours.metadata.children,
+        &theirs.metadata.children,
+    );
+    metadata.blocked_by = merge_list(
+        &base.metadata.blocked_by,
+        &ours.metadata.blocked_by,
+        &theirs.metadata.blocked_by,
+    );
+    metadata.blocks = merge_list(
+        &base.metadata.blocks,
+        &ours.metadata.blocks,
+        &theirs.metadata.blocks,
+    );
+    metadata.aliases = merge_list(
+        &base.metadata.aliases,
+        &ours.metadata.aliases,
+        &theirs.metadata.aliases,
+    );
+    metadata.tags = merge_list(
+        &base.metadata.tags,
+        &ours.metadata.tags,
+        &theirs.metadata.tags,
+    );
+    metadata.time_log = merge_list(
+        &base.metadata.time_log,
+        &ours.metadata.time_log,
+        &theirs.metadata.time_log,
+    );
+    metadata.modified = ours.metadata.modified.max(theirs.metadata.modified);
+    // The completion time goes along with the state it was recorded for
+    let state = |node: &Node| node.data.as_task().map(|data| data.state);
+    if state(&node) != state(ours) {
+        node.metadata.completed = theirs.metadata.completed;
+    }
+    node
+}
+
+/// Moves the children of `from`, a date node for the same date as `into`, under `into` and
+/// removes `from`.
+fn fold_date(nodes: &mut HashMap<usize, Node>, from: usize, into: usize) {
+    let Some(from) = nodes.remove(&from) else {
+        return;
+    };
+    for child in &from.metadata.children {
+        if let Some(child) = nodes.get_mut(child) {
+            child.metadata.parents.retain(|i| *i != from.metadata.index);
+            if !child.metadata.parents.contains(&into) {
+                child.metadata.parents.push(into);
+            }
+        }
+    }
+    if let Some(into) = nodes.get_mut(&into) {
+        for child in from.metadata.children {
+            if !into.metadata.children.contains(&child) {
+                into.metadata.children.push(child);
+            }
+        }
+    }
+}
+
+/// Merges the changes made to `base` in `ours` and `theirs`.
+///
+/// # Returns
+/// The merged graph, which is consistent even if the changes clashed, along with how they
+/// clashed. The merged graph uses the same [`crate::graph::node::task::Propagation`] as ours.
+pub fn merge(base: &Graph, ours: &Graph, theirs: &Graph) -> Merge {
+    let mut conflicts = vec![];
+
+    // Renumber the nodes they added past ours
+    let first_free = ours.next_id.max(base.next_id);
+    let added: HashMap<usize, usize> = theirs
+        .node_ids()
+        .into_iter()
+        .filter(|index| *index >= base.next_id)
+        .map(|index| (index, first_free + index - base.next_id))
+        .collect();
+    let theirs = renumber(theirs, &added);
+
+    let mut ids: Vec<usize> = base
+        .slots
+        .keys()
+        .chain(ours.slots.keys())
+        .chain(theirs.slots.keys())
+        .copied()
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    // Nodes whose subtasks changed on one side, whose state has to be recomputed
+    let mut refresh = vec![];
+    let mut nodes: HashMap<usize, Node> = HashMap::new();
+    for &index in &ids {
+        let (b, o, t) = (
+            node_of(base, index),
+            node_of(ours, index),
+            node_of(&theirs, index),
+        );
+        let node = match (b, o, t) {
+            (_, o, t) if o == t => o,
+            (b, o, t) if o == b => t,
+            (b, o, t) if t == b => o,
+            (Some(b), Some(o), Some(t)) => {
+                let node = merge_node(&b, &o, &t, &mut conflicts);
+                let state = |node: &Node| node.data.as_task().map(|data| data.state);
+                let state_changed = state(&node) != state(&o) || state(&node) != state(&t);
+                if node.metadata.children != o.metadata.children
+                    || node.metadata.children != t.metadata.children
+                    || (state_changed && !node.metadata.children.is_empty())
+                {
+                    refresh.push(index);
+                }
+                if state_changed {
+                    refresh.extend(node.metadata.parents.iter().copied());
+                }
+                Some(node)
+            }
+            // Only if their copy didn't come from the base after all
+            (None, o @ Some(_), Some(_)) => {
+                conflicts.push(Conflict::Field {
+                    index,
+                    field: "contents",
+                });
+                o
+            }
+            (_, None, t) => {
+                conflicts.push(Conflict::RemovedByUs { index });
+                t
+            }
+            (_, o, None) => {
+                conflicts.push(Conflict::RemovedByThem { index });
+                o
+            }
+        };
+        if let Some(node) = node {
+            nodes.insert(index, node);
+        }
+    }
+
+    // Date nodes both sides added for the same day become one
+    let mut dates: HashMap<String, usize> = HashMap::new();
+    for &index in &ids {
+        let Some(date) = nodes
+            .get(&index)
+            .and_then(|node| node.data.as_date())
+            .map(|data| data.format_for_hashmap())
+        else {
+            continue;
+        };
+        match dates.get(&date) {
+            Some(&into) => {
+                fold_date(&mut nodes, index, into);
+                refresh.push(into);
+            }
+            None => {
+                dates.insert(date, index);
+            }
+        }
+    }
+
+    // Aliases go to the node that has them on our side first
+    let mut aliases: HashMap<String, usize> = HashMap::new();
+    let mut claimants: Vec<usize> = ours
+        .aliases
+        .values()
+        .copied()
+        .filter(|index| nodes.contains_key(index))
+        .collect();
+    claimants.sort_unstable();
+    let rest: Vec<usize> = ids
+        .iter()
+        .copied()
+        .filter(|index| !claimants.contains(index))
+        .collect();
+    claimants.extend(rest);
+    for index in claimants {
+        let Some(node) = nodes.get_mut(&index) else {
+            continue;
+        };
+        let mut dropped = vec![];
+        for alias in &node.metadata.aliases {
+            match aliases.get(alias) {
+                Some(&kept) if kept != index => {
+                    dropped.push(alias.clone());
+                    conflicts.push(Conflict::Alias {
+                        alias: alias.clone(),
+                        kept,
+                        dropped: index,
+                    });
+                }
+                _ => {
+                    aliases.insert(alias.clone(), index);
+                }
+            }
+        }
+        node.metadata
+            .aliases
+            .retain(|alias| !dropped.contains(alias));
+    }
+
+    let mut tags: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut archived = vec![];
+    for &index in &ids {
+        let Some(node) = nodes.get(&index) else {
+            continue;
+        };
+        for tag in &node.metadata.tags {
+            tags.entry(tag.clone()).or_default().push(index);
+        }
+        if node.metadata.archived {
+            archived.push(index);
+        }
+    }
+
+    let timer = merge_value(&base.timer, &ours.timer, &theirs.timer)
+        .unwrap_or_else(|| {
+            conflicts.push(Conflict::Timer);
+            ours.timer
+        })
+        .filter(|timer| nodes.contains_key(&timer.index));
+
+    let mut graph = Graph {
+        nodes: ids
+            .iter()
+            .filter_map(|index| nodes.remove(index))
+            .map(Some)
+            .collect(),
+        slots: HashMap::new(),
+        next_id: ours.next_id.max(theirs.next_id),
+        roots: merge_list(&base.roots, &ours.roots, &theirs.roots),
+        archived,
+        dates,
+        aliases,
+        tags,
+        recurrences: merge_recurrences(&base.recurrences, &ours.recurrences, &theirs.recurrences),
+        timer,
+        propagation: ours.propagation,
+        observers: Default::default(),
+    };
+    graph.reindex();
+
+    let mut repaired = vec![];
+    for problem in graph.validate() {
+        if graph.repair(&problem) {
+            repaired.push(problem);
+        }
+    }
+
+    let refresh: Vec<usize> = refresh
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .filter(|index| graph.node_checked(*index).is_some())
+        .collect();
+    // Never fails
+    let _ = graph.update_state_recurse_parents(&refresh);
+
+    Merge {
+        graph,
+        conflicts,
+        repaired,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::node::task::TaskState;
+    use crate::graph::{Graph, GraphGetters};
+
+    use super::{merge, Conflict};
+
+    #[test]
+    fn merge_diverged_copies() {
+        let mut base = Graph::new();
+        let root = base.insert_root("root".to_string(), false);
+        let a = base.insert_child("a".to_string(), root, false).unwrap();
+        let b = base.insert_child("b".to_string(), root, false).unwrap();
+        let c = base.insert_child("c".to_string(), root, false).unwrap();
+
+        let mut ours = base.clone();
+        ours.rename_node(a, "ours".to_string()).unwrap();
+        ours.set_task_state(b, TaskState::Done, true).unwrap();
+        ours.rename_node(c, "edited".to_string()).unwrap();
+        ours.set_task_state(c, TaskState::Done, true).unwrap();
+        let our_date = ours.get_or_insert_date("2025-01-01".parse().unwrap());
+        let our_task = ours
+            .insert_child("ours".to_string(), our_date, false)
+            .unwrap();
+
+        let mut theirs = base.clone();
+        theirs.rename_node(a, "theirs".to_string()).unwrap();
+        theirs.set_task_state(a, TaskState::Done, true).unwrap();
+        theirs.remove(c).unwrap();
+        let their_date = theirs.get_or_insert_date("2025-01-01".parse().unwrap());
+        theirs
+            .insert_child("theirs".to_string(), their_date, false)
+            .unwrap();
+
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(
+            merged.conflicts,
+            vec![
+                Conflict::Field {
+                    index: a,
+                    field: "title"
+                },
+                Conflict::RemovedByThem { index: c },
+            ]
+        );
+
+        let graph = merged.graph;
+        assert!(graph.validate().is_empty());
+        assert_eq!(graph.get_node(a).title, "ours");
+        assert_eq!(graph.get_node(c).title, "edited");
+        // Between them, every subtask got checked
+        let state = graph.get_node(root).data.as_task().unwrap().state;
+        assert_eq!(state, TaskState::Done);
+
+        // Both added a node with the same ID, and both added the same date
+        let date = graph.get_node(our_date);
+        assert_eq!(graph.get_dates().len(), 1);
+        assert_eq!(date.metadata.children.len(), 2);
+        assert_eq!(graph.get_node(our_task).title, "ours");
+        let their_task = date.metadata.children[1];
+        assert_ne!(their_task, our_task);
+        assert_eq!(graph.get_node(their_task).title, "theirs");
+    }
+}
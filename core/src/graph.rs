@@ -1,26 +1,109 @@
 pub mod errors;
+pub mod filter;
+pub mod journal;
 pub mod node;
+pub mod observer;
+pub mod path;
+pub mod query;
+pub mod recur;
+pub mod search;
+pub mod subgraph;
+pub mod time;
+pub mod validate;
 
-use std::cell::{RefCell, RefMut};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
 use serde::{Deserialize, Serialize};
 
 use errors::ErrorType;
+use filter::TraverseFilter;
 use node::{date::DateData, date::HashMapFormatter, task, Node, NodeType};
+use observer::{Event, Observers};
+use recur::{RecurTarget, Recurrence};
+use time::{Interval, Timer};
 
 /// Result of graph operation.
 type GraphResult<T> = Result<T, ErrorType>;
 
+/// A graph of nodes addressed by their IDs.
+///
+/// Node IDs are handed out in increasing order and never reused, even after a node is removed.
+/// They are separate from where a node is stored, so compacting the storage (see [`Graph::clean`])
+/// keeps every ID intact.
+///
+/// Nodes are only changed through `&mut self`, so a graph is `Send + Sync` and can be shared
+/// between threads behind an `Arc<RwLock<Graph>>`.
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
+#[serde(from = "GraphData")]
 pub struct Graph {
-    pub(crate) nodes: Vec<Option<RefCell<Node>>>,
+    /// Node storage. Removed nodes leave an empty slot behind until the graph is cleaned.
+    pub(crate) nodes: Vec<Option<Node>>,
+    /// Node ID to the slot in `nodes` holding it
+    #[serde(skip)]
+    pub(crate) slots: HashMap<usize, usize>,
+    /// The ID given to the next inserted node
+    pub(crate) next_id: usize,
     pub(crate) roots: Vec<usize>,
     pub(crate) archived: Vec<usize>,
     pub(crate) dates: HashMap<String, usize>,
     pub(crate) aliases: HashMap<String, usize>,
+    /// Tag name to the indices of the nodes carrying it
+    #[serde(default)]
+    pub(crate) tags: HashMap<String, Vec<usize>>,
+    /// Rules for tasks that get materialized under date nodes
+    #[serde(default)]
+    pub(crate) recurrences: Vec<Recurrence>,
+    /// The timer running on a node, if any
+    #[serde(default)]
+    pub(crate) timer: Option<Timer>,
+    /// How task states propagate through nodes without a propagation of their own. Not saved;
+    /// frontends set it from their configuration.
+    #[serde(skip)]
+    pub(crate) propagation: task::Propagation,
+    /// Notified of every change, see [`observer`]. Not saved.
+    #[serde(skip)]
+    pub(crate) observers: Observers,
+}
+
+/// Serialized form of a [`Graph`], from which the node slots are rebuilt
+#[derive(Deserialize)]
+struct GraphData {
+    nodes: Vec<Option<Node>>,
+    #[serde(default)]
+    next_id: usize,
+    roots: Vec<usize>,
+    archived: Vec<usize>,
+    dates: HashMap<String, usize>,
+    aliases: HashMap<String, usize>,
+    #[serde(default)]
+    tags: HashMap<String, Vec<usize>>,
+    #[serde(default)]
+    recurrences: Vec<Recurrence>,
+    #[serde(default)]
+    timer: Option<Timer>,
+}
+
+impl From<GraphData> for Graph {
+    fn from(data: GraphData) -> Self {
+        let mut graph = Self {
+            nodes: data.nodes,
+            slots: HashMap::new(),
+            next_id: data.next_id,
+            roots: data.roots,
+            archived: data.archived,
+            dates: data.dates,
+            aliases: data.aliases,
+            tags: data.tags,
+            recurrences: data.recurrences,
+            timer: data.timer,
+            propagation: task::Propagation::default(),
+            observers: Observers::default(),
+        };
+        graph.reindex();
+        graph
+    }
 }
 
 impl Graph {
@@ -28,19 +111,98 @@ impl Graph {
     pub fn new() -> Self {
         Self {
             nodes: vec![],
+            slots: HashMap::new(),
+            next_id: 0,
             roots: vec![],
             archived: vec![],
             dates: HashMap::new(),
             aliases: HashMap::new(),
+            tags: HashMap::new(),
+            recurrences: vec![],
+            timer: None,
+            propagation: task::Propagation::default(),
+            observers: Observers::default(),
+        }
+    }
+
+    /// Rebuilds the node ID to slot map from the stored nodes, and makes sure no ID already in
+    /// use (or, in documents from before IDs were separate from slots, once used) is handed out
+    /// again.
+    pub(crate) fn reindex(&mut self) {
+        self.slots = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, node)| Some((node.as_ref()?.metadata.index, slot)))
+            .collect();
+        let after_last = self.slots.keys().max().map_or(0, |id| id + 1);
+        self.next_id = self.next_id.max(after_last).max(self.nodes.len());
+    }
+
+    /// Returns the node with the given ID. Panics if there is none.
+    fn node(&self, id: usize) -> &Node {
+        self.node_checked(id).expect("node ID should be valid")
+    }
+
+    /// Returns the node with the given ID for changing it. Panics if there is none.
+    fn node_mut(&mut self, id: usize) -> &mut Node {
+        self.node_checked_mut(id).expect("node ID should be valid")
+    }
+
+    /// Returns the node with the given ID, if there is one.
+    pub(crate) fn node_checked(&self, id: usize) -> Option<&Node> {
+        self.nodes[*self.slots.get(&id)?].as_ref()
+    }
+
+    /// Returns the node with the given ID for changing it, if there is one.
+    pub(crate) fn node_checked_mut(&mut self, id: usize) -> Option<&mut Node> {
+        self.nodes[*self.slots.get(&id)?].as_mut()
+    }
+
+    /// Stores a new node and hands out its ID.
+    fn push_node(&mut self, build: impl FnOnce(usize) -> Node) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.slots.insert(id, self.nodes.len());
+        self.nodes.push(Some(build(id)));
+        self.emit(Event::NodeInserted { index: id });
+        id
+    }
+
+    /// Drops a node from storage, leaving its slot empty.
+    fn take_node(&mut self, id: usize) -> Option<Node> {
+        let slot = self.slots.remove(&id)?;
+        self.nodes[slot].take()
+    }
+
+    /// Stores a node under its own ID, replacing the node with that ID if there is one.
+    fn put_node(&mut self, node: Node) {
+        let id = node.metadata.index;
+        match self.slots.get(&id) {
+            Some(slot) => self.nodes[*slot] = Some(node),
+            None => {
+                self.slots.insert(id, self.nodes.len());
+                self.nodes.push(Some(node));
+            }
         }
     }
 
     /// Returns the number of alive nodes present in the graph
     /// Alive as in does NOT include deleted (`None`) nodes in the graph
     pub fn node_count(&self) -> usize {
-        self.nodes
-            .iter()
-            .fold(0, |acc, item| if item.is_some() { acc + 1 } else { acc })
+        self.slots.len()
+    }
+
+    /// Returns the ID the next inserted node will get
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
+
+    /// Returns the IDs of every node in the graph, in increasing order
+    pub fn node_ids(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self.slots.keys().copied().collect();
+        ids.sort_unstable();
+        ids
     }
 
     /// Returns the number of root nodes in the graph
@@ -53,8 +215,14 @@ impl Graph {
         self.aliases.len()
     }
 
-    /// Returns an immutable reference to the underlying nodes `Vec`
-    pub fn get_nodes(&self) -> &[Option<RefCell<Node>>] {
+    /// Returns the number of distinct tags in the graph
+    pub fn tag_count(&self) -> usize {
+        self.tags.len()
+    }
+
+    /// Returns an immutable reference to the underlying node storage. Note that slots are not
+    /// node IDs; removed nodes leave empty slots until the graph is cleaned.
+    pub fn get_nodes(&self) -> &[Option<Node>] {
         &self.nodes
     }
 
@@ -68,6 +236,16 @@ impl Graph {
         &self.aliases
     }
 
+    /// Returns an immutable reference to the underlying tags `HashMap`
+    pub fn get_tags(&self) -> &HashMap<String, Vec<usize>> {
+        &self.tags
+    }
+
+    /// Returns the indices of the nodes tagged with `tag`
+    pub fn get_tagged(&self, tag: &str) -> &[usize] {
+        self.tags.get(tag).map(Vec::as_slice).unwrap_or_default()
+    }
+
     /// Returns an immutable reference to the underlying dates `HashMap`
     pub fn get_dates(&self) -> &HashMap<String, usize> {
         &self.dates
@@ -78,6 +256,26 @@ impl Graph {
         &self.archived
     }
 
+    /// Runs `f` as a single change to the graph: if it fails, every change it made is undone and
+    /// the graph is left as it was. Transactions can be nested.
+    ///
+    /// # Returns
+    /// Whatever `f` returns.
+    pub fn transaction<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut Graph) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let before = self.clone();
+        let result = f(self);
+        if result.is_err() {
+            let observers = std::mem::take(&mut self.observers);
+            *self = before;
+            self.observers = observers;
+            self.emit(Event::Reset);
+        }
+        result
+    }
+
     /// Inserts a node into the graph and sets it as a root node.
     ///
     /// # Arguments
@@ -87,12 +285,13 @@ impl Graph {
     /// # Returns
     /// A usize containing the index of the newly added node.
     pub fn insert_root(&mut self, message: String, pseudo: bool) -> usize {
-        let idx = self.nodes.len();
-        let mut node = Node::new(message, idx, Default::default());
-        if pseudo {
-            node.data = NodeType::Pseudo;
-        }
-        self.nodes.push(Some(RefCell::new(node)));
+        let idx = self.push_node(|idx| {
+            let mut node = Node::new(message, idx, Default::default());
+            if pseudo {
+                node.data = NodeType::Pseudo;
+            }
+            node
+        });
         self.roots.push(idx);
         idx
     }
@@ -106,14 +305,20 @@ impl Graph {
     /// # Returns
     /// A usize containing the index of the newly added node.
     pub fn insert_date(&mut self, message: String, date: NaiveDate) -> usize {
-        let idx = self.nodes.len();
         let date_data = DateData { date };
-        let node = Node::new(message, idx, NodeType::Date(date_data.clone()));
-        self.nodes.push(Some(RefCell::new(node)));
+        let idx = self.push_node(|idx| Node::new(message, idx, NodeType::Date(date_data.clone())));
         self.dates.insert(date_data.format_for_hashmap(), idx);
         idx
     }
 
+    /// Returns the index of the date node for `date`, inserting an unlabeled one if there is none.
+    pub fn get_or_insert_date(&mut self, date: NaiveDate) -> usize {
+        match self.get_date_index(&date) {
+            Ok(idx) => idx,
+            Err(_) => self.insert_date(String::new(), date),
+        }
+    }
+
     /// Inserts a node into the graph and sets it as a child of a parent node without updating the
     /// states of its parent. The parent node is represented using its node index.
     ///
@@ -130,12 +335,13 @@ impl Graph {
         parent: usize,
         pseudo: bool,
     ) -> usize {
-        let idx = self.nodes.len();
-        let mut node = Node::new(message, idx, Default::default());
-        if pseudo {
-            node.data = NodeType::Pseudo;
-        }
-        self.nodes.push(Some(RefCell::new(node)));
+        let idx = self.push_node(|idx| {
+            let mut node = Node::new(message, idx, Default::default());
+            if pseudo {
+                node.data = NodeType::Pseudo;
+            }
+            node
+        });
         self.link_unchecked(parent, idx);
         idx
     }
@@ -158,7 +364,7 @@ impl Graph {
     ) -> GraphResult<usize> {
         let idx = self.insert_child_unchecked(message, parent, pseudo);
         if !pseudo {
-            self.update_state_recurse_parents(&[parent] as *const _, 1)?;
+            self.update_state_recurse_parents(&[parent])?;
         }
         Ok(idx)
     }
@@ -168,102 +374,50 @@ impl Graph {
         // Remove node if it was root
         self.roots.retain(|i| *i != index);
 
-        // Unset alias
-        let alias = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .alias
-            .is_some();
-        if alias {
-            self.unset_alias(index)?;
-        }
+        self.clear_aliases(index)?;
+        self.clear_tags(index)?;
+        self.clear_dependencies(index);
+        self.discard_timer(index);
 
         // Delete from date hashmap first if node is a date root node
-        if let NodeType::Date(data) = &self.nodes[index].as_ref().unwrap().borrow().data {
-            self.dates.remove(&data.date.hashmap_format());
+        let date = self.node(index).data.as_date().map(|data| data.date);
+        if let Some(date) = date {
+            self.dates.remove(&date.hashmap_format());
         }
 
-        if self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .archived
-        {
+        if self.node(index).metadata.archived {
             self.archived
                 .remove(self.archived.iter().position(|x| *x == index).unwrap());
         }
 
         // Unlink node from parents and children
-        let parents_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-        let parents_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
-        for i in 0..parents_len {
-            let parent = unsafe { *parents_ptr.add(i) };
-            self.nodes[parent]
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
+        let parents = self.node(index).metadata.parents.clone();
+        for &parent in parents.iter() {
+            self.node_mut(parent)
                 .metadata
                 .children
                 .retain(|i| *i != index);
         }
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
+        self.update_state_recurse_parents(&parents)?;
 
-        let children_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .as_ptr();
-        let children_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .len();
-        for i in 0..children_len {
-            let child = unsafe { *children_ptr.add(i) };
-            self.nodes[child]
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
+        let children = self.node(index).metadata.children.clone();
+        for &child in children.iter() {
+            self.node_mut(child)
                 .metadata
                 .parents
                 .retain(|i| *i != index);
-            if self.nodes[child]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .parents
-                .is_empty()
-            {
+            if self.node(child).metadata.parents.is_empty() {
                 // Since they're now parentless, make them root.
                 // This is only applicable to non-date nodes.
                 // Delete from date hashmap first if node is a date root node
-                if !&self.nodes[child].as_ref().unwrap().borrow().data.is_date() {
+                if !&self.node(child).data.is_date() {
                     self.roots.push(child);
                 }
             }
         }
 
-        self.nodes[index] = None;
+        self.take_node(index);
+        self.emit(Event::NodeRemoved { index });
         Ok(())
     }
 
@@ -275,561 +429,563 @@ impl Graph {
     }
 
     fn _remove_children_recursive(&mut self, index: usize) -> GraphResult<()> {
-        let parents_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-        let parents_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
-        for i in 0..parents_len {
-            let parent = unsafe { *parents_ptr.add(i) };
-            self.nodes[parent]
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
+        let parents = self.node(index).metadata.parents.clone();
+        for &parent in parents.iter() {
+            self.node_mut(parent)
                 .metadata
                 .children
                 .retain(|i| *i != index);
         }
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
-        let children_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .as_ptr();
-        let children_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .len();
-        for i in 0..children_len {
-            let child = unsafe { *children_ptr.add(i) };
-            self.nodes[child]
-                .as_ref()
-                .unwrap()
-                .borrow_mut()
+        self.update_state_recurse_parents(&parents)?;
+        let children = self.node(index).metadata.children.clone();
+        for &child in children.iter() {
+            self.node_mut(child)
                 .metadata
                 .parents
                 .retain(|i| *i != index);
             self._remove_children_recursive(child)?;
         }
 
-        let alias = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .alias
-            .is_some();
-        if alias {
-            self.unset_alias(index)?;
-        }
+        self.clear_aliases(index)?;
+        self.clear_tags(index)?;
+        self.clear_dependencies(index);
+        self.discard_timer(index);
 
         // Delete from date hashmap first if node is a date root node
-        if let NodeType::Date(data) = &self.nodes[index].as_ref().unwrap().borrow().data {
-            self.dates.remove(&data.date.hashmap_format());
+        let date = self.node(index).data.as_date().map(|data| data.date);
+        if let Some(date) = date {
+            self.dates.remove(&date.hashmap_format());
         }
 
-        if self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .archived
-        {
+        if self.node(index).metadata.archived {
             self.archived
                 .remove(self.archived.iter().position(|x| *x == index).unwrap());
         }
 
-        self.nodes[index] = None;
+        self.take_node(index);
+        self.emit(Event::NodeRemoved { index });
         Ok(())
     }
 
     /// Connects two nodes on the graph with an edge
     /// Does NOT update parent states
     pub fn link_unchecked(&mut self, from: usize, to: usize) {
-        self.nodes[from]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .children
-            .push(to);
-        self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .parents
-            .push(from);
+        self.node_mut(from).metadata.children.push(to);
+        self.node_mut(to).metadata.parents.push(from);
         // Remove node from list of roots if it has a parent
         self.roots.retain(|i| *i != to);
+        self.emit(Event::Linked {
+            parent: from,
+            child: to,
+        });
     }
 
     /// Connects two nodes on the graph with an edge
     /// And updates the parents' states recursively
+    ///
+    /// Fails with [`ErrorType::LinkCycle`] if `to` is `from` or one of its ancestors.
     pub fn link(&mut self, from: usize, to: usize) -> GraphResult<()> {
+        if let Some(path) = self.link_cycle(from, to) {
+            return Err(ErrorType::LinkCycle(path));
+        }
         self.link_unchecked(from, to);
 
         // Update parent completion
-        let parents_ptr = self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-        let parents_len = self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
+        let parents = self.node(to).metadata.parents.clone();
+        self.update_state_recurse_parents(&parents)?;
 
         Ok(())
     }
 
+    /// Returns whether making `to` a child of `from` would form a cycle, i.e. whether `from` is
+    /// `to` itself or one of its descendants.
+    pub fn would_create_cycle(&self, from: usize, to: usize) -> bool {
+        self.link_cycle(from, to).is_some()
+    }
+
+    /// Returns the cycle that making `to` a child of `from` would form, starting and ending with
+    /// `to`, or [`None`] if the link is safe.
+    pub fn link_cycle(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        let mut path = self.find_path(to, from, |node| &node.metadata.children)?;
+        path.push(to);
+        Some(path)
+    }
+
+    /// Returns the ancestors of a node from the top of the graph down to its parent, following
+    /// each node's first parent.
+    pub fn ancestor_path(&self, index: usize) -> Vec<usize> {
+        let mut path = vec![];
+        let mut current = index;
+        while let Some(&parent) = self.node(current).metadata.parents.first() {
+            if parent == index || path.contains(&parent) {
+                break;
+            }
+            path.push(parent);
+            current = parent;
+        }
+        path.reverse();
+        path
+    }
+
     /// Unlinks two nodes on the graph without updating parent states
     fn unlink_unchecked(&mut self, from: usize, to: usize) {
-        self.nodes[from]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .children
-            .retain(|i| *i != to);
-        self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .parents
-            .retain(|i| *i != from);
+        self.node_mut(from).metadata.children.retain(|i| *i != to);
+        self.node_mut(to).metadata.parents.retain(|i| *i != from);
         // Add node to list of roots if it does not have a parent
-        if self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .is_empty()
-        {
-            // This is only applicable to non-date nodes.
-            if let NodeType::Task(_) = self.nodes[to].as_ref().unwrap().borrow().data {
-                self.roots.push(to);
-            } else if let NodeType::Pseudo = self.nodes[to].as_ref().unwrap().borrow().data {
-                self.roots.push(to);
-            }
+        let node = self.node(to);
+        // This is only applicable to non-date nodes.
+        if node.metadata.parents.is_empty() && !node.data.is_date() {
+            self.roots.push(to);
         }
+        self.emit(Event::Unlinked {
+            parent: from,
+            child: to,
+        });
     }
 
     /// Unlinks two nodes on the graph
     /// And updates parent states
     pub fn unlink(&mut self, from: usize, to: usize) -> GraphResult<()> {
-        let parents_ptr = self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-        let parents_len = self.nodes[to]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
+        let parents = self.node(to).metadata.parents.clone();
         self.unlink_unchecked(from, to);
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
+        self.update_state_recurse_parents(&parents)?;
         Ok(())
     }
 
     /// Clear parents of target node and other nodes that hold the target as their child
     pub fn clean_parents(&mut self, index: usize) -> GraphResult<()> {
-        let parents_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-
-        let parents_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
+        let parents = self.node(index).metadata.parents.clone();
 
-        self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .parents
-            .iter()
-            .for_each(|i| {
-                self.nodes[*i]
-                    .as_ref()
-                    .unwrap()
-                    .borrow_mut()
-                    .metadata
-                    .children
-                    .retain(|x| *x != index);
+        for &i in &parents {
+            self.node_mut(i).metadata.children.retain(|x| *x != index);
+            self.emit(Event::Unlinked {
+                parent: i,
+                child: index,
             });
+        }
 
-        self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .parents
-            .clear();
+        self.node_mut(index).metadata.parents.clear();
 
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
+        self.update_state_recurse_parents(&parents)?;
 
         Ok(())
     }
 
-    /// Sets task node state and optionally propogates changes to children and parents
+    /// Sets how task states propagate through nodes without a propagation of their own
+    pub fn set_propagation(&mut self, propagation: task::Propagation) {
+        self.propagation = propagation;
+    }
+
+    /// Returns how task states propagate through a node
+    pub fn propagation_of(&self, index: usize) -> task::Propagation {
+        self.node(index)
+            .metadata
+            .propagation
+            .unwrap_or(self.propagation)
+    }
+
+    /// Sets how task states propagate through a node, or makes it follow the graph's propagation
+    /// again if [`None`]
+    pub fn set_node_propagation(
+        &mut self,
+        index: usize,
+        propagation: Option<task::Propagation>,
+    ) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        if !node.data.is_task() {
+            return Err(ErrorType::NotTaskNode(index));
+        }
+        node.metadata.propagation = propagation;
+        node.touch();
+        self.emit(Event::NodeChanged { index });
+        Ok(())
+    }
+
+    /// Sets task node state and optionally propogates changes to children and parents, as allowed
+    /// by their [`task::Propagation`]
     pub fn set_task_state(
         &mut self,
         index: usize,
         state: task::TaskState,
         propogate: bool,
     ) -> GraphResult<()> {
-        match self.nodes[index].as_ref().unwrap().borrow_mut().data {
-            NodeType::Task(ref mut d) => d.state = state,
-            _ => return Err(ErrorType::NotTaskNode(index)),
-        };
+        let changed = self.change_task_state(index, state);
+        if changed.is_none() {
+            return Err(ErrorType::NotTaskNode(index));
+        }
 
         if !propogate {
             return Ok(());
         }
 
-        let children_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .as_ptr();
-        let children_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .len();
-        self.set_task_state_recurse(children_ptr, children_len, state)?;
-        let parents_ptr = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .as_ptr();
-        let parents_len = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow()
-            .metadata
-            .parents
-            .len();
-        self.update_state_recurse_parents(parents_ptr, parents_len)?;
+        if state.cascades() && self.propagation_of(index).cascade {
+            let children = self.node(index).metadata.children.clone();
+            self.set_task_state_recurse(&children, state)?;
+        }
+        let parents = self.node(index).metadata.parents.clone();
+        self.update_state_recurse_parents(&parents)?;
         Ok(())
     }
 
+    /// Sets the state of a single task node and reports the change, see
+    /// [`Node::set_task_state`]
+    fn change_task_state(&mut self, index: usize, state: task::TaskState) -> Option<bool> {
+        let node = self.node_mut(index);
+        let from = node.data.as_task().map(|data| data.state)?;
+        let changed = node.set_task_state(state);
+        if changed == Some(true) {
+            self.emit(Event::StateChanged {
+                index,
+                from,
+                to: state,
+            });
+        }
+        changed
+    }
+
     // Absolute
     fn set_task_state_recurse(
         &mut self,
-        indices: *const usize,
-        len: usize,
+        indices: &[usize],
         state: task::TaskState,
     ) -> GraphResult<()> {
-        for i in 0..len {
-            let i = unsafe { *indices.add(i) };
-
-            if self.nodes[i].as_ref().unwrap().borrow().data == NodeType::Pseudo {
+        for &i in indices {
+            if self.node(i).data == NodeType::Pseudo {
+                continue;
+            }
+            // Cancelling a task doesn't undo the parts of it that were done
+            let done = self
+                .node(i)
+                .data
+                .as_task()
+                .is_some_and(|data| data.state == task::TaskState::Done);
+            if state == task::TaskState::Cancelled && done {
                 continue;
             }
 
-            match self.nodes[i].as_ref().unwrap().borrow_mut().data {
-                NodeType::Task(ref mut d) => d.state = state,
-                _ => return Err(ErrorType::NotTaskNode(i)),
-            };
+            let changed = self.change_task_state(i, state);
+            if changed.is_none() {
+                return Err(ErrorType::NotTaskNode(i));
+            }
 
-            let children_ptr = self.nodes[i]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .children
-                .as_ptr();
-            let children_len = self.nodes[i]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .children
-                .len();
-            self.set_task_state_recurse(children_ptr, children_len, state)?;
+            if self.propagation_of(i).cascade {
+                let children = self.node(i).metadata.children.clone();
+                self.set_task_state_recurse(&children, state)?;
+            }
 
-            let parents_ptr = self.nodes[i]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .parents
-                .as_ptr();
-            let parents_len = self.nodes[i]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .parents
-                .len();
-            self.update_state_recurse_parents(parents_ptr, parents_len)?;
+            let parents = self.node(i).metadata.parents.clone();
+            self.update_state_recurse_parents(&parents)?;
         }
         Ok(())
     }
 
     // Check individually (because partially completed state)
-    fn update_state_recurse_parents(
-        &mut self,
-        indices: *const usize,
-        len: usize,
-    ) -> GraphResult<()> {
+    pub(crate) fn update_state_recurse_parents(&mut self, indices: &[usize]) -> GraphResult<()> {
         use task::TaskState;
 
-        for i in 0..len {
-            let i = unsafe { *indices.add(i) };
+        for &i in indices {
             let mut count = 0;
+            // Children that don't count towards completion
             let mut pseudo = 0;
             let mut partial = false;
-            for child in self.nodes[i]
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .metadata
-                .children
-                .iter()
-            {
-                let node = self.nodes[*child].as_ref().unwrap().borrow();
+            for child in self.node(i).metadata.children.iter() {
+                let node = self.node(*child);
                 match &node.data {
                     NodeType::Pseudo => {
                         pseudo += 1;
                     }
                     NodeType::Task(data) => match data.state {
-                        TaskState::None => continue,
-                        TaskState::Partial => {
+                        TaskState::None | TaskState::Blocked | TaskState::Waiting => continue,
+                        TaskState::Partial | TaskState::InProgress => {
                             partial = true;
                         }
                         TaskState::Done => {
                             partial = true;
                             count += 1;
                         }
+                        TaskState::Cancelled => {
+                            pseudo += 1;
+                        }
                     },
                     _ => {} // Other node types should not count towards completion
                 }
             }
 
-            let mut current = self.nodes[i].as_ref().unwrap().borrow_mut();
-            let completed = count > 0 && count == current.metadata.children.len() - pseudo;
+            let rollup = self.propagation_of(i).rollup;
+            if rollup == task::Rollup::Manual {
+                // The state didn't change, so neither do the parents'
+                continue;
+            }
 
-            if let Some(task) = current.data.as_task_mut() {
-                // Every child task is completed
-                task.state = if completed {
-                    TaskState::Done
-                // At least one child task is completed or partially completed
-                } else if partial {
-                    TaskState::Partial
-                } else {
-                    TaskState::None
-                };
+            let current = self.node(i);
+            let completed = count > 0 && count == current.metadata.children.len() - pseudo;
+            let previous = current.data.as_task().map(|data| data.state);
+            let is_pseudo = current.data.is_pseudo();
+            let parents = current.metadata.parents.clone();
+
+            // Every child task is completed
+            let state = if completed
+                && previous != Some(TaskState::Cancelled)
+                && (rollup == task::Rollup::Auto || previous == Some(TaskState::Done))
+            {
+                TaskState::Done
+            // Explicitly set states stay until the task is done
+            } else if let Some(state) = previous.filter(|state| state.is_explicit()) {
+                state
+            // At least one child task is completed or partially completed
+            } else if partial {
+                TaskState::Partial
+            } else {
+                TaskState::None
             };
+            // Does nothing for non-task nodes
+            self.change_task_state(i, state);
 
-            if current.data.is_pseudo() {
+            if is_pseudo {
                 // No need to recurse for pseudo nodes as they do not affect parent status
                 continue;
             }
 
-            let parents_ptr = current.metadata.parents.as_ptr();
-            let parents_len = current.metadata.parents.len();
-
-            // Drop now or else the binding gets dropped at the end of the scope.
-            // And as we need to mutably borrow self for updating parent states,
-            // this causes borrowck to cry in agony
-            std::mem::drop(current);
-
-            self.update_state_recurse_parents(parents_ptr, parents_len)?;
+            self.update_state_recurse_parents(&parents)?;
         }
         Ok(())
     }
 
     pub fn set_archived(&mut self, index: usize, archived: bool) -> GraphResult<()> {
-        let status = &mut self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .archived;
+        let node = self.node_mut(index);
+        if node.metadata.archived == archived {
+            return Ok(());
+        }
+
+        // Update node archive status
+        node.metadata.archived = archived;
+        node.touch();
 
         // Add to list of archived nodes if necessary
-        if *status != archived {
-            if archived {
-                self.archived.push(index);
-            } else {
-                self.archived.retain(|i| *i != index);
-            }
+        if archived {
+            self.archived.push(index);
+        } else {
+            self.archived.retain(|i| *i != index);
         }
+        self.emit(Event::Archived { index, archived });
 
-        // Update node archive status
-        *status = archived;
+        Ok(())
+    }
+
+    /// Sets or clears (with `None`) a task node's due date
+    pub fn set_task_due(&mut self, index: usize, due: Option<task::DueDate>) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        match node.data {
+            NodeType::Task(ref mut d) => d.due = due,
+            _ => return Err(ErrorType::NotTaskNode(index)),
+        };
+        node.touch();
+        self.emit(Event::NodeChanged { index });
+        Ok(())
+    }
+
+    /// Sets a task node's priority
+    pub fn set_task_priority(&mut self, index: usize, priority: task::Priority) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        match node.data {
+            NodeType::Task(ref mut d) => d.priority = priority,
+            _ => return Err(ErrorType::NotTaskNode(index)),
+        };
+        node.touch();
+        self.emit(Event::NodeChanged { index });
+        Ok(())
+    }
+
+    /// Sets a task node's effort estimate
+    pub fn set_task_estimate(&mut self, index: usize, estimate: Option<u32>) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        match node.data {
+            NodeType::Task(ref mut d) => d.estimate = estimate,
+            _ => return Err(ErrorType::NotTaskNode(index)),
+        };
+        node.touch();
+        self.emit(Event::NodeChanged { index });
+        Ok(())
+    }
+
+    /// Returns how much of a node's effort is done.
+    ///
+    /// The progress of a node with child tasks is that of its children, each weighted by its
+    /// estimate. Pseudo nodes don't count towards it. A task without child tasks is either fully
+    /// done or not at all, and a done task is always fully done.
+    ///
+    /// # Returns
+    /// [`None`] if the node is not a task and has no child tasks.
+    pub fn progress(&self, index: usize) -> Option<task::Progress> {
+        self.progress_memo(index, &mut HashMap::new())
+    }
+
+    fn progress_memo(
+        &self,
+        index: usize,
+        memo: &mut HashMap<usize, Option<task::Progress>>,
+    ) -> Option<task::Progress> {
+        if let Some(progress) = memo.get(&index) {
+            return *progress;
+        }
+
+        let node = self.node(index);
+        let mut done = 0.0;
+        let mut total = 0;
+        let mut counted = false;
+        for &child in node.metadata.children.iter() {
+            let estimate = match &self.node(child).data {
+                NodeType::Task(data) if data.state != task::TaskState::Cancelled => data.estimate,
+                _ => continue,
+            };
+            let Some(progress) = self.progress_memo(child, memo) else {
+                continue;
+            };
+            let weight = estimate.unwrap_or(progress.total);
+            done += progress.fraction() * weight as f64;
+            total += weight;
+            counted = true;
+        }
+
+        let children = task::Progress { done, total };
+        let progress = match node.data.as_task() {
+            Some(data) => {
+                let fraction = if data.state == task::TaskState::Done {
+                    1.0
+                } else {
+                    children.fraction()
+                };
+                let total = data.estimate.unwrap_or(if counted { total } else { 1 });
+                Some(task::Progress {
+                    done: fraction * total as f64,
+                    total,
+                })
+            }
+            None if counted => Some(children),
+            None => None,
+        };
+        memo.insert(index, progress);
+        progress
+    }
 
+    /// Returns the indices of tasks completed at or after `since`, in order of completion.
+    pub fn completed_since(&self, since: NaiveDateTime) -> Vec<usize> {
+        let mut completed: Vec<(NaiveDateTime, usize)> = self
+            .nodes
+            .iter()
+            .flatten()
+            .filter_map(|node| {
+                node.metadata
+                    .completed
+                    .filter(|completed| *completed >= since)
+                    .map(|completed| (completed, node.metadata.index))
+            })
+            .collect();
+        completed.sort();
+        completed.into_iter().map(|(_, index)| index).collect()
+    }
+
+    /// Returns the priority of a node. Non-task nodes have no priority.
+    pub fn priority_of(&self, index: usize) -> task::Priority {
+        self.node(index)
+            .data
+            .as_task()
+            .map(|task| task.priority)
+            .unwrap_or_default()
+    }
+
+    /// Sets or clears a node's note. Blank notes are cleared.
+    pub fn set_note(&mut self, index: usize, note: Option<String>) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        node.note = note.filter(|note| !note.trim().is_empty());
+        node.touch();
+        self.emit(Event::NodeChanged { index });
         Ok(())
     }
 
     /// Replaces a node on the graph's message with a new provided message
     pub fn rename_node(&mut self, index: usize, message: String) -> GraphResult<()> {
-        self.nodes[index].as_ref().unwrap().borrow_mut().title = message;
+        let node = self.node_mut(index);
+        node.title = message;
+        node.touch();
+        self.emit(Event::NodeChanged { index });
         Ok(())
     }
 
-    /// Fixes desynchronized or invalid links, and remaps nodes
+    /// Fixes desynchronized or invalid links, and compacts the node storage
     /// ~ The fixer-upper method
     pub fn clean(&mut self) {
         // Clears root tracked properties and resynchronizes them based on local node states
         self.dates.clear();
         self.aliases.clear();
+        self.tags.clear();
         self.archived.clear();
 
-        for node in self.nodes.iter() {
-            if node.is_none() {
-                continue;
+        for node in self.nodes.iter_mut().flatten() {
+            // Add aliases, tags, dates, and archival status
+            for alias in node.metadata.aliases.iter() {
+                self.aliases.insert(alias.clone(), node.metadata.index);
             }
-            let node = node.as_ref().unwrap();
-            let rnode = node.borrow();
-
-            // Add aliases, dates, and archival status
-            if rnode.metadata.alias.is_some() {
-                self.aliases.insert(
-                    rnode.metadata.alias.as_ref().unwrap().clone(),
-                    rnode.metadata.index,
-                );
+            for tag in node.metadata.tags.iter() {
+                self.tags
+                    .entry(tag.clone())
+                    .or_default()
+                    .push(node.metadata.index);
             }
-            if let NodeType::Date(data) = &rnode.data {
+            if let NodeType::Date(data) = &node.data {
                 self.dates
-                    .insert(data.format_for_hashmap(), rnode.metadata.index);
+                    .insert(data.format_for_hashmap(), node.metadata.index);
             }
 
-            if rnode.metadata.archived {
-                self.archived.push(rnode.metadata.index);
+            if node.metadata.archived {
+                self.archived.push(node.metadata.index);
             }
 
             // Remove invalid edges
-            drop(rnode);
-            let mut mnode = node.borrow_mut();
-            mnode.metadata.parents.retain(|i| self.nodes[*i].is_some());
-            mnode.metadata.children.retain(|i| self.nodes[*i].is_some());
+            let metadata = &mut node.metadata;
+            metadata.parents.retain(|i| self.slots.contains_key(i));
+            metadata.children.retain(|i| self.slots.contains_key(i));
+            metadata.blocked_by.retain(|i| self.slots.contains_key(i));
+            metadata.blocks.retain(|i| self.slots.contains_key(i));
         }
 
         // Add unreachable nodes into roots
         // Nodes without parents, are not root, and not date
         self.roots.clear();
         let date_values: Vec<_> = self.dates.values().collect();
-        for (i, node) in self.nodes.iter().enumerate() {
-            if node.is_none() {
-                continue;
-            }
-            let node = node.as_ref().unwrap();
-            let rnode = node.borrow();
-
-            let parents = rnode.metadata.parents.len();
+        for node in self.nodes.iter().flatten() {
+            let parents = node.metadata.parents.len();
             if parents > 0 {
                 continue;
             }
-            let index = rnode.metadata.index;
+            let index = node.metadata.index;
             if !self.roots.contains(&index) && !date_values.contains(&&index) {
-                self.roots.push(i);
+                self.roots.push(index);
             }
         }
 
-        // Remaps old indices to new indices
-        // Compress by ignoring unused indices
+        // Compact the storage by dropping empty slots. Node IDs are left untouched.
         //
         // Example:
         // Old [Some(a), None, Some(b), Some(c), None, Some(d)]
-        // Map indices [0->0, 2->1, 3->2, 4->3]
         // New [Some(a), Some(b), Some(c), Some(d)]
-        let mut map = Vec::with_capacity(self.nodes.len());
-        let mut last_used_index: usize = 0;
-        for node in &self.nodes {
-            match node {
-                None => map.push(None), // Ignored sentinel value
-                Some(_) => {
-                    map.push(Some(last_used_index));
-                    last_used_index += 1;
-                }
-            }
-        }
-
-        // Add nodes, aliases, roots, dates, and archival status
-        let mut new_graph = Graph::new();
-        for node in self.nodes.iter() {
-            match node {
-                None => continue,
-                Some(node) => {
-                    let mut new_node = node.borrow().clone();
-                    new_node.map_indices(&map);
-                    new_graph.nodes.push(Some(RefCell::new(new_node)));
-                }
-            }
-        }
-        for (alias, idx) in self.aliases.iter() {
-            new_graph.aliases.insert(alias.clone(), map[*idx].unwrap());
-        }
-        for r in self.roots.iter() {
-            new_graph.roots.push(map[*r].unwrap());
-        }
-        for (d, i) in self.dates.iter() {
-            new_graph.dates.insert(d.clone(), map[*i].unwrap());
-        }
-        for a in self.archived.iter() {
-            new_graph.archived.push(map[*a].unwrap());
-        }
-
-        // Replace self with new cleaned and fixed graph
-        *self = new_graph;
-    }
+        self.nodes.retain(Option::is_some);
+        self.reindex();
+        self.emit(Event::Reset);
+    }
 
     /// Call a closure that takes a node, with given index.
     pub fn with_node(&self, index: usize, f: &mut impl FnMut(&Node)) {
-        let node = self.nodes[index].as_ref().unwrap().borrow();
-        f(&node);
+        f(self.node(index));
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn _traverse_recurse(
         &self,
         indices: &[usize],
         show_archived: bool,
         max_depth: u32,
+        filter: &TraverseFilter,
         depth: u32,
         start: Option<usize>,
         child_of_last: bool,
@@ -846,13 +1002,12 @@ impl Graph {
         // for example, an entry is not actually the last entry, but it is rendered as last because
         // the actual last entry is archived. this will make the arm look wrong (not using the last
         // arm icon).
-        let indices: Vec<usize> = indices
+        let mut indices: Vec<usize> = indices
             .iter()
-            .filter(|i| {
-                !self.nodes[**i].as_ref().unwrap().borrow().metadata.archived || show_archived
-            })
+            .filter(|i| !self.node(**i).metadata.archived || show_archived)
             .copied()
             .collect();
+        filter.apply(self, &mut indices);
 
         for (i, idx) in indices.iter().enumerate() {
             if let Some(start) = start {
@@ -865,8 +1020,8 @@ impl Graph {
 
             let child_of_last = if last { true } else { child_of_last };
 
-            if let Some(node) = &self.nodes[*idx] {
-                f(&node.borrow(), depth, last, skipped_depths);
+            if let Some(node) = self.node_checked(*idx) {
+                f(node, depth, last, skipped_depths);
             }
 
             if last {
@@ -876,15 +1031,10 @@ impl Graph {
             *last_depth = depth + 1;
 
             self._traverse_recurse(
-                self.nodes[*idx]
-                    .as_ref()
-                    .unwrap()
-                    .borrow()
-                    .metadata
-                    .children
-                    .as_slice(),
+                self.node(*idx).metadata.children.as_slice(),
                 false,
                 max_depth,
+                filter,
                 depth + 1,
                 start,
                 child_of_last,
@@ -911,11 +1061,31 @@ impl Graph {
         show_archived: bool,
         max_depth: u32,
         f: &mut impl FnMut(&Node, u32, bool, &[u32]),
+    ) -> GraphResult<()> {
+        self.traverse_recurse_filtered(
+            indices,
+            show_archived,
+            max_depth,
+            &TraverseFilter::default(),
+            f,
+        )
+    }
+
+    /// Same as [`Graph::traverse_recurse`], but filters and orders every level of siblings using
+    /// `filter`.
+    pub fn traverse_recurse_filtered(
+        &self,
+        indices: &[usize],
+        show_archived: bool,
+        max_depth: u32,
+        filter: &TraverseFilter,
+        f: &mut impl FnMut(&Node, u32, bool, &[u32]),
     ) -> GraphResult<()> {
         self._traverse_recurse(
             indices,
             show_archived,
             max_depth,
+            filter,
             1,
             None,
             false,
@@ -927,19 +1097,28 @@ impl Graph {
 
     // TODO: returning an Option may make more sense?
     /// Returns the node index based on a identifier string.
-    /// The identifier string may be an alias, or an index. For dates node accessing, use the
-    /// `get_date_index` method.
+    /// The identifier string may be an alias, an index, or a path (see [`path`]). An index can
+    /// be written `#12` and an alias `:proj`, to leave no doubt about which is meant. For dates
+    /// node accessing, use the `get_date_index` method.
     pub fn get_index(&self, id: &str) -> GraphResult<usize> {
         // Check if it is an alias and if so return its corresponding index
         if let Some(x) = self.aliases.get(id) {
             return Ok(*x);
         }
+        if let Some(alias) = id.strip_prefix(':') {
+            return self.resolve_path(&format!("alias:{alias}"));
+        }
+        if path::is_path(id) {
+            return self.resolve_path(id);
+        }
 
         // Assume it is an index already, check for validity
         let index = id
+            .strip_prefix('#')
+            .unwrap_or(id)
             .parse::<usize>()
             .or(Err(ErrorType::MalformedIndex(id.to_string())))?;
-        if index >= self.nodes.len() || self.nodes[index].is_none() {
+        if !self.slots.contains_key(&index) {
             return Err(ErrorType::InvalidIndex(index));
         }
         Ok(index)
@@ -954,29 +1133,587 @@ impl Graph {
             .copied()
     }
 
-    /// Sets an alias for node at `index`
+    /// Checks that `alias` can be given to a node. Aliases may not be empty, contain whitespace,
+    /// `/` or `:`, or start with `#` or `@`, and may not look like a node ID, a date or a path, as
+    /// they would be read as one.
+    pub fn validate_alias(alias: &str) -> GraphResult<()> {
+        if alias.is_empty()
+            || alias.contains(|c: char| c.is_whitespace() || c == '/' || c == ':')
+            || alias.starts_with(['#', '@'])
+            || alias.parse::<i64>().is_ok()
+            || query::is_date(alias)
+        {
+            return Err(ErrorType::InvalidAlias(alias.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Adds an alias to node at `index`, after the ones it already has. Fails with
+    /// [`ErrorType::AliasTaken`] if another node has it, see [`Graph::steal_alias`].
     pub fn set_alias(&mut self, index: usize, alias: String) -> GraphResult<()> {
+        Self::validate_alias(&alias)?;
+        match self.aliases.get(&alias) {
+            Some(&owner) if owner == index => return Ok(()),
+            Some(&owner) => return Err(ErrorType::AliasTaken(alias, owner)),
+            None => {}
+        }
         self.aliases.insert(alias.clone(), index);
-        self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
+        let node = self.node_mut(index);
+        node.metadata.aliases.push(alias.clone());
+        node.touch();
+        self.emit(Event::AliasSet { index, alias });
+        Ok(())
+    }
+
+    /// Adds an alias to node at `index`, taking it from the node that has it, if any.
+    pub fn steal_alias(&mut self, index: usize, alias: String) -> GraphResult<()> {
+        Self::validate_alias(&alias)?;
+        if let Some(&owner) = self.aliases.get(&alias).filter(|&&owner| owner != index) {
+            self.unset_alias(owner, &alias)?;
+        }
+        self.set_alias(index, alias)
+    }
+
+    /// Removes an alias from node at `index`. Does nothing if the node does not have it.
+    pub fn unset_alias(&mut self, index: usize, alias: &str) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        if !node.metadata.aliases.iter().any(|a| a == alias) {
+            return Ok(());
+        }
+        node.metadata.aliases.retain(|a| a != alias);
+        node.touch();
+        self.aliases.remove(alias);
+        self.emit(Event::AliasUnset {
+            index,
+            alias: alias.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Removes every alias from node at `index`
+    pub fn clear_aliases(&mut self, index: usize) -> GraphResult<()> {
+        let aliases = std::mem::take(&mut self.node_mut(index).metadata.aliases);
+        for alias in aliases {
+            self.aliases.remove(&alias);
+            self.emit(Event::AliasUnset { index, alias });
+        }
+        Ok(())
+    }
+
+    /// Renames the alias `old` to `new`, keeping its place among the node's aliases. Fails with
+    /// [`ErrorType::AliasTaken`] if another node has `new`, unless `force` is set, in which case
+    /// that node loses it.
+    ///
+    /// # Returns
+    /// The index of the node with the alias.
+    pub fn rename_alias(&mut self, old: &str, new: String, force: bool) -> GraphResult<usize> {
+        let index = *self
+            .aliases
+            .get(old)
+            .ok_or_else(|| ErrorType::UnknownAlias(old.to_string()))?;
+        Self::validate_alias(&new)?;
+        match self.aliases.get(&new) {
+            Some(&owner) if owner == index => {
+                self.unset_alias(index, old)?;
+                return Ok(index);
+            }
+            Some(&owner) if !force => return Err(ErrorType::AliasTaken(new, owner)),
+            Some(&owner) => self.unset_alias(owner, &new)?,
+            None => {}
+        }
+
+        self.aliases.remove(old);
+        self.aliases.insert(new.clone(), index);
+        let node = self.node_mut(index);
+        for alias in node.metadata.aliases.iter_mut().filter(|a| *a == old) {
+            *alias = new.clone();
+        }
+        node.touch();
+        self.emit(Event::AliasUnset {
+            index,
+            alias: old.to_string(),
+        });
+        self.emit(Event::AliasSet { index, alias: new });
+        Ok(index)
+    }
+
+    /// Records that the task at `index` cannot be started until the task at `blocker` is done.
+    /// Dependencies are separate from parent/child edges and do not affect completion.
+    pub fn add_dependency(&mut self, index: usize, blocker: usize) -> GraphResult<()> {
+        for i in [index, blocker] {
+            if !self.node(i).data.is_task() {
+                return Err(ErrorType::NotTaskNode(i));
+            }
+        }
+        if let Some(mut path) = self.dependency_path(blocker, index) {
+            path.insert(0, index);
+            return Err(ErrorType::DependencyCycle(path));
+        }
+
+        let node = self.node_mut(index);
+        if node.metadata.blocked_by.contains(&blocker) {
+            return Ok(());
+        }
+        node.metadata.blocked_by.push(blocker);
+        node.touch();
+        self.node_mut(blocker).metadata.blocks.push(index);
+        self.emit(Event::DependencyAdded { index, blocker });
+        Ok(())
+    }
+
+    /// Removes the dependency of the task at `index` on the task at `blocker`, if any.
+    pub fn remove_dependency(&mut self, index: usize, blocker: usize) -> GraphResult<()> {
+        let node = self.node_mut(index);
+        if !node.metadata.blocked_by.contains(&blocker) {
+            return Ok(());
+        }
+        node.metadata.blocked_by.retain(|i| *i != blocker);
+        node.touch();
+        self.node_mut(blocker)
             .metadata
-            .alias = Some(alias);
+            .blocks
+            .retain(|i| *i != index);
+        self.emit(Event::DependencyRemoved { index, blocker });
         Ok(())
     }
 
-    /// Unsets a node at `index`'s alias
-    pub fn unset_alias(&mut self, index: usize) -> GraphResult<()> {
-        let alias = self.nodes[index]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
+    /// Removes every dependency from and on a node
+    fn clear_dependencies(&mut self, index: usize) {
+        let node = self.node(index);
+        let blocked_by = node.metadata.blocked_by.clone();
+        let blocks = node.metadata.blocks.clone();
+
+        for blocker in blocked_by {
+            self.node_mut(blocker)
+                .metadata
+                .blocks
+                .retain(|i| *i != index);
+            self.emit(Event::DependencyRemoved { index, blocker });
+        }
+        for blocked in blocks {
+            self.node_mut(blocked)
+                .metadata
+                .blocked_by
+                .retain(|i| *i != index);
+            self.emit(Event::DependencyRemoved {
+                index: blocked,
+                blocker: index,
+            });
+        }
+
+        let node = self.node_mut(index);
+        node.metadata.blocked_by.clear();
+        node.metadata.blocks.clear();
+    }
+
+    /// Returns a chain of dependencies through which `from` waits on `to`, starting with `from`
+    /// and ending with `to`.
+    fn dependency_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        self.find_path(from, to, |node| &node.metadata.blocked_by)
+    }
+
+    /// Returns a path from `from` to `to` following the edges returned by `edges`, starting with
+    /// `from` and ending with `to`.
+    fn find_path(
+        &self,
+        from: usize,
+        to: usize,
+        edges: fn(&Node) -> &Vec<usize>,
+    ) -> Option<Vec<usize>> {
+        let mut path = vec![from];
+        let mut visited = HashSet::new();
+        if self._find_path(to, edges, &mut path, &mut visited) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    fn _find_path(
+        &self,
+        to: usize,
+        edges: fn(&Node) -> &Vec<usize>,
+        path: &mut Vec<usize>,
+        visited: &mut HashSet<usize>,
+    ) -> bool {
+        let current = *path.last().unwrap();
+        if current == to {
+            return true;
+        }
+        if !visited.insert(current) {
+            return false;
+        }
+        let next = edges(self.node(current)).clone();
+        for node in next {
+            path.push(node);
+            if self._find_path(to, edges, path, visited) {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    /// Returns the tasks the node at `index` still waits on, i.e. its blockers that are neither
+    /// done nor cancelled
+    pub fn pending_blockers(&self, index: usize) -> Vec<usize> {
+        self.node(index)
             .metadata
-            .alias
-            .take()
-            .unwrap();
-        self.aliases.remove(alias.as_str());
+            .blocked_by
+            .iter()
+            .filter(|blocker| {
+                self.node(**blocker)
+                    .data
+                    .as_task()
+                    .is_none_or(|task| !task.state.is_closed())
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Returns whether the node at `index` waits on a task that is still open
+    pub fn is_blocked(&self, index: usize) -> bool {
+        !self.pending_blockers(index).is_empty()
+    }
+
+    /// Returns whether the node at `index` is a task that is still open and not blocked, neither
+    /// by another task nor explicitly
+    pub fn is_ready(&self, index: usize) -> bool {
+        use task::TaskState;
+
+        let ready = self.node(index).data.as_task().is_some_and(|task| {
+            !task.state.is_closed()
+                && !matches!(task.state, TaskState::Blocked | TaskState::Waiting)
+        });
+        ready && !self.is_blocked(index)
+    }
+
+    /// Returns the running timer, if any
+    pub fn get_timer(&self) -> Option<&Timer> {
+        self.timer.as_ref()
+    }
+
+    /// Starts timing work on a node, stopping the timer already running, if any.
+    ///
+    /// # Returns
+    /// The node the stopped timer was running on and the interval it recorded.
+    pub fn start_timer(&mut self, index: usize) -> GraphResult<Option<(usize, Interval)>> {
+        if !self.node_at_exists(index) {
+            return Err(ErrorType::InvalidIndex(index));
+        }
+        let now = node::now();
+        let stopped = self.stop_timer_at(now)?;
+        self.timer = Some(Timer { index, start: now });
+        self.emit(Event::TimerStarted { index });
+        Ok(stopped)
+    }
+
+    /// Stops the running timer, recording the time worked on its node.
+    ///
+    /// # Returns
+    /// The node the timer was running on and the interval it recorded.
+    pub fn stop_timer(&mut self) -> GraphResult<(usize, Interval)> {
+        self.stop_timer_at(node::now())?
+            .ok_or(ErrorType::NoTimerRunning)
+    }
+
+    /// Stops the running timer, if any. A timer whose node no longer exists, e.g. in a
+    /// hand-edited document, has nowhere to record its time: it is discarded and reported with
+    /// [`ErrorType::TimerNodeMissing`].
+    fn stop_timer_at(&mut self, end: NaiveDateTime) -> GraphResult<Option<(usize, Interval)>> {
+        let Some(timer) = self.timer else {
+            return Ok(None);
+        };
+        self.timer = None;
+        self.emit(Event::TimerStopped { index: timer.index });
+        let node = self
+            .node_checked_mut(timer.index)
+            .ok_or(ErrorType::TimerNodeMissing(timer.index))?;
+        let interval = timer.until(end);
+        node.metadata.time_log.push(interval);
+        Ok(Some((timer.index, interval)))
+    }
+
+    /// Stops the timer without recording anything if it is running on a node
+    fn discard_timer(&mut self, index: usize) {
+        if self.timer.take_if(|timer| timer.index == index).is_some() {
+            self.emit(Event::TimerStopped { index });
+        }
+    }
+
+    /// Returns the time worked on a node itself, including the running timer.
+    pub fn time_spent(&self, index: usize) -> TimeDelta {
+        let logged = self
+            .node(index)
+            .metadata
+            .time_log
+            .iter()
+            .map(Interval::duration)
+            .sum();
+        match self.timer {
+            Some(timer) if timer.index == index => logged + timer.until(node::now()).duration(),
+            _ => logged,
+        }
+    }
+
+    /// Returns a node and all of its descendants
+    pub fn subtree(&self, index: usize) -> HashSet<usize> {
+        let mut visited = HashSet::from([index]);
+        let mut stack = vec![index];
+        while let Some(current) = stack.pop() {
+            for child in self.node(current).metadata.children.iter() {
+                if visited.insert(*child) {
+                    stack.push(*child);
+                }
+            }
+        }
+        visited
+    }
+
+    /// Returns the time worked on a node and all of its descendants. Descendants reachable
+    /// through more than one path are only counted once.
+    pub fn total_time(&self, index: usize) -> TimeDelta {
+        self.subtree(index)
+            .into_iter()
+            .map(|index| self.time_spent(index))
+            .sum()
+    }
+
+    /// Returns the intervals worked on a node and its descendants, or on every node if no node is
+    /// given, in the order they started. The running timer is not included.
+    pub fn time_log(&self, index: Option<usize>) -> Vec<(usize, Interval)> {
+        let indices: Vec<usize> = match index {
+            Some(index) => self.subtree(index).into_iter().collect(),
+            None => self.node_ids(),
+        };
+        let mut log: Vec<(usize, Interval)> = indices
+            .into_iter()
+            .flat_map(|index| {
+                self.node(index)
+                    .metadata
+                    .time_log
+                    .iter()
+                    .map(|interval| (index, *interval))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        log.sort_by_key(|(index, interval)| (interval.start, *index));
+        log
+    }
+
+    /// Returns the recurrence rules of the graph
+    pub fn get_recurrences(&self) -> &[Recurrence] {
+        &self.recurrences
+    }
+
+    /// Adds a recurrence rule. Nothing is materialized until it is synced or its dates are viewed.
+    ///
+    /// # Returns
+    /// The index of the new rule.
+    pub fn add_recurrence(&mut self, recurrence: Recurrence) -> usize {
+        self.recurrences.push(recurrence);
+        let index = self.recurrences.len() - 1;
+        self.emit(Event::RecurrenceAdded { index });
+        index
+    }
+
+    /// Removes the recurrence rule at `index`. Tasks it already materialized are kept.
+    pub fn remove_recurrence(&mut self, index: usize) -> GraphResult<Recurrence> {
+        if index >= self.recurrences.len() {
+            return Err(ErrorType::InvalidRecurrence(index));
+        }
+        let recurrence = self.recurrences.remove(index);
+        self.emit(Event::RecurrenceRemoved { index });
+        Ok(recurrence)
+    }
+
+    /// Materializes every recurrence still pending on `date` under that date's node, which is
+    /// inserted if needed. Task targets are inserted directly, while blueprint targets are handed
+    /// to `insert_blueprint` along with the index of the date node. It returns whether it inserted
+    /// the blueprint; a blueprint it leaves out stays pending, for frontends that can't insert
+    /// blueprints.
+    ///
+    /// # Returns
+    /// The number of materialized recurrences.
+    pub fn materialize_recurrences<E: From<ErrorType>>(
+        &mut self,
+        date: NaiveDate,
+        insert_blueprint: &mut impl FnMut(&mut Graph, &str, usize) -> Result<bool, E>,
+    ) -> Result<usize, E> {
+        self.materialize_on(date, insert_blueprint, &mut HashSet::new())
+    }
+
+    /// Does [`Graph::materialize_recurrences`], adding the recurrences whose blueprint was left
+    /// out to `skipped`
+    fn materialize_on<E: From<ErrorType>>(
+        &mut self,
+        date: NaiveDate,
+        insert_blueprint: &mut impl FnMut(&mut Graph, &str, usize) -> Result<bool, E>,
+        skipped: &mut HashSet<usize>,
+    ) -> Result<usize, E> {
+        let pending: Vec<usize> = (0..self.recurrences.len())
+            .filter(|i| self.recurrences[*i].is_pending(date))
+            .collect();
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let parent = self.get_or_insert_date(date);
+        let mut count = 0;
+        for i in pending {
+            let inserted = match self.recurrences[i].target.clone() {
+                RecurTarget::Task(title) => {
+                    self.insert_child(title, parent, false)?;
+                    true
+                }
+                RecurTarget::Blueprint(name) => insert_blueprint(self, &name, parent)?,
+            };
+            if inserted {
+                self.recurrences[i].generated.insert(date);
+                count += 1;
+            } else {
+                skipped.insert(i);
+            }
+        }
+        Ok(count)
+    }
+
+    /// Materializes the recurrences pending on the date nodes a frontend is about to show, so
+    /// that recurring tasks appear the first time their date is viewed. These are the date nodes
+    /// among `indices` and their descendants, down to `depth` levels including `indices`
+    /// themselves (0 for no limit). See [`Graph::materialize_recurrences`].
+    ///
+    /// # Returns
+    /// The number of materialized recurrences.
+    pub fn view_dates<E: From<ErrorType>>(
+        &mut self,
+        indices: &[usize],
+        depth: u32,
+        insert_blueprint: &mut impl FnMut(&mut Graph, &str, usize) -> Result<bool, E>,
+    ) -> Result<usize, E> {
+        if self.recurrences.is_empty() {
+            return Ok(0);
+        }
+
+        let mut dates = vec![];
+        let mut visited = HashSet::new();
+        let mut level: Vec<usize> = indices.to_vec();
+        let mut current_depth = 1;
+        while !level.is_empty() {
+            let mut next = vec![];
+            for index in level {
+                if !visited.insert(index) {
+                    continue;
+                }
+                let Some(node) = self.node_checked(index) else {
+                    continue;
+                };
+                if let NodeType::Date(data) = &node.data {
+                    dates.push(data.date);
+                }
+                if depth == 0 || current_depth < depth {
+                    next.extend(node.metadata.children.iter().copied());
+                }
+            }
+            level = next;
+            current_depth += 1;
+        }
+
+        let mut count = 0;
+        for date in dates {
+            count += self.materialize_recurrences(date, insert_blueprint)?;
+        }
+        Ok(count)
+    }
+
+    /// Materializes every pending recurrence on each date up to and including `until`. See
+    /// [`Graph::materialize_recurrences`].
+    ///
+    /// # Returns
+    /// The number of materialized recurrences.
+    pub fn sync_recurrences<E: From<ErrorType>>(
+        &mut self,
+        until: NaiveDate,
+        insert_blueprint: &mut impl FnMut(&mut Graph, &str, usize) -> Result<bool, E>,
+    ) -> Result<usize, E> {
+        let Some(mut date) = self.recurrences.iter().map(|r| r.first_unsynced()).min() else {
+            return Ok(0);
+        };
+
+        let mut count = 0;
+        let mut skipped = HashSet::new();
+        while date <= until {
+            count += self.materialize_on(date, insert_blueprint, &mut skipped)?;
+            date = match date.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+        for (i, recurrence) in self.recurrences.iter_mut().enumerate() {
+            if !skipped.contains(&i) {
+                recurrence.mark_synced(until);
+            }
+        }
+        Ok(count)
+    }
+
+    /// Normalizes a user-given tag by stripping its leading `#`. Tags must be non-empty and may
+    /// not contain whitespace.
+    pub fn normalize_tag(tag: &str) -> GraphResult<String> {
+        let name = tag.strip_prefix('#').unwrap_or(tag);
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(ErrorType::InvalidTag(tag.to_string()));
+        }
+        Ok(name.to_string())
+    }
+
+    /// Tags node at `index`. Tagging a node with a tag it already has does nothing.
+    pub fn add_tag(&mut self, index: usize, tag: &str) -> GraphResult<()> {
+        let tag = Self::normalize_tag(tag)?;
+        let node = self.node_mut(index);
+        if node.metadata.tags.contains(&tag) {
+            return Ok(());
+        }
+        node.metadata.tags.push(tag.clone());
+        node.touch();
+        self.tags.entry(tag.clone()).or_default().push(index);
+        self.emit(Event::TagAdded { index, tag });
+        Ok(())
+    }
+
+    /// Removes a tag from node at `index`. Does nothing if the node does not have the tag.
+    pub fn remove_tag(&mut self, index: usize, tag: &str) -> GraphResult<()> {
+        let tag = Self::normalize_tag(tag)?;
+        let node = self.node_mut(index);
+        if node.metadata.tags.contains(&tag) {
+            node.metadata.tags.retain(|t| *t != tag);
+            node.touch();
+            self.emit(Event::TagRemoved {
+                index,
+                tag: tag.clone(),
+            });
+        }
+        if let Some(indices) = self.tags.get_mut(&tag) {
+            indices.retain(|i| *i != index);
+            if indices.is_empty() {
+                self.tags.remove(&tag);
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes every tag from node at `index`
+    pub fn clear_tags(&mut self, index: usize) -> GraphResult<()> {
+        let tags = std::mem::take(&mut self.node_mut(index).metadata.tags);
+        for tag in tags {
+            if let Some(indices) = self.tags.get_mut(&tag) {
+                indices.retain(|i| *i != index);
+                if indices.is_empty() {
+                    self.tags.remove(&tag);
+                }
+            }
+            self.emit(Event::TagRemoved { index, tag });
+        }
         Ok(())
     }
 
@@ -994,23 +1731,16 @@ impl Graph {
         if delta_target_location == 0 {
             return Ok(());
         }
-        let parents_vec = &mut self.nodes[parent_idx]
-            .as_ref()
-            .unwrap()
-            .borrow_mut()
-            .metadata
-            .children;
+        let parents_vec = &mut self.node_mut(parent_idx).metadata.children;
         if let Some(pos) = parents_vec.iter().position(|&x| x == node_idx) {
-            let pos_fix;
             // FIXME: uhm..
-
-            if delta_target_location < 0 && pos as i32 >= delta_target_location {
+            let pos_fix = if delta_target_location < 0 && pos as i32 >= delta_target_location {
                 if pos as i32 + delta_target_location < 0 {
                     return Err(ErrorType::IndexOutOfRange(format!(
                         "Index is out of range from parents when reordering. Max move count: {pos}"
                     )));
                 }
-                pos_fix = pos - delta_target_location.unsigned_abs() as usize;
+                pos - delta_target_location.unsigned_abs() as usize
             } else {
                 if pos + delta_target_location as usize > parents_vec.len() - 1 {
                     return Err(ErrorType::IndexOutOfRange(format!(
@@ -1018,8 +1748,8 @@ impl Graph {
                         parents_vec.len() - 1 - pos
                     )));
                 }
-                pos_fix = pos + delta_target_location as usize;
-            }
+                pos + delta_target_location as usize
+            };
             parents_vec.remove(pos);
             parents_vec.insert(pos_fix, node_idx);
         } else {
@@ -1027,6 +1757,7 @@ impl Graph {
                 "Index {node_idx} not found in {parent_idx} when reordering"
             )));
         }
+        self.emit(Event::Reordered { parent: parent_idx });
         Ok(())
     }
 }
@@ -1036,7 +1767,7 @@ pub trait GraphGetters {
     fn get_node(&self, index: usize) -> Node;
     fn get_node_checked(&self, index: usize) -> Option<Node>;
     fn node_at_exists(&self, index: usize) -> bool;
-    fn get_node_mut(&self, index: usize) -> RefMut<'_, Node>;
+    fn get_node_mut(&mut self, index: usize) -> &mut Node;
     fn get_root_nodes_indices(&self) -> &[usize];
     fn get_archived_node_indices(&self) -> &[usize];
 
@@ -1056,7 +1787,7 @@ impl GraphGetters for Graph {
     /// # Returns
     /// A `Node`.
     fn get_node(&self, index: usize) -> Node {
-        self.nodes[index].as_ref().unwrap().borrow().clone()
+        self.node(index).clone()
     }
 
     /// Get a node of an index from graph. Note that the returned node is cloned from the original.
@@ -1067,21 +1798,17 @@ impl GraphGetters for Graph {
     /// # Returns
     /// An `Option` containing `Node` when node is found.
     fn get_node_checked(&self, index: usize) -> Option<Node> {
-        if let Some(node) = self.nodes.get(index) {
-            if let Some(node) = node {
-                return Some(node.borrow().clone());
-            }
-        }
-        None
+        self.node_checked(index).cloned()
     }
 
     fn node_at_exists(&self, index: usize) -> bool {
-        self.nodes.get(index).is_some()
+        self.slots.contains_key(&index)
     }
 
-    /// Get a node of an index from graph.
-    fn get_node_mut(&self, index: usize) -> RefMut<'_, Node> {
-        self.nodes[index].as_ref().unwrap().borrow_mut()
+    /// Get a node of an index from graph for changing it.
+    /// *Warning: panics if index is invalid.*
+    fn get_node_mut(&mut self, index: usize) -> &mut Node {
+        self.node_mut(index)
     }
 
     fn get_root_nodes_indices(&self) -> &[usize] {
@@ -1096,12 +1823,575 @@ impl GraphGetters for Graph {
     }
 
     fn get_node_children(&self, index: usize) -> Vec<usize> {
-        self.nodes[index]
-            .as_ref()
+        self.node(index).metadata.children.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeDelta};
+
+    use super::errors::ErrorType;
+    use super::node::now;
+    use super::node::task::{Propagation, Rollup, TaskState};
+    use super::time::{Interval, Timer};
+    use super::{Graph, GraphGetters};
+
+    #[test]
+    fn clean_keeps_ids() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let removed = graph
+            .insert_child("removed".to_string(), root, false)
+            .unwrap();
+        let kept = graph.insert_child("kept".to_string(), root, false).unwrap();
+        graph.set_alias(kept, "kept".to_string()).unwrap();
+        graph.remove(removed).unwrap();
+
+        graph.clean();
+        assert_eq!(graph.get_nodes().len(), 2);
+        assert_eq!(graph.get_node(kept).title, "kept");
+        assert_eq!(graph.get_index("kept").unwrap(), kept);
+        assert_eq!(graph.get_node_children(root), vec![kept]);
+        assert!(graph.get_index(&removed.to_string()).is_err());
+
+        // Removed IDs are not handed out again
+        let new = graph.insert_root("new".to_string(), false);
+        assert!(new > kept);
+        let serialized = serde_yaml_ng::to_string(&graph).unwrap();
+        let mut graph: Graph = serde_yaml_ng::from_str(&serialized).unwrap();
+        assert_eq!(graph.get_node(new).title, "new");
+        assert!(graph.insert_root("newer".to_string(), false) > new);
+    }
+
+    #[test]
+    fn tag_index_follows_removals() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let removed = graph
+            .insert_child("removed".to_string(), root, false)
+            .unwrap();
+        let subtree = graph
+            .insert_child("subtree".to_string(), root, false)
+            .unwrap();
+        let below = graph
+            .insert_child("below".to_string(), subtree, false)
+            .unwrap();
+        let kept = graph.insert_child("kept".to_string(), root, false).unwrap();
+        for index in [removed, subtree, below, kept] {
+            graph.add_tag(index, "#work").unwrap();
+        }
+        graph.add_tag(removed, "gone").unwrap();
+        graph.add_tag(below, "deep").unwrap();
+        assert_eq!(graph.get_tagged("work"), [removed, subtree, below, kept]);
+
+        graph.remove(removed).unwrap();
+        assert_eq!(graph.get_tagged("work"), [subtree, below, kept]);
+        assert!(!graph.get_tags().contains_key("gone"));
+
+        graph.remove_children_recursive(subtree).unwrap();
+        assert_eq!(graph.get_tagged("work"), [kept]);
+        assert!(!graph.get_tags().contains_key("deep"));
+
+        // Cleaning rebuilds the index from the nodes that are left
+        graph.clean();
+        assert_eq!(graph.get_tagged("work"), [kept]);
+        assert_eq!(graph.get_tags().len(), 1);
+        assert_eq!(graph.get_node(kept).metadata.tags, ["work"]);
+
+        graph.remove_tag(kept, "work").unwrap();
+        assert!(graph.get_tagged("work").is_empty());
+        assert!(graph.get_tags().is_empty());
+    }
+
+    #[test]
+    fn link_cycles() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        let grandchild = graph
+            .insert_child("grandchild".to_string(), child, false)
+            .unwrap();
+        let other = graph.insert_root("other".to_string(), false);
+        let unchanged = serde_yaml_ng::to_string(&graph).unwrap();
+
+        for (from, to, cycle) in [
+            (child, root, vec![root, child, root]),
+            (grandchild, root, vec![root, child, grandchild, root]),
+            (root, root, vec![root, root]),
+        ] {
+            assert!(graph.would_create_cycle(from, to));
+            assert_eq!(graph.link_cycle(from, to), Some(cycle.clone()));
+            assert!(matches!(
+                graph.link(from, to),
+                Err(ErrorType::LinkCycle(path)) if path == cycle
+            ));
+        }
+        assert_eq!(serde_yaml_ng::to_string(&graph).unwrap(), unchanged);
+
+        // Nodes may have several parents, as long as none is also a descendant
+        assert!(!graph.would_create_cycle(root, grandchild));
+        assert!(!graph.would_create_cycle(other, child));
+        graph.link(other, child).unwrap();
+        assert_eq!(graph.get_node(child).metadata.parents, [root, other]);
+        assert!(!graph.get_roots().contains(&child));
+        assert_eq!(
+            graph.link_cycle(grandchild, other),
+            Some(vec![other, child, grandchild, other])
+        );
+    }
+
+    #[test]
+    fn dependencies() {
+        let mut graph = Graph::new();
+        let [a, b, c, d] =
+            ["a", "b", "c", "d"].map(|title| graph.insert_root(title.to_string(), false));
+        let pseudo = graph.insert_root("pseudo".to_string(), true);
+        graph.add_dependency(a, b).unwrap();
+        graph.add_dependency(b, c).unwrap();
+        graph.add_dependency(d, c).unwrap();
+        // Adding a dependency twice does nothing
+        graph.add_dependency(a, b).unwrap();
+        assert_eq!(graph.get_node(a).metadata.blocked_by, [b]);
+        assert_eq!(graph.get_node(c).metadata.blocks, [b, d]);
+
+        // c waits on a, which waits on b, which waits on c
+        assert!(matches!(
+            graph.add_dependency(c, a),
+            Err(ErrorType::DependencyCycle(path)) if path == [c, a, b, c]
+        ));
+        assert!(matches!(
+            graph.add_dependency(a, a),
+            Err(ErrorType::DependencyCycle(path)) if path == [a, a]
+        ));
+        assert!(matches!(
+            graph.add_dependency(a, pseudo),
+            Err(ErrorType::NotTaskNode(i)) if i == pseudo
+        ));
+        assert!(graph.get_node(c).metadata.blocked_by.is_empty());
+        assert!(graph.get_node(a).metadata.blocks.is_empty());
+
+        assert!(!graph.is_ready(a));
+        assert!(!graph.is_ready(b));
+        assert!(graph.is_ready(c));
+        assert!(!graph.is_ready(pseudo));
+        graph.set_task_state(c, TaskState::Done, false).unwrap();
+        assert!(graph.is_ready(b));
+        assert!(!graph.is_ready(c));
+        // Cancelled blockers don't hold anything up either
+        graph
+            .set_task_state(b, TaskState::Cancelled, false)
+            .unwrap();
+        assert!(graph.is_ready(a));
+        // Tasks marked as waiting are not ready, whatever their blockers
+        graph.set_task_state(a, TaskState::Waiting, false).unwrap();
+        assert!(!graph.is_ready(a));
+
+        // Both sides of a dependency go away together
+        graph.remove_dependency(a, b).unwrap();
+        assert!(graph.get_node(a).metadata.blocked_by.is_empty());
+        assert!(graph.get_node(b).metadata.blocks.is_empty());
+        graph.remove(c).unwrap();
+        assert!(graph.get_node(b).metadata.blocked_by.is_empty());
+        assert!(graph.get_node(d).metadata.blocked_by.is_empty());
+        graph.set_task_state(b, TaskState::None, false).unwrap();
+        graph.add_dependency(d, b).unwrap();
+        graph.remove(d).unwrap();
+        assert!(graph.get_node(b).metadata.blocks.is_empty());
+    }
+
+    #[test]
+    fn timestamps_follow_changes() {
+        let start = now();
+        let long_ago = NaiveDate::from_ymd_opt(2000, 1, 1)
             .unwrap()
-            .borrow()
-            .metadata
-            .children
-            .to_vec()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        let metadata = &graph.get_node(child).metadata;
+        assert!(metadata.created.is_some_and(|created| created >= start));
+        assert_eq!(metadata.modified, metadata.created);
+        assert_eq!(metadata.completed, None);
+
+        // Renaming touches the node, leaving its creation time alone
+        let created = graph.get_node(child).metadata.created;
+        graph.get_node_mut(child).metadata.modified = Some(long_ago);
+        graph.rename_node(child, "renamed".to_string()).unwrap();
+        let metadata = &graph.get_node(child).metadata;
+        assert!(metadata.modified.is_some_and(|modified| modified >= start));
+        assert_eq!(metadata.created, created);
+
+        // Checking the only subtask completes its parent too
+        graph.get_node_mut(root).metadata.modified = Some(long_ago);
+        graph.set_task_state(child, TaskState::Done, true).unwrap();
+        for index in [child, root] {
+            let metadata = &graph.get_node(index).metadata;
+            assert!(metadata
+                .completed
+                .is_some_and(|completed| completed >= start));
+            assert_eq!(metadata.modified, metadata.completed);
+        }
+
+        // Setting the state a task already has changes nothing
+        graph.get_node_mut(child).metadata.modified = Some(long_ago);
+        graph.set_task_state(child, TaskState::Done, true).unwrap();
+        assert_eq!(graph.get_node(child).metadata.modified, Some(long_ago));
+
+        // Unchecking clears the completion time, on the parent as well
+        graph.set_task_state(child, TaskState::None, true).unwrap();
+        for index in [child, root] {
+            let metadata = &graph.get_node(index).metadata;
+            assert_eq!(metadata.completed, None);
+            assert!(metadata.modified.is_some_and(|modified| modified >= start));
+        }
+    }
+
+    #[test]
+    fn viewing_dates_materializes_recurrences() {
+        use super::recur::{RecurRule, RecurTarget, Recurrence};
+
+        let mut graph = Graph::new();
+        let start = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let next = start.succ_opt().unwrap();
+        let first = graph.insert_date(String::new(), start);
+        let second = graph.insert_date(String::new(), next);
+        let project = graph.insert_root("project".to_string(), false);
+        graph.link(project, second).unwrap();
+        graph.add_recurrence(Recurrence::new(
+            RecurRule::Daily,
+            RecurTarget::Task("standup".to_string()),
+            start,
+        ));
+        graph.add_recurrence(Recurrence::new(
+            RecurRule::Daily,
+            RecurTarget::Blueprint("review".to_string()),
+            start,
+        ));
+        // A frontend that can't insert blueprints
+        let mut no_blueprints = |_: &mut Graph, _: &str, _: usize| Ok::<_, ErrorType>(false);
+
+        // Listing the date nodes themselves, as `lsd` or the date view do
+        assert_eq!(
+            graph.view_dates(&[first], 1, &mut no_blueprints).unwrap(),
+            1
+        );
+        let titles = |graph: &Graph, date| -> Vec<String> {
+            graph
+                .get_node_children(date)
+                .iter()
+                .map(|i| graph.get_node(*i).title)
+                .collect()
+        };
+        assert_eq!(titles(&graph, first), vec!["standup"]);
+        assert!(graph.recurrences[0].is_generated(start));
+        assert!(graph.recurrences[1].is_pending(start));
+
+        // Date nodes listed below another node, but only within the listed depth
+        assert_eq!(
+            graph.view_dates(&[project], 1, &mut no_blueprints).unwrap(),
+            0
+        );
+        assert_eq!(
+            graph.view_dates(&[project], 2, &mut no_blueprints).unwrap(),
+            1
+        );
+        assert_eq!(titles(&graph, second), vec!["standup"]);
+
+        // Viewed dates are not materialized again, but skipped blueprints still are
+        let mut inserted = vec![];
+        let mut insert = |graph: &mut Graph, name: &str, parent: usize| {
+            graph.insert_child(name.to_string(), parent, false)?;
+            inserted.push(parent);
+            Ok::<_, ErrorType>(true)
+        };
+        assert_eq!(
+            graph.view_dates(&[first, second], 0, &mut insert).unwrap(),
+            2
+        );
+        assert_eq!(inserted, vec![first, second]);
+        assert_eq!(titles(&graph, first), vec!["standup", "review"]);
+    }
+
+    #[test]
+    fn time_rolls_up_once() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let a = graph.insert_child("a".to_string(), root, false).unwrap();
+        let b = graph.insert_child("b".to_string(), root, false).unwrap();
+        let shared = graph.insert_child("shared".to_string(), a, false).unwrap();
+        graph.link(b, shared).unwrap();
+
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let hour = |hours| Interval {
+            start,
+            end: start + TimeDelta::hours(hours),
+        };
+        graph.get_node_mut(a).metadata.time_log.push(hour(1));
+        graph.get_node_mut(shared).metadata.time_log.push(hour(2));
+        assert_eq!(graph.total_time(b), TimeDelta::hours(2));
+        assert_eq!(graph.total_time(root), TimeDelta::hours(3));
+        assert_eq!(graph.time_log(Some(b)), vec![(shared, hour(2))]);
+
+        // Only one timer runs at a time
+        assert!(graph.start_timer(a).unwrap().is_none());
+        let (stopped, _) = graph.start_timer(b).unwrap().unwrap();
+        assert_eq!(stopped, a);
+        assert_eq!(graph.get_node(a).metadata.time_log.len(), 2);
+        assert_eq!(graph.stop_timer().unwrap().0, b);
+        assert!(graph.stop_timer().is_err());
+
+        // Removing the node a timer runs on discards the timer
+        graph.start_timer(shared).unwrap();
+        graph.remove(shared).unwrap();
+        assert!(graph.get_timer().is_none());
+
+        // A timer on a node that is gone, e.g. in a hand-edited document, says so when stopped
+        graph.timer = Some(Timer {
+            index: shared,
+            start,
+        });
+        assert!(matches!(
+            graph.stop_timer(),
+            Err(ErrorType::TimerNodeMissing(index)) if index == shared
+        ));
+        assert!(graph.get_timer().is_none());
+    }
+
+    #[test]
+    fn weighted_progress() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let big = graph.insert_child("big".to_string(), root, false).unwrap();
+        let small = graph
+            .insert_child("small".to_string(), root, false)
+            .unwrap();
+        let pseudo = graph
+            .insert_child("pseudo".to_string(), root, true)
+            .unwrap();
+        let sub = graph.insert_child("sub".to_string(), big, false).unwrap();
+        graph.insert_child("other".to_string(), big, false).unwrap();
+        graph.set_task_estimate(big, Some(4)).unwrap();
+
+        graph.set_task_state(small, TaskState::Done, true).unwrap();
+        graph.set_task_state(sub, TaskState::Done, true).unwrap();
+        let progress = graph.progress(root).unwrap();
+        assert_eq!(progress.total, 5);
+        // Half of big, and all of small
+        assert_eq!(progress.done, 3.0);
+        assert_eq!(graph.progress(big).unwrap().fraction(), 0.5);
+
+        // Pseudo nodes don't count, and have no progress of their own
+        graph.set_task_state(big, TaskState::Done, true).unwrap();
+        assert_eq!(graph.progress(root).unwrap().fraction(), 1.0);
+        assert!(graph.progress(pseudo).is_none());
+    }
+
+    #[test]
+    fn propagation_policies() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let a = graph.insert_child("a".to_string(), root, false).unwrap();
+        let b = graph.insert_child("b".to_string(), root, false).unwrap();
+        let state = |graph: &Graph, i| graph.get_node(i).data.as_task().unwrap().state;
+
+        // The parent stays open until explicitly checked
+        graph.set_propagation(Propagation {
+            rollup: Rollup::Partial,
+            cascade: true,
+        });
+        graph.set_task_state(a, TaskState::Done, true).unwrap();
+        graph.set_task_state(b, TaskState::Done, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::Partial);
+        graph.set_task_state(root, TaskState::Done, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::Done);
+        graph.set_task_state(b, TaskState::None, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::Partial);
+
+        // Checking the parent doesn't touch its children
+        let policy = Propagation {
+            rollup: Rollup::Manual,
+            cascade: false,
+        };
+        graph.set_node_propagation(root, Some(policy)).unwrap();
+        assert_eq!(graph.propagation_of(root), policy);
+        graph.set_task_state(root, TaskState::None, true).unwrap();
+        assert_eq!(state(&graph, a), TaskState::Done);
+        graph.set_task_state(b, TaskState::Done, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::None);
+    }
+
+    #[test]
+    fn explicit_states() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let a = graph.insert_child("a".to_string(), root, false).unwrap();
+        let b = graph.insert_child("b".to_string(), root, false).unwrap();
+        let c = graph.insert_child("c".to_string(), root, false).unwrap();
+        let state = |graph: &Graph, i| graph.get_node(i).data.as_task().unwrap().state;
+
+        // Explicit states stay put, and don't cascade
+        graph
+            .set_task_state(root, TaskState::InProgress, true)
+            .unwrap();
+        assert_eq!(state(&graph, a), TaskState::None);
+        graph.set_task_state(a, TaskState::Done, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::InProgress);
+        graph.set_task_state(b, TaskState::Waiting, true).unwrap();
+        assert!(!graph.is_ready(b));
+
+        // Cancelled tasks don't count towards completion
+        graph.set_task_state(b, TaskState::Cancelled, true).unwrap();
+        assert_eq!(graph.progress(root).unwrap().total, 2);
+        graph.set_task_state(c, TaskState::Done, true).unwrap();
+        assert_eq!(state(&graph, root), TaskState::Done);
+
+        // Cancelling keeps what was done
+        graph.set_task_state(c, TaskState::None, true).unwrap();
+        graph
+            .set_task_state(root, TaskState::Cancelled, true)
+            .unwrap();
+        assert_eq!(state(&graph, a), TaskState::Done);
+        assert_eq!(state(&graph, c), TaskState::Cancelled);
+        assert_eq!(state(&graph, root), TaskState::Cancelled);
+    }
+
+    #[test]
+    fn transaction_rolls_back() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+
+        let result = graph.transaction(|graph| {
+            graph.set_task_state(child, TaskState::Done, true)?;
+            graph.insert_child("new".to_string(), root, false)?;
+            graph.set_alias(child, "child".to_string())?;
+            // Fails after the changes above
+            graph.link(child, root)
+        });
+        assert!(result.is_err());
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.next_id(), 2);
+        assert_eq!(graph.get_node(root).metadata.children, vec![child]);
+        assert_eq!(
+            graph.get_node(root).data.as_task().unwrap().state,
+            TaskState::None
+        );
+        assert!(graph.get_aliases().is_empty());
+
+        let new = graph
+            .transaction(|graph| graph.insert_child("new".to_string(), root, false))
+            .unwrap();
+        assert_eq!(graph.get_node(root).metadata.children, vec![child, new]);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        use std::sync::{Arc, RwLock};
+        use std::thread;
+
+        let graph = Arc::new(RwLock::new(Graph::new()));
+        let root = graph
+            .write()
+            .unwrap()
+            .insert_root("root".to_string(), false);
+
+        let writer = {
+            let graph = Arc::clone(&graph);
+            thread::spawn(move || {
+                let mut graph = graph.write().unwrap();
+                let child = graph
+                    .insert_child("child".to_string(), root, false)
+                    .unwrap();
+                graph.set_task_state(child, TaskState::Done, true).unwrap();
+            })
+        };
+        writer.join().unwrap();
+
+        // The way a background autosave would read it
+        let reader = {
+            let graph = Arc::clone(&graph);
+            thread::spawn(move || serde_yaml_ng::to_string(&*graph.read().unwrap()).unwrap())
+        };
+        assert!(reader.join().unwrap().contains("child"));
+        assert_eq!(
+            graph
+                .read()
+                .unwrap()
+                .get_node(root)
+                .data
+                .as_task()
+                .unwrap()
+                .state,
+            TaskState::Done
+        );
+    }
+
+    #[test]
+    fn alias_rules() {
+        let mut graph = Graph::new();
+        let a = graph.insert_root("a".to_string(), false);
+        let b = graph.insert_root("b".to_string(), false);
+
+        for alias in [
+            "",
+            "two words",
+            "12",
+            "-3",
+            "today",
+            "2025-01-31",
+            "7d",
+            "a/b",
+            "a:b",
+            "#a",
+            "@a",
+        ] {
+            assert!(
+                matches!(
+                    graph.set_alias(a, alias.to_string()),
+                    Err(ErrorType::InvalidAlias(_))
+                ),
+                "{alias:?} should be rejected"
+            );
+        }
+
+        graph.set_alias(a, "work".to_string()).unwrap();
+        graph.set_alias(a, "job".to_string()).unwrap();
+        assert_eq!(graph.get_node(a).metadata.aliases, vec!["work", "job"]);
+        assert!(matches!(
+            graph.set_alias(b, "work".to_string()),
+            Err(ErrorType::AliasTaken(_, owner)) if owner == a
+        ));
+
+        // Stealing clears the alias from the node that had it
+        graph.steal_alias(b, "work".to_string()).unwrap();
+        assert_eq!(graph.get_node(a).metadata.aliases, vec!["job"]);
+        assert_eq!(graph.get_index("work").unwrap(), b);
+
+        graph
+            .rename_alias("job", "career".to_string(), false)
+            .unwrap();
+        assert_eq!(graph.get_node(a).metadata.aliases, vec!["career"]);
+        assert!(graph.get_index("job").is_err());
+        assert!(graph
+            .rename_alias("career", "work".to_string(), false)
+            .is_err());
+        graph
+            .rename_alias("career", "work".to_string(), true)
+            .unwrap();
+        assert_eq!(graph.get_index("work").unwrap(), a);
+        assert!(graph.get_node(b).metadata.aliases.is_empty());
+        assert!(graph.validate().is_empty());
     }
 }
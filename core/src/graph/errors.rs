@@ -8,15 +8,79 @@ pub enum ErrorType {
     #[error("Malformed index: '{0}'")]
     MalformedIndex(String),
 
-    #[error("Invalid alias: '{0}'")]
+    #[error(
+        "Invalid alias: '{0}' (aliases can't be empty, contain whitespace, '/' or ':', start with '#' or '@', or look like an ID or a date)"
+    )]
     InvalidAlias(String),
 
+    #[error("Alias '{0}' is already taken by node {1}")]
+    AliasTaken(String, usize),
+
+    #[error("Unknown alias: '{0}'")]
+    UnknownAlias(String),
+
+    #[error("No node at path '{0}'")]
+    PathNotFound(String),
+
+    #[error("Path '{0}' matches several nodes: {candidates}", candidates = .1.join(", "))]
+    AmbiguousPath(String, Vec<String>),
+
+    #[error("Invalid tag: '{0}'")]
+    InvalidTag(String),
+
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
     #[error("Invalid date: '{0}'")]
     InvalidDate(String),
 
     #[error("Malformed date string: '{0}'")]
     MalformedDate(String),
 
+    #[error(
+        "Invalid recurrence rule: '{0}' (expected daily, weekdays, every:<N>, or monthly:<day>)"
+    )]
+    InvalidRecurRule(String),
+
+    #[error("Invalid recurrence: '{0}'")]
+    InvalidRecurrence(usize),
+
+    #[error(
+        "Dependency would form a cycle: {} (each task waits on the next)",
+        .0.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    DependencyCycle(Vec<usize>),
+
+    #[error(
+        "Link would form a cycle: {} (each node is a parent of the next)",
+        .0.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    LinkCycle(Vec<usize>),
+
+    #[error(
+        "Subgraph nodes form a cycle: {} (each node is a parent of the next)",
+        .0.iter().map(|i| i.to_string()).collect::<Vec<_>>().join(" -> ")
+    )]
+    SubgraphCycle(Vec<usize>),
+
+    #[error("Subgraph has no nodes")]
+    EmptySubgraph,
+
+    #[error("No timer is running")]
+    NoTimerRunning,
+
+    #[error("The timer was running on node {0}, which no longer exists; its time was discarded")]
+    TimerNodeMissing(usize),
+
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    #[error("Nothing to redo")]
+    NothingToRedo,
+
+    #[error("The graph was changed outside of the journal, cannot revert '{0}'")]
+    JournalOutOfSync(String),
+
     #[error("Graph looped back: {0}->...->{1}->{0}")]
     GraphLooped(usize, usize),
 
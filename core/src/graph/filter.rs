@@ -0,0 +1,78 @@
+//! Filtering and ordering of sibling nodes during traversal.
+
+use std::collections::HashSet;
+
+use super::node::task::Priority;
+use super::node::Node;
+use super::Graph;
+
+/// Additional filtering and ordering applied to every level of a traversal.
+#[derive(Clone, Debug, Default)]
+pub struct TraverseFilter {
+    /// Order siblings from the most to the least important task. Nodes of equal priority keep
+    /// their original order.
+    pub sort_by_priority: bool,
+    /// Only keep task nodes of at least this priority, along with the nodes leading to them.
+    /// Non-task nodes are always kept.
+    pub min_priority: Option<Priority>,
+    /// Only keep nodes carrying this tag (without the leading `#`), along with the nodes leading
+    /// to them.
+    pub tag: Option<String>,
+    /// Only keep tasks that are neither done nor blocked, along with the nodes leading to them.
+    pub ready: bool,
+}
+
+impl TraverseFilter {
+    /// Returns whether this filter would leave the given indices untouched.
+    pub fn is_noop(&self) -> bool {
+        !self.sort_by_priority && !self.filters()
+    }
+
+    /// Returns whether this filter drops any nodes, as opposed to only reordering them.
+    fn filters(&self) -> bool {
+        self.min_priority.is_some() || self.tag.is_some() || self.ready
+    }
+
+    /// Returns whether a node passes this filter by itself.
+    pub fn matches(&self, graph: &Graph, node: &Node) -> bool {
+        let priority = match (self.min_priority, node.data.as_task()) {
+            (Some(min), Some(task)) => task.priority >= min,
+            _ => true,
+        };
+        let tag = match &self.tag {
+            Some(tag) => node.metadata.tags.contains(tag),
+            None => true,
+        };
+        let ready = !self.ready || graph.is_ready(node.metadata.index);
+        priority && tag && ready
+    }
+
+    /// Returns whether a node or any of its descendants passes this filter. Used so that the
+    /// ancestors of matching nodes stay visible.
+    fn matches_subtree(&self, graph: &Graph, index: usize, visited: &mut HashSet<usize>) -> bool {
+        if !visited.insert(index) {
+            return false;
+        }
+        let node = graph.node(index);
+        self.matches(graph, node)
+            || node
+                .metadata
+                .children
+                .iter()
+                .any(|child| self.matches_subtree(graph, *child, visited))
+    }
+
+    /// Filters and reorders a list of sibling node indices in place.
+    pub fn apply(&self, graph: &Graph, indices: &mut Vec<usize>) {
+        if self.is_noop() {
+            return;
+        }
+        if self.filters() {
+            indices.retain(|i| self.matches_subtree(graph, *i, &mut HashSet::new()));
+        }
+        if self.sort_by_priority {
+            // Stable sort so that equally important siblings keep their manual ordering
+            indices.sort_by_key(|i| std::cmp::Reverse(graph.priority_of(*i)));
+        }
+    }
+}
@@ -0,0 +1,470 @@
+//! Undo/redo journal of graph mutations.
+//!
+//! Rather than mirroring every mutating method of [`Graph`] with a hand-written inverse, the
+//! journal records the difference a mutation made: the nodes and graph-wide indices it
+//! touched, before and after. Undoing restores the former, redoing the latter. This also covers
+//! changes made along the way, such as propagated task states.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use super::errors::ErrorType;
+use super::node::{self, Node};
+use super::observer::Event;
+use super::recur::Recurrence;
+use super::time::Timer;
+use super::{Graph, GraphResult};
+
+/// How many entries are kept. Older ones are forgotten.
+const MAX_ENTRIES: usize = 100;
+
+/// The graph-wide bookkeeping kept next to the nodes themselves
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Indices {
+    roots: Vec<usize>,
+    archived: Vec<usize>,
+    dates: HashMap<String, usize>,
+    aliases: HashMap<String, usize>,
+    tags: HashMap<String, Vec<usize>>,
+    recurrences: Vec<Recurrence>,
+    timer: Option<Timer>,
+}
+
+impl Indices {
+    fn of(graph: &Graph) -> Self {
+        Self {
+            roots: graph.roots.clone(),
+            archived: graph.archived.clone(),
+            dates: graph.dates.clone(),
+            aliases: graph.aliases.clone(),
+            tags: graph.tags.clone(),
+            recurrences: graph.recurrences.clone(),
+            timer: graph.timer,
+        }
+    }
+
+    fn restore(&self, graph: &mut Graph) {
+        graph.roots = self.roots.clone();
+        graph.archived = self.archived.clone();
+        graph.dates = self.dates.clone();
+        graph.aliases = self.aliases.clone();
+        graph.tags = self.tags.clone();
+        graph.recurrences = self.recurrences.clone();
+        graph.timer = self.timer;
+    }
+}
+
+/// A node that changed, with its contents before and after
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct NodeChange {
+    index: usize,
+    before: Option<Node>,
+    after: Option<Node>,
+}
+
+/// The difference between two states of a graph
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Patch {
+    next_id_before: usize,
+    next_id_after: usize,
+    nodes: Vec<NodeChange>,
+    /// Graph-wide indices before and after, if they changed
+    indices: Option<(Indices, Indices)>,
+}
+
+fn slot(graph: &Graph, index: usize) -> Option<Node> {
+    graph.node_checked(index).cloned()
+}
+
+impl Patch {
+    /// Returns the changes from `before` to `after`, or [`None`] if there are none.
+    fn diff(before: &Graph, after: &Graph) -> Option<Self> {
+        let mut indices: Vec<usize> = before
+            .slots
+            .keys()
+            .chain(after.slots.keys())
+            .copied()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        let nodes: Vec<NodeChange> = indices
+            .into_iter()
+            .filter_map(|index| {
+                let (before, after) = (slot(before, index), slot(after, index));
+                (before != after).then_some(NodeChange {
+                    index,
+                    before,
+                    after,
+                })
+            })
+            .collect();
+
+        let (indices_before, indices_after) = (Indices::of(before), Indices::of(after));
+        let indices = (indices_before != indices_after).then_some((indices_before, indices_after));
+
+        if nodes.is_empty() && indices.is_none() && before.next_id == after.next_id {
+            return None;
+        }
+        Some(Self {
+            next_id_before: before.next_id,
+            next_id_after: after.next_id,
+            nodes,
+            indices,
+        })
+    }
+
+    /// Extends this patch with `next`, which starts from the state this patch leaves the graph in.
+    fn then(&mut self, next: Patch) {
+        for change in next.nodes {
+            match self.nodes.iter_mut().find(|c| c.index == change.index) {
+                Some(existing) => existing.after = change.after,
+                None => self.nodes.push(change),
+            }
+        }
+        self.nodes.retain(|change| change.before != change.after);
+        self.next_id_after = next.next_id_after;
+
+        self.indices = match (self.indices.take(), next.indices) {
+            (Some((before, _)), Some((_, after))) => (before != after).then_some((before, after)),
+            (indices, None) | (None, indices) => indices,
+        };
+    }
+
+    /// Returns whether `graph` is in the state after (or before, when `forward` is false) this
+    /// patch, so that it can be reverted (or reapplied).
+    fn is_at(&self, graph: &Graph, forward: bool) -> bool {
+        let next_id = if forward {
+            self.next_id_after
+        } else {
+            self.next_id_before
+        };
+        if graph.next_id != next_id {
+            return false;
+        }
+        let nodes = self.nodes.iter().all(|change| {
+            let expected = if forward {
+                &change.after
+            } else {
+                &change.before
+            };
+            slot(graph, change.index) == *expected
+        });
+        let indices = match &self.indices {
+            Some((before, after)) => Indices::of(graph) == *if forward { after } else { before },
+            None => true,
+        };
+        nodes && indices
+    }
+
+    /// Brings `graph` to the state after (or before, when `forward` is false) this patch.
+    fn apply(&self, graph: &mut Graph, forward: bool) {
+        for change in self.nodes.iter() {
+            let node = if forward {
+                &change.after
+            } else {
+                &change.before
+            };
+            match node {
+                Some(node) => graph.put_node(node.clone()),
+                None => {
+                    graph.take_node(change.index);
+                }
+            }
+        }
+        // Nodes inserted by the patch leave empty slots behind when it is undone. Those at the end
+        // would otherwise count as used IDs once the graph is loaded again, see `Graph::reindex`.
+        while matches!(graph.nodes.last(), Some(None)) {
+            graph.nodes.pop();
+        }
+        graph.next_id = if forward {
+            self.next_id_after
+        } else {
+            self.next_id_before
+        };
+
+        if let Some((before, after)) = &self.indices {
+            if forward { after } else { before }.restore(graph);
+        }
+        graph.emit(Event::Reset);
+    }
+}
+
+/// A single undoable change, usually made by one command
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// Describes what was done, e.g. the command that was run
+    pub label: String,
+    /// When the change was made
+    pub time: NaiveDateTime,
+    patch: Patch,
+}
+
+/// History of graph mutations that can be undone and redone.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Journal {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl Journal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Changes that can be undone, the most recent last
+    pub fn undo_entries(&self) -> &[Entry] {
+        &self.undo
+    }
+
+    /// Undone changes that can be redone, the most recently undone last
+    pub fn redo_entries(&self) -> &[Entry] {
+        &self.redo
+    }
+
+    /// Records the changes made to a graph from `before` to `after`, forgetting anything that
+    /// could be redone. Does nothing if the graph did not change.
+    ///
+    /// # Returns
+    /// Whether anything was recorded.
+    pub fn record(&mut self, label: impl Into<String>, before: &Graph, after: &Graph) -> bool {
+        let Some(patch) = Patch::diff(before, after) else {
+            return false;
+        };
+
+        // The graph was changed without being recorded, so the history no longer applies
+        if self
+            .undo
+            .last()
+            .is_some_and(|entry| !entry.patch.is_at(before, true))
+        {
+            self.undo.clear();
+        }
+
+        self.redo.clear();
+        self.undo.push(Entry {
+            label: label.into(),
+            time: node::now(),
+            patch,
+        });
+        if self.undo.len() > MAX_ENTRIES {
+            self.undo.remove(0);
+        }
+        true
+    }
+
+    /// Folds the changes made to a graph from `before` to `after` into the most recent entry, so
+    /// that they are undone and redone along with it. This is meant for changes made on the user's
+    /// behalf, such as materializing recurring tasks, which shouldn't be undone on their own.
+    /// Changes that can no longer be redone afterwards are forgotten.
+    ///
+    /// # Returns
+    /// Whether the changes were folded in. They aren't if there is no entry to fold them into, or
+    /// if the graph was changed without being recorded since that entry.
+    pub fn amend(&mut self, before: &Graph, after: &Graph) -> bool {
+        let Some(patch) = Patch::diff(before, after) else {
+            return false;
+        };
+
+        if self
+            .redo
+            .last()
+            .is_some_and(|entry| !entry.patch.is_at(after, false))
+        {
+            self.redo.clear();
+        }
+
+        match self.undo.last_mut() {
+            Some(entry) if entry.patch.is_at(before, true) => {
+                entry.patch.then(patch);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Undoes up to `count` of the most recent changes.
+    ///
+    /// # Returns
+    /// The labels of the undone changes, the most recent first.
+    pub fn undo(&mut self, graph: &mut Graph, count: usize) -> GraphResult<Vec<String>> {
+        if self.undo.is_empty() {
+            return Err(ErrorType::NothingToUndo);
+        }
+        Self::step(&mut self.undo, &mut self.redo, graph, count, false)
+    }
+
+    /// Redoes up to `count` of the most recently undone changes.
+    ///
+    /// # Returns
+    /// The labels of the redone changes, in the order they were redone.
+    pub fn redo(&mut self, graph: &mut Graph, count: usize) -> GraphResult<Vec<String>> {
+        if self.redo.is_empty() {
+            return Err(ErrorType::NothingToRedo);
+        }
+        Self::step(&mut self.redo, &mut self.undo, graph, count, true)
+    }
+
+    fn step(
+        from: &mut Vec<Entry>,
+        to: &mut Vec<Entry>,
+        graph: &mut Graph,
+        count: usize,
+        forward: bool,
+    ) -> GraphResult<Vec<String>> {
+        let mut labels = vec![];
+        for _ in 0..count {
+            let Some(entry) = from.last() else {
+                break;
+            };
+            if !entry.patch.is_at(graph, !forward) {
+                return Err(ErrorType::JournalOutOfSync(entry.label.clone()));
+            }
+            entry.patch.apply(graph, forward);
+
+            let entry = from.pop().unwrap();
+            labels.push(entry.label.clone());
+            to.push(entry);
+        }
+        Ok(labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Indices, Journal};
+    use crate::graph::node::{task::TaskState, Node};
+    use crate::graph::{Graph, GraphGetters};
+
+    /// The state of a graph, regardless of where its nodes are stored
+    fn snapshot(graph: &Graph) -> (Vec<Node>, Indices, usize) {
+        let nodes = graph
+            .node_ids()
+            .into_iter()
+            .map(|id| graph.get_node(id))
+            .collect();
+        (nodes, Indices::of(graph), graph.next_id())
+    }
+
+    #[test]
+    fn undo_redo_roundtrip() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        graph.set_alias(child, "c".to_string()).unwrap();
+        let initial = snapshot(&graph);
+        let mut journal = Journal::new();
+
+        // Checking the child also completes the root
+        let before = graph.clone();
+        graph.set_task_state(child, TaskState::Done, true).unwrap();
+        assert!(journal.record("check", &before, &graph));
+        let checked = snapshot(&graph);
+
+        let before = graph.clone();
+        graph.remove_children_recursive(root).unwrap();
+        assert!(journal.record("rm -r", &before, &graph));
+        let removed = snapshot(&graph);
+
+        assert_eq!(journal.undo(&mut graph, 1).unwrap(), vec!["rm -r"]);
+        assert_eq!(snapshot(&graph), checked);
+        assert_eq!(graph.get_index("c").unwrap(), child);
+
+        assert_eq!(journal.undo(&mut graph, 5).unwrap(), vec!["check"]);
+        assert_eq!(snapshot(&graph), initial);
+        assert_eq!(
+            graph.get_node(root).data.as_task().unwrap().state,
+            TaskState::None
+        );
+
+        assert_eq!(journal.redo(&mut graph, 2).unwrap(), vec!["check", "rm -r"]);
+        assert_eq!(snapshot(&graph), removed);
+        assert!(journal.redo(&mut graph, 1).is_err());
+    }
+
+    #[test]
+    fn redo_after_reloading() {
+        let mut graph = Graph::new();
+        let mut journal = Journal::new();
+
+        let before = graph.clone();
+        let root = graph.insert_root("root".to_string(), false);
+        graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        journal.record("add", &before, &graph);
+        let added = snapshot(&graph);
+
+        // Undoing and redoing may happen in separate runs, with the graph saved in between
+        journal.undo(&mut graph, 1).unwrap();
+        let serialized = serde_yaml_ng::to_string(&graph).unwrap();
+        let mut graph: Graph = serde_yaml_ng::from_str(&serialized).unwrap();
+        assert_eq!(graph.next_id(), 0);
+        journal.redo(&mut graph, 1).unwrap();
+        assert_eq!(snapshot(&graph), added);
+    }
+
+    #[test]
+    fn amend_folds_into_last_entry() {
+        let mut graph = Graph::new();
+        let mut journal = Journal::new();
+
+        // Nothing to fold into yet
+        let before = graph.clone();
+        let auto = graph.insert_root("auto".to_string(), false);
+        assert!(!journal.amend(&before, &graph));
+        let initial = snapshot(&graph);
+
+        let before = graph.clone();
+        let root = graph.insert_root("root".to_string(), false);
+        assert!(journal.record("add", &before, &graph));
+
+        let before = graph.clone();
+        graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        graph.rename_node(auto, "renamed".to_string()).unwrap();
+        assert!(journal.amend(&before, &graph));
+        assert!(!journal.amend(&graph.clone(), &graph));
+        let amended = snapshot(&graph);
+
+        // The folded changes go along with the entry
+        assert_eq!(journal.undo_entries().len(), 1);
+        assert_eq!(journal.undo(&mut graph, 1).unwrap(), vec!["add"]);
+        assert_eq!(snapshot(&graph), initial);
+        assert_eq!(journal.redo(&mut graph, 1).unwrap(), vec!["add"]);
+        assert_eq!(snapshot(&graph), amended);
+
+        // Redoing is still possible as long as the graph is where the undone entry left it
+        journal.undo(&mut graph, 1).unwrap();
+        assert!(!journal.amend(&graph.clone(), &graph));
+        assert_eq!(journal.redo_entries().len(), 1);
+        let before = graph.clone();
+        graph.insert_root("other".to_string(), false);
+        assert!(!journal.amend(&before, &graph));
+        assert!(journal.redo_entries().is_empty());
+    }
+
+    #[test]
+    fn refuses_to_undo_unrecorded_changes() {
+        let mut graph = Graph::new();
+        let mut journal = Journal::new();
+
+        let before = graph.clone();
+        let root = graph.insert_root("root".to_string(), false);
+        journal.record("add", &before, &graph);
+
+        graph.rename_node(root, "renamed".to_string()).unwrap();
+        assert!(journal.undo(&mut graph, 1).is_err());
+        assert_eq!(graph.get_node(root).title, "renamed");
+
+        // Recording on top of unrecorded changes forgets the stale history
+        let before = graph.clone();
+        graph.insert_root("other".to_string(), false);
+        journal.record("add", &before, &graph);
+        assert_eq!(journal.undo_entries().len(), 1);
+    }
+}
@@ -1,6 +1,9 @@
+use chrono::{Local, NaiveDateTime};
 use date::DateData;
-use serde::{Deserialize, Serialize};
-use task::TaskData;
+use serde::{Deserialize, Deserializer, Serialize};
+use task::{Propagation, TaskData, TaskState};
+
+use super::time::Interval;
 
 pub mod date;
 pub mod task;
@@ -10,6 +13,9 @@ pub struct Node {
     pub title: String,
     pub data: NodeType,
     pub metadata: NodeMetadata,
+    /// Optional long-form, possibly multi-line, body
+    #[serde(default)]
+    pub note: Option<String>,
 }
 
 impl Node {
@@ -19,19 +25,35 @@ impl Node {
             title: message,
             data,
             metadata: NodeMetadata::new(index),
+            note: None,
         }
     }
 
-    /// Maps the locally stored indices (self, parents, and children) using a slice
-    /// Where an index `i` gets mapped into a `map[i]` where `map[i]` **MUST BE** a `Some(usize)`
-    pub fn map_indices(&mut self, map: &[Option<usize>]) {
-        self.metadata.index = map[self.metadata.index].unwrap();
-        for i in self.metadata.parents.iter_mut() {
-            *i = map[*i].unwrap();
-        }
-        for i in self.metadata.children.iter_mut() {
-            *i = map[*i].unwrap();
+    /// Marks this node as modified now
+    pub fn touch(&mut self) {
+        self.metadata.modified = Some(now());
+    }
+
+    /// Sets the state of a task node, updating its modification and completion times if the state
+    /// changed.
+    ///
+    /// # Returns
+    /// [`None`] if this is not a task node, otherwise whether the state changed.
+    pub fn set_task_state(&mut self, state: TaskState) -> Option<bool> {
+        let task = self.data.as_task_mut()?;
+        if task.state == state {
+            return Some(false);
         }
+        task.state = state;
+
+        let now = now();
+        self.metadata.modified = Some(now);
+        self.metadata.completed = if state == TaskState::Done {
+            Some(now)
+        } else {
+            None
+        };
+        Some(true)
     }
 }
 
@@ -102,20 +124,78 @@ impl Default for NodeType {
 pub struct NodeMetadata {
     pub archived: bool,
     pub index: usize,
-    pub alias: Option<String>,
+    /// Names the node can be addressed by besides its ID, in the order they were given. Nodes
+    /// written before this list, such as in old blueprints, had a single optional `alias`.
+    #[serde(default, alias = "alias", deserialize_with = "deserialize_aliases")]
+    pub aliases: Vec<String>,
     pub parents: Vec<usize>,
     pub children: Vec<usize>,
+    /// Free-form labels, stored without the leading `#`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// When the node was created. `None` for nodes made before this was tracked.
+    #[serde(default)]
+    pub created: Option<NaiveDateTime>,
+    /// When the node itself (not its children) was last changed
+    #[serde(default)]
+    pub modified: Option<NaiveDateTime>,
+    /// When the task was completed. Cleared once it is no longer done.
+    #[serde(default)]
+    pub completed: Option<NaiveDateTime>,
+    /// Tasks that have to be done before this one can be started
+    #[serde(default)]
+    pub blocked_by: Vec<usize>,
+    /// Tasks waiting on this one. The inverse of `blocked_by`.
+    #[serde(default)]
+    pub blocks: Vec<usize>,
+    /// Time worked on the node itself, oldest first
+    #[serde(default)]
+    pub time_log: Vec<Interval>,
+    /// How task states propagate through this node, if it differs from the graph's
+    #[serde(default)]
+    pub propagation: Option<Propagation>,
+}
+
+/// Reads either a list of aliases or the single, optional alias of older documents
+fn deserialize_aliases<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Aliases {
+        List(Vec<String>),
+        Legacy(Option<String>),
+    }
+
+    Ok(match Aliases::deserialize(deserializer)? {
+        Aliases::List(aliases) => aliases,
+        Aliases::Legacy(alias) => alias.into_iter().collect(),
+    })
 }
 
 impl NodeMetadata {
     /// Constructs fresh node metadata from an index
     pub fn new(index: usize) -> Self {
+        let now = now();
         Self {
             archived: false,
             index,
-            alias: None,
+            aliases: vec![],
             parents: vec![],
             children: vec![],
+            tags: vec![],
+            created: Some(now),
+            modified: Some(now),
+            completed: None,
+            blocked_by: vec![],
+            blocks: vec![],
+            time_log: vec![],
+            propagation: None,
         }
     }
 }
+
+/// The current local time, as used for node timestamps
+pub(crate) fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
@@ -1,14 +1,169 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct TaskData {
     pub state: TaskState,
+    /// When this task is due by. Unlike linking a task under a date node ("do on"), this marks a
+    /// deadline.
+    #[serde(default)]
+    pub due: Option<DueDate>,
+    #[serde(default)]
+    pub priority: Priority,
+    /// How much effort the task takes, in whatever unit it is estimated in. Tasks without an
+    /// estimate weigh as much as their subtasks put together, or 1 if they have none.
+    #[serde(default)]
+    pub estimate: Option<u32>,
 }
 
 #[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum)]
 pub enum TaskState {
     #[default]
     None,
+    /// Some subtasks are done. Derived from the subtasks' states.
     Partial,
+    /// Being worked on
+    InProgress,
+    /// Can't be worked on until something else happens
+    Blocked,
+    /// Waiting on someone else
+    Waiting,
     Done,
+    /// Won't be done. Doesn't count towards its parents' completion.
+    Cancelled,
+}
+
+impl TaskState {
+    /// Returns whether nothing is left to do on the task
+    pub fn is_closed(self) -> bool {
+        matches!(self, TaskState::Done | TaskState::Cancelled)
+    }
+
+    /// Returns whether the state was set by the user, in which case subtasks don't change it
+    /// unless they are all done
+    pub fn is_explicit(self) -> bool {
+        matches!(
+            self,
+            TaskState::InProgress | TaskState::Blocked | TaskState::Waiting | TaskState::Cancelled
+        )
+    }
+
+    /// Returns whether setting this state on a task also sets it on its subtasks
+    pub fn cascades(self) -> bool {
+        matches!(
+            self,
+            TaskState::None | TaskState::Done | TaskState::Cancelled
+        )
+    }
+}
+
+/// How important a task is. Variants are ordered from least to most important.
+#[derive(
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    clap::ValueEnum,
+)]
+pub enum Priority {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+/// How a task's state follows the states of its subtasks
+#[derive(Copy, Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq, clap::ValueEnum)]
+pub enum Rollup {
+    /// Partially done once any subtask is, and done once every subtask is
+    #[default]
+    Auto,
+    /// Like [`Rollup::Auto`], but stays partially done until explicitly checked
+    Partial,
+    /// Only ever changed explicitly
+    Manual,
+}
+
+/// How task states propagate through the graph when a task is checked or unchecked
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Propagation {
+    /// How the task follows its subtasks
+    pub rollup: Rollup,
+    /// Whether setting the task's state also sets it on its subtasks
+    pub cascade: bool,
+}
+
+impl Default for Propagation {
+    fn default() -> Self {
+        Self {
+            rollup: Rollup::Auto,
+            cascade: true,
+        }
+    }
+}
+
+/// How much of a node's effort is done, see [`crate::graph::Graph::progress`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Progress {
+    /// Effort done, which may be fractional for partially done subtasks
+    pub done: f64,
+    /// Total effort
+    pub total: u32,
+}
+
+impl Progress {
+    /// Returns the done fraction of the effort, from 0 to 1. Nodes with no effort are not done.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.done / self.total as f64
+        }
+    }
+}
+
+/// A deadline for a task, with an optional time of day.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DueDate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+impl DueDate {
+    pub fn new(date: NaiveDate, time: Option<NaiveTime>) -> Self {
+        Self { date, time }
+    }
+
+    /// Returns the last instant at which the task is still not overdue. Due dates without a time
+    /// are due by the end of the day.
+    pub fn deadline(&self) -> NaiveDateTime {
+        match self.time {
+            Some(time) => self.date.and_time(time),
+            None => self
+                .date
+                .and_hms_opt(23, 59, 59)
+                .expect("23:59:59 should be a valid time"),
+        }
+    }
+
+    /// Returns whether the deadline has passed at `now`.
+    pub fn is_passed(&self, now: NaiveDateTime) -> bool {
+        now > self.deadline()
+    }
+}
+
+impl TaskData {
+    /// Returns whether this task is past its due date at `now` without being completed.
+    pub fn is_overdue(&self, now: NaiveDateTime) -> bool {
+        !self.state.is_closed() && self.due.is_some_and(|due| due.is_passed(now))
+    }
 }
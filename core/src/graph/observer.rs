@@ -0,0 +1,225 @@
+//! Change notifications for frontends.
+//!
+//! Every mutating method of [`Graph`] reports what it changed to the graph's observers as
+//! [`Event`]s, including changes made along the way, such as propagated task states. Changes
+//! made through [`GraphGetters::get_node_mut`](super::GraphGetters::get_node_mut) bypass the
+//! graph and are not reported.
+
+use std::fmt;
+
+use super::node::task::TaskState;
+use super::Graph;
+
+/// A change made to a graph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    NodeInserted {
+        index: usize,
+    },
+    NodeRemoved {
+        index: usize,
+    },
+    Linked {
+        parent: usize,
+        child: usize,
+    },
+    Unlinked {
+        parent: usize,
+        child: usize,
+    },
+    /// A task's state changed, either because it was set or because it propagated from a
+    /// related task
+    StateChanged {
+        index: usize,
+        from: TaskState,
+        to: TaskState,
+    },
+    Archived {
+        index: usize,
+        archived: bool,
+    },
+    AliasSet {
+        index: usize,
+        alias: String,
+    },
+    AliasUnset {
+        index: usize,
+        alias: String,
+    },
+    TagAdded {
+        index: usize,
+        tag: String,
+    },
+    TagRemoved {
+        index: usize,
+        tag: String,
+    },
+    DependencyAdded {
+        index: usize,
+        blocker: usize,
+    },
+    DependencyRemoved {
+        index: usize,
+        blocker: usize,
+    },
+    /// The children of `parent` were reordered
+    Reordered {
+        parent: usize,
+    },
+    /// Something else about a node changed: its title, note, due date, priority, estimate or
+    /// propagation policy
+    NodeChanged {
+        index: usize,
+    },
+    TimerStarted {
+        index: usize,
+    },
+    TimerStopped {
+        index: usize,
+    },
+    RecurrenceAdded {
+        index: usize,
+    },
+    RecurrenceRemoved {
+        index: usize,
+    },
+    /// The graph changed in a way the other events don't describe, such as being cleaned,
+    /// repaired, rolled back or restored from the journal. Anything read from it may be stale.
+    Reset,
+}
+
+/// Something notified of every change made to a graph, see [`Graph::add_observer`].
+/// Closures taking an [`Event`] are observers too.
+pub trait Observer: Send + Sync {
+    fn notify(&self, event: &Event);
+}
+
+impl<F: Fn(&Event) + Send + Sync> Observer for F {
+    fn notify(&self, event: &Event) {
+        self(event)
+    }
+}
+
+/// The observers of a graph. Copies of a graph start without any, so that changes made to a
+/// copy are not mistaken for changes to the original.
+#[derive(Default)]
+pub(crate) struct Observers(Vec<Box<dyn Observer>>);
+
+impl Clone for Observers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl Graph {
+    /// Registers an observer to be notified of every later change to the graph
+    pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+        self.observers.0.push(Box::new(observer));
+    }
+
+    /// Drops every registered observer
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    pub(crate) fn emit(&self, event: Event) {
+        for observer in &self.observers.0 {
+            observer.notify(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::graph::node::task::TaskState;
+    use crate::graph::Graph;
+
+    use super::Event;
+
+    #[test]
+    fn observers_see_every_change() {
+        let mut graph = Graph::new();
+        let events = Arc::new(Mutex::new(vec![]));
+        let log = Arc::clone(&events);
+        graph.add_observer(move |event: &Event| log.lock().unwrap().push(event.clone()));
+        let take = || std::mem::take(&mut *events.lock().unwrap());
+
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        assert_eq!(
+            take(),
+            vec![
+                Event::NodeInserted { index: root },
+                Event::NodeInserted { index: child },
+                Event::Linked {
+                    parent: root,
+                    child
+                },
+            ]
+        );
+
+        // Propagated states are reported too
+        graph.set_task_state(child, TaskState::Done, true).unwrap();
+        assert_eq!(
+            take(),
+            vec![
+                Event::StateChanged {
+                    index: child,
+                    from: TaskState::None,
+                    to: TaskState::Done
+                },
+                Event::StateChanged {
+                    index: root,
+                    from: TaskState::None,
+                    to: TaskState::Done
+                },
+            ]
+        );
+
+        graph.set_alias(child, "c".to_string()).unwrap();
+        graph.add_tag(child, "work").unwrap();
+        graph.remove(child).unwrap();
+        assert_eq!(
+            take(),
+            vec![
+                Event::AliasSet {
+                    index: child,
+                    alias: "c".to_string()
+                },
+                Event::TagAdded {
+                    index: child,
+                    tag: "work".to_string()
+                },
+                Event::AliasUnset {
+                    index: child,
+                    alias: "c".to_string()
+                },
+                Event::TagRemoved {
+                    index: child,
+                    tag: "work".to_string()
+                },
+                Event::StateChanged {
+                    index: root,
+                    from: TaskState::Done,
+                    to: TaskState::None
+                },
+                Event::NodeRemoved { index: child },
+            ]
+        );
+
+        // Copies don't report to the original's observers, and rolling back is reported once
+        graph.clone().insert_root("copy".to_string(), false);
+        let _ = graph.transaction(|graph| graph.link(root, root));
+        assert_eq!(take(), vec![Event::Reset]);
+    }
+}
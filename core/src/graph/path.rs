@@ -0,0 +1,171 @@
+//! Addressing nodes by path instead of by ID.
+//!
+//! A path such as `work/website/deploy` walks down from the top of the graph, each part naming a
+//! child of the node before it by title, alias or ID. Titles are compared exactly first, and
+//! regardless of case if no title matches exactly. The first part names a root or a date node,
+//! unless it is written `alias:<alias>`, in which case the walk starts at the aliased node, e.g.
+//! `alias:proj/2`.
+
+use super::errors::ErrorType;
+use super::node::{Node, NodeType};
+use super::{Graph, GraphResult};
+
+/// Prefix of a path's first part that starts the walk at an aliased node
+const ALIAS_PREFIX: &str = "alias:";
+
+/// Returns whether an ID is meant as a path
+pub(crate) fn is_path(id: &str) -> bool {
+    id.contains('/') || id.starts_with(ALIAS_PREFIX)
+}
+
+/// Returns the name a node goes by in paths: its title, or its date for untitled date nodes
+pub fn name_of(node: &Node) -> String {
+    match &node.data {
+        NodeType::Date(data) if node.title.is_empty() => data.date.to_string(),
+        _ => node.title.clone(),
+    }
+}
+
+impl Graph {
+    /// Returns the nodes among `candidates` that a path part names
+    fn match_part(&self, candidates: &[usize], part: &str) -> Vec<usize> {
+        let nodes = candidates.iter().map(|&i| self.node(i));
+        let exact: Vec<usize> = nodes
+            .clone()
+            .filter(|node| {
+                name_of(node) == part
+                    || node.metadata.aliases.iter().any(|alias| alias == part)
+                    || node.metadata.index.to_string() == part
+                    || node
+                        .data
+                        .as_date()
+                        .is_some_and(|d| d.date.to_string() == part)
+            })
+            .map(|node| node.metadata.index)
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+        let part = part.to_lowercase();
+        nodes
+            .filter(|node| name_of(node).to_lowercase() == part)
+            .map(|node| node.metadata.index)
+            .collect()
+    }
+
+    /// Returns the node a path leads to. Fails with [`ErrorType::PathNotFound`] if a part names
+    /// no node, and with [`ErrorType::AmbiguousPath`] if it names several.
+    pub fn resolve_path(&self, path: &str) -> GraphResult<usize> {
+        let mut parts = path.split('/').filter(|part| !part.is_empty()).peekable();
+        let mut walked = String::new();
+
+        let mut current = match parts
+            .peek()
+            .and_then(|part| part.strip_prefix(ALIAS_PREFIX))
+        {
+            Some(alias) => {
+                let index = *self
+                    .aliases
+                    .get(alias)
+                    .ok_or_else(|| ErrorType::UnknownAlias(alias.to_string()))?;
+                walked.push_str(parts.next().unwrap());
+                Some(index)
+            }
+            None => None,
+        };
+
+        for part in parts {
+            if !walked.is_empty() {
+                walked.push('/');
+            }
+            walked.push_str(part);
+
+            let candidates = match current {
+                Some(index) => self.node(index).metadata.children.clone(),
+                None => {
+                    let mut top = self.roots.clone();
+                    top.extend(self.dates.values());
+                    top.sort_unstable();
+                    top
+                }
+            };
+            current = match self.match_part(&candidates, part)[..] {
+                [] => return Err(ErrorType::PathNotFound(walked)),
+                [index] => Some(index),
+                ref matches => {
+                    let candidates = matches
+                        .iter()
+                        .map(|&i| format!("{i} ({})", self.path_of(i)))
+                        .collect();
+                    return Err(ErrorType::AmbiguousPath(walked, candidates));
+                }
+            };
+        }
+        current.ok_or(ErrorType::PathNotFound(path.to_string()))
+    }
+
+    /// Returns a path to a node, following its first parents from the top of the graph
+    pub fn path_of(&self, index: usize) -> String {
+        let mut path = self.ancestor_path(index);
+        path.push(index);
+        path.iter()
+            .map(|&i| name_of(self.node(i)))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::errors::ErrorType;
+    use crate::graph::Graph;
+
+    #[test]
+    fn resolve_paths() {
+        let mut graph = Graph::new();
+        let work = graph.insert_root("Work".to_string(), false);
+        let website = graph
+            .insert_child("website".to_string(), work, false)
+            .unwrap();
+        let deploy = graph
+            .insert_child("deploy".to_string(), website, false)
+            .unwrap();
+        let other = graph
+            .insert_child("deploy".to_string(), website, false)
+            .unwrap();
+        let two = graph.insert_child("2".to_string(), work, false).unwrap();
+        graph.set_alias(work, "proj".to_string()).unwrap();
+
+        assert_eq!(graph.get_index("work/website").unwrap(), website);
+        assert_eq!(graph.get_index("alias:proj/2").unwrap(), two);
+        assert_eq!(graph.get_index("alias:proj").unwrap(), work);
+        assert_eq!(
+            graph.get_index(&format!("proj/website/{other}")).unwrap(),
+            other
+        );
+        assert_eq!(graph.path_of(deploy), "Work/website/deploy");
+
+        // Sigils for indices and aliases
+        assert_eq!(graph.get_index(&format!("#{deploy}")).unwrap(), deploy);
+        assert_eq!(graph.get_index(":proj").unwrap(), work);
+        assert_eq!(graph.get_index(":proj/website").unwrap(), website);
+        assert!(matches!(
+            graph.get_index(":nope"),
+            Err(ErrorType::UnknownAlias(_))
+        ));
+
+        assert!(matches!(
+            graph.get_index("Work/website/deploy"),
+            Err(ErrorType::AmbiguousPath(_, candidates)) if candidates.len() == 2
+        ));
+        assert!(matches!(
+            graph.get_index("Work/blog/deploy"),
+            Err(ErrorType::PathNotFound(walked)) if walked == "Work/blog"
+        ));
+
+        // Paths stay valid when the storage is compacted
+        graph.remove(two).unwrap();
+        graph.clean();
+        assert_eq!(graph.get_index("work/website").unwrap(), website);
+    }
+}
@@ -0,0 +1,527 @@
+//! A small query language for selecting nodes.
+//!
+//! A query is a whitespace-separated list of terms, all of which a node has to match, e.g.
+//! `state:partial tag:work due<7d under:project-x depth<=2 archived:false`. A term is either a
+//! word to look for in titles or a `<field><op><value>` condition, and is negated by a leading
+//! `-`. Words and values containing spaces can be double-quoted.
+//!
+//! Fields:
+//! - `title:<word>`: the title contains the word (same as a bare word).
+//! - `state:<none|partial|in-progress|blocked|waiting|done|cancelled>`: task state.
+//! - `type:<task|date|pseudo>`: node type.
+//! - `tag:<tag>`: the node carries the tag.
+//! - `due:<none|any|overdue>`, `due<op><date>`: task deadline.
+//! - `priority<op><none|low|medium|high|urgent>`: task priority.
+//! - `under:<ID>`: descendants of a node, by index, alias, path, or `YYYY-MM-DD` date.
+//! - `depth<op><N>`: distance from the `under:` node, or from the top of the graph (roots and
+//!   date nodes are at depth 0).
+//! - `archived:<bool>`, `ready:<bool>`, `blocked:<bool>`.
+//!
+//! Comparisons (`<op>`) are one of `:` or `=`, `<`, `<=`, `>`, `>=`. Dates are `today`,
+//! `tomorrow`, `yesterday`, `YYYY-MM-DD`, or an offset from today such as `7d`, `-3d` or `2w`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+use chrono::{Days, NaiveDate};
+use clap::ValueEnum;
+
+use super::errors::ErrorType;
+use super::node::task::{Priority, TaskState};
+use super::node::{self, Node, NodeType};
+use super::{Graph, GraphGetters, GraphResult};
+
+/// How a field is compared to a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Cmp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    fn test<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Eq => left == right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+        }
+    }
+}
+
+/// The kinds of node a `type:` term selects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Task,
+    Date,
+    Pseudo,
+}
+
+/// A condition on task deadlines
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Due {
+    None,
+    Any,
+    Overdue,
+    /// Compared against a date
+    Date(Cmp, DateRef),
+}
+
+/// A date, possibly relative to the day the query is evaluated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DateRef {
+    Absolute(NaiveDate),
+    /// Days from today
+    Relative(i64),
+}
+
+impl DateRef {
+    fn resolve(&self, today: NaiveDate) -> NaiveDate {
+        match *self {
+            DateRef::Absolute(date) => date,
+            DateRef::Relative(days) if days >= 0 => today + Days::new(days as u64),
+            DateRef::Relative(days) => today - Days::new(days.unsigned_abs()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Condition {
+    /// Lowercased word the title has to contain
+    Text(String),
+    State(TaskState),
+    Type(Kind),
+    Tag(String),
+    Due(Due),
+    Priority(Cmp, Priority),
+    Under(String),
+    Depth(Cmp, u32),
+    Archived(bool),
+    Ready(bool),
+    Blocked(bool),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+/// A parsed query. See the [module documentation](self) for the syntax.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+impl Query {
+    /// Returns whether the query has any `field:value` condition, as opposed to only searching
+    /// titles for words.
+    pub fn has_fields(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| !matches!(term.condition, Condition::Text(_)))
+    }
+
+    /// Returns the (lowercased) words titles are searched for, excluding negated ones
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.terms.iter().filter_map(|term| match &term.condition {
+            Condition::Text(word) if !term.negated => Some(word.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the indices of the nodes matching this query, in increasing order.
+    pub fn eval(&self, graph: &Graph) -> GraphResult<Vec<usize>> {
+        let context = Context::new(self, graph)?;
+        Ok(graph
+            .node_ids()
+            .into_iter()
+            .filter(|index| {
+                let node = graph.node(*index);
+                self.terms
+                    .iter()
+                    .enumerate()
+                    .all(|(i, term)| context.matches(graph, node, i, term) != term.negated)
+            })
+            .collect())
+    }
+}
+
+/// What a query needs to know about the graph as a whole, computed once per evaluation
+struct Context {
+    today: NaiveDate,
+    /// Descendants of each `under:` term's node, by term position
+    descendants: HashMap<usize, HashSet<usize>>,
+    /// Distance of every reachable node from the depth anchors
+    depths: HashMap<usize, u32>,
+}
+
+impl Context {
+    fn new(query: &Query, graph: &Graph) -> GraphResult<Self> {
+        let mut descendants = HashMap::new();
+        let mut anchors = vec![];
+        for (i, term) in query.terms.iter().enumerate() {
+            if let Condition::Under(id) = &term.condition {
+                let index = resolve(graph, id)?;
+                let mut depths = distances(graph, &[index]);
+                depths.remove(&index);
+                descendants.insert(i, depths.into_keys().collect());
+                if !term.negated {
+                    anchors.push(index);
+                }
+            }
+        }
+        if anchors.is_empty() {
+            anchors.extend(graph.get_roots());
+            anchors.extend(graph.get_dates().values());
+        }
+
+        Ok(Self {
+            today: node::now().date(),
+            descendants,
+            depths: distances(graph, &anchors),
+        })
+    }
+
+    /// Returns whether a node satisfies the condition of the `i`th term, ignoring negation
+    fn matches(&self, graph: &Graph, node: &Node, i: usize, term: &Term) -> bool {
+        let index = node.metadata.index;
+        let task = node.data.as_task();
+        match &term.condition {
+            Condition::Text(word) => node.title.to_lowercase().contains(word),
+            Condition::State(state) => task.is_some_and(|task| task.state == *state),
+            Condition::Type(kind) => {
+                let actual = match node.data {
+                    NodeType::Task(_) => Kind::Task,
+                    NodeType::Date(_) => Kind::Date,
+                    NodeType::Pseudo => Kind::Pseudo,
+                };
+                actual == *kind
+            }
+            Condition::Tag(tag) => node.metadata.tags.contains(tag),
+            Condition::Due(due) => {
+                let Some(task) = task else {
+                    return false;
+                };
+                match due {
+                    Due::None => task.due.is_none(),
+                    Due::Any => task.due.is_some(),
+                    Due::Overdue => task.is_overdue(node::now()),
+                    Due::Date(cmp, date) => task
+                        .due
+                        .is_some_and(|due| cmp.test(due.date, date.resolve(self.today))),
+                }
+            }
+            Condition::Priority(cmp, priority) => {
+                task.is_some_and(|task| cmp.test(task.priority, *priority))
+            }
+            Condition::Under(_) => self.descendants[&i].contains(&index),
+            Condition::Depth(cmp, depth) => self
+                .depths
+                .get(&index)
+                .is_some_and(|actual| cmp.test(*actual, *depth)),
+            Condition::Archived(archived) => node.metadata.archived == *archived,
+            Condition::Ready(ready) => graph.is_ready(index) == *ready,
+            Condition::Blocked(blocked) => graph.is_blocked(index) == *blocked,
+        }
+    }
+}
+
+/// Returns the node index an `under:` term refers to
+fn resolve(graph: &Graph, id: &str) -> GraphResult<usize> {
+    graph
+        .get_index(id)
+        .or_else(|err| match NaiveDate::parse_from_str(id, "%Y-%m-%d") {
+            Ok(date) => graph.get_date_index(&date),
+            Err(_) => Err(err),
+        })
+}
+
+/// Returns the shortest distance from any of `anchors` to every node reachable from them
+fn distances(graph: &Graph, anchors: &[usize]) -> HashMap<usize, u32> {
+    let mut depths = HashMap::new();
+    let mut queue = VecDeque::new();
+    for anchor in anchors {
+        depths.insert(*anchor, 0);
+        queue.push_back(*anchor);
+    }
+    while let Some(index) = queue.pop_front() {
+        let depth = depths[&index];
+        for child in graph.get_node_children(index) {
+            if let std::collections::hash_map::Entry::Vacant(entry) = depths.entry(child) {
+                entry.insert(depth + 1);
+                queue.push_back(child);
+            }
+        }
+    }
+    depths
+}
+
+/// Splits a query into terms at whitespace, keeping double-quoted parts together
+fn tokenize(input: &str) -> GraphResult<Vec<String>> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut in_token = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_token = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if quoted {
+        return Err(ErrorType::InvalidQuery(format!(
+            "unclosed quote in '{input}'"
+        )));
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Splits a term into its field, comparison and value, if it has a field
+fn split_field(term: &str) -> Option<(&str, Cmp, &str)> {
+    let start = term.find([':', '=', '<', '>'])?;
+    let field = &term[..start];
+    if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let rest = &term[start..];
+    let (cmp, len) = if rest.starts_with("<=") {
+        (Cmp::Le, 2)
+    } else if rest.starts_with(">=") {
+        (Cmp::Ge, 2)
+    } else if rest.starts_with('<') {
+        (Cmp::Lt, 1)
+    } else if rest.starts_with('>') {
+        (Cmp::Gt, 1)
+    } else {
+        (Cmp::Eq, 1)
+    };
+    Some((field, cmp, &rest[len..]))
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Returns whether `value` reads as a date in queries
+pub(crate) fn is_date(value: &str) -> bool {
+    parse_date(value).is_some()
+}
+
+fn parse_date(value: &str) -> Option<DateRef> {
+    match value.to_lowercase().as_str() {
+        "today" => return Some(DateRef::Relative(0)),
+        "tomorrow" => return Some(DateRef::Relative(1)),
+        "yesterday" => return Some(DateRef::Relative(-1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(DateRef::Absolute(date));
+    }
+    let (count, unit) = value.split_at(value.len().checked_sub(1)?);
+    let count = count.parse::<i64>().ok()?;
+    match unit {
+        "d" => Some(DateRef::Relative(count)),
+        "w" => Some(DateRef::Relative(count * 7)),
+        _ => None,
+    }
+}
+
+fn parse_term(token: &str) -> GraphResult<Term> {
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let invalid = |reason: &str| ErrorType::InvalidQuery(format!("{reason} in '{token}'"));
+
+    let Some((field, cmp, value)) = split_field(token) else {
+        return Ok(Term {
+            negated,
+            condition: Condition::Text(token.to_lowercase()),
+        });
+    };
+    if value.is_empty() {
+        return Err(invalid("missing value"));
+    }
+    let equality = || {
+        if cmp == Cmp::Eq {
+            Ok(())
+        } else {
+            Err(invalid("only ':' can be used"))
+        }
+    };
+    let boolean = || parse_bool(value).ok_or_else(|| invalid("expected true or false"));
+
+    let condition = match field.to_lowercase().as_str() {
+        "title" => {
+            equality()?;
+            Condition::Text(value.to_lowercase())
+        }
+        "state" => {
+            equality()?;
+            Condition::State(
+                TaskState::from_str(value, true).map_err(|_| invalid("unknown task state"))?,
+            )
+        }
+        "type" => {
+            equality()?;
+            Condition::Type(match value.to_lowercase().as_str() {
+                "task" => Kind::Task,
+                "date" => Kind::Date,
+                "pseudo" => Kind::Pseudo,
+                _ => return Err(invalid("unknown node type")),
+            })
+        }
+        "tag" => {
+            equality()?;
+            Condition::Tag(Graph::normalize_tag(value)?)
+        }
+        "due" => Condition::Due(match (cmp, value.to_lowercase().as_str()) {
+            (Cmp::Eq, "none") => Due::None,
+            (Cmp::Eq, "any") => Due::Any,
+            (Cmp::Eq, "overdue") => Due::Overdue,
+            _ => Due::Date(
+                cmp,
+                parse_date(value).ok_or_else(|| invalid("invalid date"))?,
+            ),
+        }),
+        "priority" => Condition::Priority(
+            cmp,
+            Priority::from_str(value, true).map_err(|_| invalid("unknown priority"))?,
+        ),
+        "under" => {
+            equality()?;
+            Condition::Under(value.to_string())
+        }
+        "depth" => Condition::Depth(
+            cmp,
+            value.parse().map_err(|_| invalid("expected a number"))?,
+        ),
+        "archived" => {
+            equality()?;
+            Condition::Archived(boolean()?)
+        }
+        "ready" => {
+            equality()?;
+            Condition::Ready(boolean()?)
+        }
+        "blocked" => {
+            equality()?;
+            Condition::Blocked(boolean()?)
+        }
+        _ => return Err(invalid("unknown field")),
+    };
+    Ok(Term { negated, condition })
+}
+
+impl FromStr for Query {
+    type Err = ErrorType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let terms = tokenize(s)?
+            .iter()
+            .map(|token| parse_term(token))
+            .collect::<GraphResult<Vec<_>>>()?;
+        Ok(Self { terms })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+    use crate::graph::node::task::{DueDate, TaskState};
+    use crate::graph::{node, Graph};
+
+    fn eval(graph: &Graph, query: &str) -> Vec<usize> {
+        query.parse::<Query>().unwrap().eval(graph).unwrap()
+    }
+
+    #[test]
+    fn query_fields() {
+        let mut graph = Graph::new();
+        let project = graph.insert_root("Project X".to_string(), true);
+        graph.set_alias(project, "project-x".to_string()).unwrap();
+        let design = graph
+            .insert_child("Design".to_string(), project, false)
+            .unwrap();
+        let mockups = graph
+            .insert_child("Mockups".to_string(), design, false)
+            .unwrap();
+        let review = graph
+            .insert_child("Review mockups".to_string(), design, false)
+            .unwrap();
+        let deep = graph
+            .insert_child("Deep".to_string(), mockups, false)
+            .unwrap();
+        let other = graph.insert_root("Groceries".to_string(), false);
+        graph.add_tag(mockups, "work").unwrap();
+        graph.add_tag(other, "#home").unwrap();
+        graph
+            .set_task_state(mockups, TaskState::Done, true)
+            .unwrap();
+        let soon = node::now().date() + chrono::Days::new(3);
+        graph
+            .set_task_due(review, Some(DueDate::new(soon, None)))
+            .unwrap();
+
+        assert_eq!(eval(&graph, "state:partial"), vec![design]);
+        assert_eq!(eval(&graph, "tag:work"), vec![mockups]);
+        assert_eq!(
+            eval(&graph, "-tag:work type:task"),
+            vec![design, review, deep, other]
+        );
+        assert_eq!(eval(&graph, "due<7d"), vec![review]);
+        assert!(eval(&graph, "due<2d").is_empty());
+        assert_eq!(
+            eval(&graph, "under:project-x depth<=2"),
+            vec![design, mockups, review]
+        );
+        assert_eq!(eval(&graph, "under:project-x depth:3"), vec![deep]);
+        assert_eq!(eval(&graph, "depth:0"), vec![project, other]);
+        assert_eq!(eval(&graph, "mockups"), vec![mockups, review]);
+        assert_eq!(eval(&graph, "\"review mock\""), vec![review]);
+        assert_eq!(
+            eval(&graph, "archived:false state:done"),
+            vec![mockups, deep]
+        );
+    }
+
+    #[test]
+    fn query_errors() {
+        for query in [
+            "colour:red",
+            "state<done",
+            "depth<=x",
+            "due<someday",
+            "\"open",
+        ] {
+            assert!(query.parse::<Query>().is_err(), "{query} should not parse");
+        }
+        let query = "under:nowhere".parse::<Query>().unwrap();
+        assert!(query.eval(&Graph::new()).is_err());
+        assert!(!"just words".parse::<Query>().unwrap().has_fields());
+    }
+}
@@ -0,0 +1,133 @@
+//! Recurring tasks that are materialized under date nodes.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+use super::errors::ErrorType;
+
+/// When a recurrence occurs.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RecurRule {
+    Daily,
+    /// Monday through Friday
+    Weekdays,
+    /// Every N days, counting from the start of the recurrence
+    EveryNDays(u32),
+    /// On this day of every month. Months that are too short use their last day instead.
+    Monthly(u32),
+}
+
+impl RecurRule {
+    /// Returns whether this rule occurs on `date`, for a recurrence starting on `start`.
+    pub fn occurs_on(&self, start: NaiveDate, date: NaiveDate) -> bool {
+        if date < start {
+            return false;
+        }
+        match *self {
+            RecurRule::Daily => true,
+            RecurRule::Weekdays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+            RecurRule::EveryNDays(n) => (date - start).num_days() % n as i64 == 0,
+            RecurRule::Monthly(day) => date.day() == day.min(last_day_of_month(date)),
+        }
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).expect("every month has a first day");
+    (first + Months::new(1)).pred_opt().unwrap().day()
+}
+
+/// Parses rules written as `daily`, `weekdays`, `every:<N>` (every N days), or `monthly:<day>`.
+impl FromStr for RecurRule {
+    type Err = ErrorType;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ErrorType::InvalidRecurRule(s.to_string());
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg.parse::<u32>().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        match (kind.to_lowercase().as_str(), arg) {
+            ("daily", None) => Ok(RecurRule::Daily),
+            ("weekdays", None) => Ok(RecurRule::Weekdays),
+            ("every", Some(n)) if n > 0 => Ok(RecurRule::EveryNDays(n)),
+            ("monthly", Some(day)) if (1..=31).contains(&day) => Ok(RecurRule::Monthly(day)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for RecurRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecurRule::Daily => write!(f, "daily"),
+            RecurRule::Weekdays => write!(f, "weekdays"),
+            RecurRule::EveryNDays(n) => write!(f, "every:{n}"),
+            RecurRule::Monthly(day) => write!(f, "monthly:{day}"),
+        }
+    }
+}
+
+/// What a recurrence materializes.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RecurTarget {
+    /// A single task with this title
+    Task(String),
+    /// A blueprint, by name or path. Blueprints are stored by the front-end, which inserts them.
+    Blueprint(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Recurrence {
+    pub rule: RecurRule,
+    pub target: RecurTarget,
+    /// First date the recurrence may occur on
+    pub start: NaiveDate,
+    /// Every date up to and including this one has been materialized
+    pub(crate) synced_until: Option<NaiveDate>,
+    /// Dates after `synced_until` that have been materialized, e.g. because they were viewed
+    pub(crate) generated: BTreeSet<NaiveDate>,
+}
+
+impl Recurrence {
+    pub fn new(rule: RecurRule, target: RecurTarget, start: NaiveDate) -> Self {
+        Self {
+            rule,
+            target,
+            start,
+            synced_until: None,
+            generated: BTreeSet::new(),
+        }
+    }
+
+    /// Returns whether this recurrence has already been materialized on `date`
+    pub fn is_generated(&self, date: NaiveDate) -> bool {
+        self.synced_until.is_some_and(|until| date <= until) || self.generated.contains(&date)
+    }
+
+    /// Returns whether this recurrence occurs on `date` and still has to be materialized there
+    pub fn is_pending(&self, date: NaiveDate) -> bool {
+        self.rule.occurs_on(self.start, date) && !self.is_generated(date)
+    }
+
+    /// Returns the first date that may still have to be materialized
+    pub(crate) fn first_unsynced(&self) -> NaiveDate {
+        match self.synced_until.and_then(|until| until.succ_opt()) {
+            Some(next) => next.max(self.start),
+            None => self.start,
+        }
+    }
+
+    /// Records that every date up to and including `until` has been materialized
+    pub(crate) fn mark_synced(&mut self, until: NaiveDate) {
+        if self.synced_until.is_some_and(|synced| synced >= until) {
+            return;
+        }
+        self.synced_until = Some(until);
+        self.generated.retain(|date| *date > until);
+    }
+}
@@ -0,0 +1,143 @@
+//! Full-text search across node titles, notes and aliases.
+//!
+//! Every word of the search text has to be found somewhere in a node, ignoring case. Hits are
+//! ranked by where their words were found: titles weigh the most, then aliases, then notes. A
+//! word matching a whole field counts more than one starting it, which counts more than one
+//! starting any of its words, which in turn counts more than one found anywhere else.
+
+use super::node::Node;
+use super::Graph;
+
+/// How much matches in each field weigh
+const TITLE_WEIGHT: u32 = 3;
+const ALIAS_WEIGHT: u32 = 2;
+const NOTE_WEIGHT: u32 = 1;
+
+/// Bonus for a title containing the whole search text as is
+const PHRASE_BONUS: u32 = 10;
+
+/// A node found by [`Graph::search`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SearchHit {
+    pub index: usize,
+    /// How well the node matched. Higher is better.
+    pub score: u32,
+}
+
+/// Scores how well `word` matches `text`, both lowercased. Zero if it doesn't.
+fn score_field(text: &str, word: &str) -> u32 {
+    if text == word {
+        4
+    } else if text.starts_with(word) {
+        3
+    } else if text.match_indices(word).any(|(pos, _)| {
+        text[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_alphanumeric())
+    }) {
+        2
+    } else if text.contains(word) {
+        1
+    } else {
+        0
+    }
+}
+
+/// Scores a node against the lowercased search words, or returns [`None`] if a word is missing.
+fn score_node(node: &Node, words: &[String], phrase: &str) -> Option<u32> {
+    let title = node.title.to_lowercase();
+    let aliases: Vec<String> = node
+        .metadata
+        .aliases
+        .iter()
+        .map(|alias| alias.to_lowercase())
+        .collect();
+    let note = node.note.as_deref().map(str::to_lowercase);
+
+    let mut score = 0;
+    for word in words {
+        let best = [
+            Some((&title, TITLE_WEIGHT)),
+            note.as_ref().map(|note| (note, NOTE_WEIGHT)),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(aliases.iter().map(|alias| (alias, ALIAS_WEIGHT)))
+        .map(|(text, weight)| score_field(text, word) * weight)
+        .max()
+        .unwrap_or_default();
+        if best == 0 {
+            return None;
+        }
+        score += best;
+    }
+    if words.len() > 1 && title.contains(phrase) {
+        score += PHRASE_BONUS;
+    }
+    Some(score)
+}
+
+impl Graph {
+    /// Searches node titles, aliases and notes for every word of `text`.
+    ///
+    /// # Returns
+    /// The matching nodes, best first. Nodes that match equally well are ordered by index.
+    pub fn search(&self, text: &str) -> Vec<SearchHit> {
+        let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return vec![];
+        }
+        let phrase = words.join(" ");
+
+        let mut hits: Vec<SearchHit> = self
+            .nodes
+            .iter()
+            .flatten()
+            .filter_map(|node| {
+                score_node(node, &words, &phrase).map(|score| SearchHit {
+                    index: node.metadata.index,
+                    score,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.cmp(&a.score).then(a.index.cmp(&b.index)));
+        hits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{Graph, GraphGetters};
+
+    #[test]
+    fn search_ranking() {
+        let mut graph = Graph::new();
+        let work = graph.insert_root("work".to_string(), false);
+        let deploy = graph
+            .insert_child("deploy website".to_string(), work, false)
+            .unwrap();
+        let redeploy = graph
+            .insert_child("redeploy".to_string(), work, false)
+            .unwrap();
+        let noted = graph.insert_root("chores".to_string(), false);
+        graph.get_node_mut(noted).note = Some("Deploy the dishwasher".to_string());
+        let aliased = graph.insert_root("misc".to_string(), false);
+        graph.set_alias(aliased, "deploys".to_string()).unwrap();
+
+        let ranked: Vec<usize> = graph
+            .search("DEPLOY")
+            .into_iter()
+            .map(|hit| hit.index)
+            .collect();
+        assert_eq!(ranked, vec![deploy, aliased, redeploy, noted]);
+
+        // Every word has to be found, though not necessarily in the same field
+        let hits = graph.search("website deploy");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].index, deploy);
+        assert_eq!(graph.ancestor_path(deploy), vec![work]);
+        assert!(graph.search("deploy nowhere").is_empty());
+        assert!(graph.search("  ").is_empty());
+    }
+}
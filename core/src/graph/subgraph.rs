@@ -0,0 +1,329 @@
+//! Detached copies of part of a graph, used to duplicate subtrees and to store blueprints.
+//!
+//! [`Graph::extract`] copies a node and everything below it into a [`Subgraph`], and
+//! [`Graph::graft`] inserts a subgraph's nodes as new nodes, into the same graph or another one.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::ErrorType;
+use super::node::{now, task::TaskState, Node, NodeType};
+use super::observer::Event;
+use super::{Graph, GraphResult};
+
+/// A node and everything below it, detached from the graph it came from. Nodes are addressed by
+/// their position in `nodes`, the first being the top node, and only link to each other.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct Subgraph {
+    pub nodes: Vec<Node>,
+}
+
+impl Subgraph {
+    /// Returns a cycle reachable from the node at `start`, starting and ending with the same
+    /// node, or [`None`] if there is none. Subgraphs written by hand may contain one.
+    pub fn find_cycle(&self, start: usize) -> Option<Vec<usize>> {
+        let mut path = vec![start];
+        let mut done = HashSet::new();
+        self._find_cycle(&mut path, &mut done)
+    }
+
+    fn _find_cycle(&self, path: &mut Vec<usize>, done: &mut HashSet<usize>) -> Option<Vec<usize>> {
+        let current = *path.last().unwrap();
+        for child in &self.nodes.get(current)?.metadata.children {
+            if let Some(pos) = path.iter().position(|i| i == child) {
+                let mut cycle = path[pos..].to_vec();
+                cycle.push(*child);
+                return Some(cycle);
+            }
+            if done.contains(child) {
+                continue;
+            }
+            path.push(*child);
+            if let Some(cycle) = self._find_cycle(path, done) {
+                return Some(cycle);
+            }
+            path.pop();
+        }
+        done.insert(current);
+        None
+    }
+
+    /// Returns the position of a node linking past the end of `nodes`, and the link
+    fn dangling_link(&self) -> Option<(usize, usize)> {
+        self.nodes.iter().enumerate().find_map(|(pos, node)| {
+            let meta = &node.metadata;
+            meta.parents
+                .iter()
+                .chain(&meta.children)
+                .chain(&meta.blocked_by)
+                .chain(&meta.blocks)
+                .find(|&&link| link >= self.nodes.len())
+                .map(|&link| (pos, link))
+        })
+    }
+}
+
+impl Graph {
+    /// Numbers `index` and the nodes below it in depth-first order. Nodes reachable through
+    /// several parents are numbered once.
+    fn number_subtree(&self, index: usize, map: &mut HashMap<usize, usize>) {
+        if map.contains_key(&index) {
+            return;
+        }
+        map.insert(index, map.len());
+        let children = self.node(index).metadata.children.clone();
+        for child in children {
+            self.number_subtree(child, map);
+        }
+    }
+
+    /// Copies the node at `index` and everything below it. Links leaving the subtree, including
+    /// the top node's parents, are dropped. Timestamps and logged time belong to the original
+    /// nodes, so the copies have none.
+    pub fn extract(&self, index: usize) -> Subgraph {
+        let mut map = HashMap::new();
+        self.number_subtree(index, &mut map);
+
+        let remap = |list: &[usize]| -> Vec<usize> {
+            list.iter().filter_map(|i| map.get(i)).copied().collect()
+        };
+        let mut nodes = vec![Node::default(); map.len()];
+        for (&id, &pos) in &map {
+            let mut node = self.node(id).clone();
+            let meta = &mut node.metadata;
+            meta.index = pos;
+            meta.parents = remap(&meta.parents);
+            meta.children = remap(&meta.children);
+            meta.blocked_by = remap(&meta.blocked_by);
+            meta.blocks = remap(&meta.blocks);
+            meta.created = None;
+            meta.modified = None;
+            meta.completed = None;
+            meta.time_log.clear();
+            nodes[pos] = node;
+        }
+        Subgraph { nodes }
+    }
+
+    /// Inserts a copy of a subgraph, under `parent` or as a root if it is `None`. Nodes shared
+    /// by several parents are inserted once, and keep their task states, pseudo flags, tags,
+    /// notes and the dependencies between them. Date nodes become pseudo nodes, since there is
+    /// only one node per date.
+    ///
+    /// # Arguments
+    /// - `aliases`: whether to keep the subgraph's aliases. Aliases already in use, or that are
+    ///   not valid, are dropped.
+    ///
+    /// # Returns
+    /// The ID of the inserted top node.
+    pub fn graft(
+        &mut self,
+        subgraph: &Subgraph,
+        parent: Option<usize>,
+        aliases: bool,
+    ) -> GraphResult<usize> {
+        if subgraph.nodes.is_empty() {
+            return Err(ErrorType::EmptySubgraph);
+        }
+        if let Some(parent) = parent.filter(|&i| self.node_checked(i).is_none()) {
+            return Err(ErrorType::InvalidIndex(parent));
+        }
+        if let Some((pos, link)) = subgraph.dangling_link() {
+            return Err(ErrorType::IndexOutOfRange(format!(
+                "subgraph node {pos} links to {link}"
+            )));
+        }
+        if let Some(path) = subgraph.find_cycle(0) {
+            return Err(ErrorType::SubgraphCycle(path));
+        }
+
+        // Build every node before inserting any, so that a bad tag leaves the graph untouched
+        let first = self.next_id;
+        let now = now();
+        let mut nodes = Vec::with_capacity(subgraph.nodes.len());
+        for (pos, node) in subgraph.nodes.iter().enumerate() {
+            let mut node = node.clone();
+            if let NodeType::Date(date) = &node.data {
+                if node.title.is_empty() {
+                    node.title = date.date.to_string();
+                }
+                node.data = NodeType::Pseudo;
+            }
+            let done = node
+                .data
+                .as_task()
+                .is_some_and(|data| data.state == TaskState::Done);
+
+            let meta = &mut node.metadata;
+            meta.index = first + pos;
+            for list in [
+                &mut meta.parents,
+                &mut meta.children,
+                &mut meta.blocked_by,
+                &mut meta.blocks,
+            ] {
+                list.iter_mut().for_each(|i| *i += first);
+            }
+            meta.created = Some(now);
+            meta.modified = Some(now);
+            meta.completed = done.then_some(now);
+            meta.time_log.clear();
+
+            let mut tags: Vec<String> = vec![];
+            for tag in &meta.tags {
+                let tag = Self::normalize_tag(tag)?;
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            meta.tags = tags;
+            nodes.push(node);
+        }
+
+        for mut node in nodes {
+            let id = node.metadata.index;
+            let meta = &mut node.metadata;
+            if !aliases {
+                meta.aliases.clear();
+            }
+            meta.aliases.retain(|alias| {
+                !self.aliases.contains_key(alias) && Self::validate_alias(alias).is_ok()
+            });
+            for alias in &meta.aliases {
+                self.aliases.insert(alias.clone(), id);
+            }
+            for tag in &meta.tags {
+                self.tags.entry(tag.clone()).or_default().push(id);
+            }
+            if meta.archived {
+                self.archived.push(id);
+            }
+            // Nodes the top node does not lead to have nowhere else to go
+            if id != first && meta.parents.is_empty() {
+                self.roots.push(id);
+            }
+            let pushed = self.push_node(|_| node);
+            debug_assert_eq!(pushed, id);
+        }
+        // Links between the new nodes came with them, so report them once every node is in
+        for id in first..self.next_id {
+            let meta = &self.node(id).metadata;
+            let mut events = vec![];
+            events.extend(meta.aliases.iter().map(|alias| Event::AliasSet {
+                index: id,
+                alias: alias.clone(),
+            }));
+            events.extend(meta.tags.iter().map(|tag| Event::TagAdded {
+                index: id,
+                tag: tag.clone(),
+            }));
+            events.extend(
+                meta.children
+                    .iter()
+                    .map(|&child| Event::Linked { parent: id, child }),
+            );
+            events.extend(
+                meta.blocked_by
+                    .iter()
+                    .map(|&blocker| Event::DependencyAdded { index: id, blocker }),
+            );
+            for event in events {
+                self.emit(event);
+            }
+        }
+
+        match parent {
+            Some(parent) => {
+                self.link_unchecked(parent, first);
+                self.update_state_recurse_parents(&[parent])?;
+            }
+            None => self.roots.push(first),
+        }
+        Ok(first)
+    }
+
+    /// Copies the node at `from` and everything below it under `to`. The copies get no aliases,
+    /// since those are taken by the originals.
+    ///
+    /// # Returns
+    /// The ID of the copy of `from`.
+    pub fn copy_subgraph(&mut self, from: usize, to: usize) -> GraphResult<usize> {
+        let subgraph = self.extract(from);
+        self.graft(&subgraph, Some(to), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::graph::node::task::TaskState;
+    use crate::graph::{Graph, GraphGetters};
+
+    #[test]
+    fn extract_and_graft() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let a = graph.insert_child("a".to_string(), root, false).unwrap();
+        let b = graph.insert_child("b".to_string(), root, true).unwrap();
+        let shared = graph.insert_child("shared".to_string(), a, false).unwrap();
+        graph.link(b, shared).unwrap();
+        let outside = graph.insert_root("outside".to_string(), false);
+        graph.add_dependency(shared, outside).unwrap();
+        graph.add_dependency(a, shared).unwrap();
+        graph.set_task_state(shared, TaskState::Done, true).unwrap();
+        graph.set_alias(a, "a".to_string()).unwrap();
+        graph.add_tag(shared, "work").unwrap();
+
+        // Nodes are numbered depth-first, shared ones once
+        let mut map = HashMap::new();
+        graph.number_subtree(root, &mut map);
+        assert_eq!(map, HashMap::from([(root, 0), (a, 1), (shared, 2), (b, 3)]));
+
+        let subgraph = graph.extract(root);
+        assert_eq!(subgraph.nodes.len(), 4);
+        assert_eq!(subgraph.nodes[2].metadata.parents, vec![1, 3]);
+        // Only links inside the subgraph are kept
+        assert!(subgraph.nodes[2].metadata.blocked_by.is_empty());
+        assert_eq!(subgraph.nodes[1].metadata.blocked_by, vec![2]);
+        assert!(subgraph.nodes[2].metadata.created.is_none());
+
+        // Into another graph, keeping aliases
+        let mut other = Graph::new();
+        let top = other.graft(&subgraph, None, true).unwrap();
+        assert_eq!(other.get_roots(), &[top]);
+        assert_eq!(other.node_count(), 4);
+        assert_eq!(other.get_node(2).metadata.parents, vec![1, 3]);
+        assert!(other.get_node(3).data.is_pseudo());
+        assert_eq!(
+            other.get_node(2).data.as_task().unwrap().state,
+            TaskState::Done
+        );
+        assert!(other.get_node(2).metadata.completed.is_some());
+        assert_eq!(other.get_node(1).metadata.blocked_by, vec![2]);
+        assert_eq!(other.get_node(2).metadata.blocks, vec![1]);
+        assert_eq!(other.get_aliases().get("a"), Some(&1));
+        assert_eq!(other.get_tagged("work"), &[2]);
+        assert!(other.validate().is_empty());
+
+        // Into the same graph, where the alias is taken
+        let copy = graph.copy_subgraph(a, outside).unwrap();
+        assert!(graph.get_node(copy).metadata.aliases.is_empty());
+        assert_eq!(graph.get_aliases().get("a"), Some(&a));
+        assert_eq!(graph.get_node(outside).metadata.children, vec![copy]);
+        assert_eq!(
+            graph.get_node(outside).data.as_task().unwrap().state,
+            TaskState::Done
+        );
+        assert!(graph.validate().is_empty());
+
+        let mut cyclic = subgraph.clone();
+        cyclic.nodes[2].metadata.children.push(0);
+        assert!(matches!(
+            other.graft(&cyclic, None, false),
+            Err(crate::graph::errors::ErrorType::SubgraphCycle(path)) if path == vec![0, 1, 2, 0]
+        ));
+        assert_eq!(other.node_count(), 4);
+    }
+}
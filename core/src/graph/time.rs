@@ -0,0 +1,35 @@
+//! Time tracking: work intervals recorded on nodes, and the timer recording the next one.
+
+use chrono::{NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+/// A span of time worked on a node
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Interval {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl Interval {
+    pub fn duration(&self) -> TimeDelta {
+        self.end - self.start
+    }
+}
+
+/// The timer currently running on a node. Only one runs at a time.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Timer {
+    pub index: usize,
+    pub start: NaiveDateTime,
+}
+
+impl Timer {
+    /// Returns the interval this timer has recorded so far
+    pub fn until(&self, end: NaiveDateTime) -> Interval {
+        Interval {
+            start: self.start,
+            // Clocks can go backwards, but intervals can't
+            end: end.max(self.start),
+        }
+    }
+}
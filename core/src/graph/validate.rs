@@ -0,0 +1,495 @@
+//! Integrity checks for the graph's links and indices.
+//!
+//! Unlike [`Graph::clean`], which rebuilds every index from scratch, [`Graph::validate`] only
+//! reports what is inconsistent, and [`Graph::repair`] only touches what a problem points at.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use super::node::Node;
+use super::observer::Event;
+use super::Graph;
+
+/// A kind of edge a node keeps a list of
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Parent,
+    Child,
+    BlockedBy,
+    Blocks,
+}
+
+impl Edge {
+    /// The edge the other node should list in return
+    pub fn inverse(self) -> Edge {
+        match self {
+            Edge::Parent => Edge::Child,
+            Edge::Child => Edge::Parent,
+            Edge::BlockedBy => Edge::Blocks,
+            Edge::Blocks => Edge::BlockedBy,
+        }
+    }
+
+    fn list(self, node: &Node) -> &Vec<usize> {
+        match self {
+            Edge::Parent => &node.metadata.parents,
+            Edge::Child => &node.metadata.children,
+            Edge::BlockedBy => &node.metadata.blocked_by,
+            Edge::Blocks => &node.metadata.blocks,
+        }
+    }
+
+    fn list_mut(self, node: &mut Node) -> &mut Vec<usize> {
+        match self {
+            Edge::Parent => &mut node.metadata.parents,
+            Edge::Child => &mut node.metadata.children,
+            Edge::BlockedBy => &mut node.metadata.blocked_by,
+            Edge::Blocks => &mut node.metadata.blocks,
+        }
+    }
+}
+
+impl fmt::Display for Edge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Edge::Parent => "parent",
+            Edge::Child => "child",
+            Edge::BlockedBy => "blocker",
+            Edge::Blocks => "blocked task",
+        })
+    }
+}
+
+/// Which problems to repair
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProblemKind {
+    Edges,
+    Roots,
+    Aliases,
+    Dates,
+    Cycles,
+}
+
+/// An inconsistency found by [`Graph::validate`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// A node lists a node that doesn't exist
+    DanglingEdge {
+        index: usize,
+        edge: Edge,
+        target: usize,
+    },
+    /// A node lists another which doesn't list it back
+    AsymmetricEdge {
+        index: usize,
+        edge: Edge,
+        target: usize,
+    },
+    /// The roots list a node that doesn't exist
+    DanglingRoot { index: usize },
+    /// The roots list a node that has parents or is a date
+    StrayRoot { index: usize },
+    /// A non-date node without parents is missing from the roots, and can't be reached
+    UnlistedRoot { index: usize },
+    /// An alias points at a node that doesn't exist or doesn't carry it
+    DanglingAlias { alias: String, index: usize },
+    /// A node carries an alias that doesn't point at it
+    UnlistedAlias { alias: String, index: usize },
+    /// A date points at a node that doesn't exist or isn't a node for that date
+    DanglingDate { date: String, index: usize },
+    /// A date node that isn't what its date points at
+    UnlistedDate { date: String, index: usize },
+    /// Nodes that each list the next one through `edge`, the last listing the first
+    Cycle { edge: Edge, path: Vec<usize> },
+}
+
+impl Problem {
+    pub fn kind(&self) -> ProblemKind {
+        match self {
+            Problem::DanglingEdge { .. } | Problem::AsymmetricEdge { .. } => ProblemKind::Edges,
+            Problem::DanglingRoot { .. }
+            | Problem::StrayRoot { .. }
+            | Problem::UnlistedRoot { .. } => ProblemKind::Roots,
+            Problem::DanglingAlias { .. } | Problem::UnlistedAlias { .. } => ProblemKind::Aliases,
+            Problem::DanglingDate { .. } | Problem::UnlistedDate { .. } => ProblemKind::Dates,
+            Problem::Cycle { .. } => ProblemKind::Cycles,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::DanglingEdge {
+                index,
+                edge,
+                target,
+            } => write!(f, "Node {index} lists missing node {target} as a {edge}"),
+            Problem::AsymmetricEdge {
+                index,
+                edge,
+                target,
+            } => write!(
+                f,
+                "Node {index} lists node {target} as a {edge}, but not the other way around"
+            ),
+            Problem::DanglingRoot { index } => {
+                write!(f, "Missing node {index} is listed as a root")
+            }
+            Problem::StrayRoot { index } => write!(
+                f,
+                "Node {index} is listed as a root, but has parents or is a date"
+            ),
+            Problem::UnlistedRoot { index } => {
+                write!(f, "Node {index} has no parents, but isn't listed as a root")
+            }
+            Problem::DanglingAlias { alias, index } => write!(
+                f,
+                "Alias '{alias}' points at node {index}, which doesn't exist or carry it"
+            ),
+            Problem::UnlistedAlias { alias, index } => write!(
+                f,
+                "Node {index} carries alias '{alias}', which doesn't point at it"
+            ),
+            Problem::DanglingDate { date, index } => write!(
+                f,
+                "Date {date} points at node {index}, which doesn't exist or isn't for that date"
+            ),
+            Problem::UnlistedDate { date, index } => {
+                write!(f, "Date {date} doesn't point at its node {index}")
+            }
+            Problem::Cycle { edge, path } => write!(
+                f,
+                "Nodes form a cycle: {} -> {} (each lists the next as a {edge})",
+                path.iter()
+                    .map(|i| i.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" -> "),
+                path[0]
+            ),
+        }
+    }
+}
+
+impl Graph {
+    /// Checks the graph for inconsistencies without changing anything.
+    ///
+    /// # Returns
+    /// Every problem found, grouped by kind. Empty if the graph is consistent.
+    pub fn validate(&self) -> Vec<Problem> {
+        let ids = self.node_ids();
+        let mut problems = vec![];
+
+        for &index in &ids {
+            let node = self.node(index);
+            for edge in [Edge::Parent, Edge::Child, Edge::BlockedBy, Edge::Blocks] {
+                for &target in edge.list(node) {
+                    let problem = match self.node_checked(target) {
+                        None => Problem::DanglingEdge {
+                            index,
+                            edge,
+                            target,
+                        },
+                        Some(other) if !edge.inverse().list(other).contains(&index) => {
+                            Problem::AsymmetricEdge {
+                                index,
+                                edge,
+                                target,
+                            }
+                        }
+                        Some(_) => continue,
+                    };
+                    problems.push(problem);
+                }
+            }
+        }
+
+        for &index in &self.roots {
+            match self.node_checked(index) {
+                None => problems.push(Problem::DanglingRoot { index }),
+                Some(node) => {
+                    if !node.metadata.parents.is_empty() || node.data.is_date() {
+                        problems.push(Problem::StrayRoot { index });
+                    }
+                }
+            }
+        }
+        for &index in &ids {
+            let node = self.node(index);
+            if node.metadata.parents.is_empty()
+                && !node.data.is_date()
+                && !self.roots.contains(&index)
+            {
+                problems.push(Problem::UnlistedRoot { index });
+            }
+        }
+
+        let mut aliases: Vec<_> = self.aliases.iter().collect();
+        aliases.sort();
+        for (alias, &index) in aliases {
+            let carried = self
+                .node_checked(index)
+                .is_some_and(|node| node.metadata.aliases.contains(alias));
+            if !carried {
+                problems.push(Problem::DanglingAlias {
+                    alias: alias.clone(),
+                    index,
+                });
+            }
+        }
+        for &index in &ids {
+            for alias in &self.node(index).metadata.aliases {
+                if self.aliases.get(alias) != Some(&index) {
+                    problems.push(Problem::UnlistedAlias {
+                        alias: alias.clone(),
+                        index,
+                    });
+                }
+            }
+        }
+
+        let mut dates: Vec<_> = self.dates.iter().collect();
+        dates.sort();
+        for (date, &index) in dates {
+            let matches = self.node_checked(index).is_some_and(|node| {
+                node.data
+                    .as_date()
+                    .is_some_and(|data| data.format_for_hashmap() == *date)
+            });
+            if !matches {
+                problems.push(Problem::DanglingDate {
+                    date: date.clone(),
+                    index,
+                });
+            }
+        }
+        for &index in &ids {
+            if let Some(data) = self.node(index).data.as_date() {
+                let date = data.format_for_hashmap();
+                if self.dates.get(&date) != Some(&index) {
+                    problems.push(Problem::UnlistedDate { date, index });
+                }
+            }
+        }
+
+        for edge in [Edge::Child, Edge::BlockedBy] {
+            let mut done = HashSet::new();
+            for &index in &ids {
+                let mut path = vec![];
+                self.find_cycles(index, edge, &mut path, &mut done, &mut problems);
+            }
+        }
+
+        problems
+    }
+
+    /// Walks every node reachable from the last node of `path` through `edge`, reporting the
+    /// edges leading back into `path`.
+    fn find_cycles(
+        &self,
+        index: usize,
+        edge: Edge,
+        path: &mut Vec<usize>,
+        done: &mut HashSet<usize>,
+        problems: &mut Vec<Problem>,
+    ) {
+        if done.contains(&index) {
+            return;
+        }
+        if let Some(start) = path.iter().position(|&i| i == index) {
+            problems.push(Problem::Cycle {
+                edge,
+                path: path[start..].to_vec(),
+            });
+            return;
+        }
+        let Some(node) = self.node_checked(index) else {
+            return;
+        };
+        let next = edge.list(node).clone();
+        path.push(index);
+        for target in next {
+            self.find_cycles(target, edge, path, done, problems);
+        }
+        path.pop();
+        done.insert(index);
+    }
+
+    /// Repairs a problem found by [`Graph::validate`], touching nothing else. Links are repaired
+    /// by completing them, except for cycles, which are broken by dropping their last link.
+    ///
+    /// # Returns
+    /// Whether the problem could be repaired. An alias or date that two nodes claim stays with
+    /// the node it points at, and the other node loses its alias; a date claimed by two date
+    /// nodes is left alone.
+    pub fn repair(&mut self, problem: &Problem) -> bool {
+        match problem {
+            Problem::DanglingEdge {
+                index,
+                edge,
+                target,
+            } => {
+                let Some(node) = self.node_checked_mut(*index) else {
+                    return false;
+                };
+                edge.list_mut(node).retain(|i| i != target);
+                if *edge == Edge::Parent {
+                    self.list_if_parentless(*index);
+                }
+            }
+            Problem::AsymmetricEdge {
+                index,
+                edge,
+                target,
+            } => {
+                let Some(node) = self.node_checked_mut(*target) else {
+                    return false;
+                };
+                let list = edge.inverse().list_mut(node);
+                if !list.contains(index) {
+                    list.push(*index);
+                }
+                if *edge == Edge::Child {
+                    self.roots.retain(|i| i != target);
+                }
+            }
+            Problem::DanglingRoot { index } | Problem::StrayRoot { index } => {
+                self.roots.retain(|i| i != index);
+            }
+            Problem::UnlistedRoot { index } => self.list_if_parentless(*index),
+            Problem::DanglingAlias { alias, index } => {
+                if self.aliases.get(alias) == Some(index) {
+                    self.aliases.remove(alias);
+                }
+            }
+            Problem::UnlistedAlias { alias, index } => {
+                if self.node_checked(*index).is_none() {
+                    return false;
+                }
+                let taken = self.aliases.get(alias).is_some_and(|&other| {
+                    self.node_checked(other)
+                        .is_some_and(|other| other.metadata.aliases.contains(alias))
+                });
+                if taken {
+                    self.node_mut(*index)
+                        .metadata
+                        .aliases
+                        .retain(|a| a != alias);
+                } else {
+                    self.aliases.insert(alias.clone(), *index);
+                }
+            }
+            Problem::DanglingDate { date, index } => {
+                if self.dates.get(date) == Some(index) {
+                    self.dates.remove(date);
+                }
+            }
+            Problem::UnlistedDate { date, index } => {
+                let taken = self.dates.get(date).is_some_and(|&other| {
+                    self.node_checked(other).is_some_and(|other| {
+                        other
+                            .data
+                            .as_date()
+                            .is_some_and(|data| data.format_for_hashmap() == *date)
+                    })
+                });
+                if taken || self.node_checked(*index).is_none() {
+                    return false;
+                }
+                self.dates.insert(date.clone(), *index);
+            }
+            Problem::Cycle { edge, path } => {
+                let (&first, &last) = (path.first().unwrap(), path.last().unwrap());
+                if self.node_checked(last).is_none() || self.node_checked(first).is_none() {
+                    return false;
+                }
+                edge.list_mut(self.node_mut(last)).retain(|&i| i != first);
+                edge.inverse()
+                    .list_mut(self.node_mut(first))
+                    .retain(|&i| i != last);
+                if *edge == Edge::Child {
+                    self.list_if_parentless(first);
+                }
+            }
+        }
+        self.emit(Event::Reset);
+        true
+    }
+
+    /// Lists a node as a root if it is a non-date node without parents, and isn't one already
+    fn list_if_parentless(&mut self, index: usize) {
+        let Some(node) = self.node_checked(index) else {
+            return;
+        };
+        if node.metadata.parents.is_empty() && !node.data.is_date() && !self.roots.contains(&index)
+        {
+            self.roots.push(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{Graph, GraphGetters};
+
+    use super::{Edge, Problem};
+
+    #[test]
+    fn validate_and_repair() {
+        let mut graph = Graph::new();
+        let root = graph.insert_root("root".to_string(), false);
+        let child = graph
+            .insert_child("child".to_string(), root, false)
+            .unwrap();
+        let grandchild = graph
+            .insert_child("grandchild".to_string(), child, false)
+            .unwrap();
+        let date = graph.insert_date(String::new(), "2025-01-01".parse().unwrap());
+        graph.set_alias(child, "kid".to_string()).unwrap();
+        assert!(graph.validate().is_empty());
+
+        // Corrupt the graph behind its back
+        graph.get_node_mut(root).metadata.children.push(99);
+        graph.get_node_mut(grandchild).metadata.children.push(root);
+        graph.aliases.insert("gone".to_string(), 42);
+        graph.dates.clear();
+        graph.roots.push(date);
+
+        let problems = graph.validate();
+        assert_eq!(
+            problems,
+            vec![
+                Problem::DanglingEdge {
+                    index: root,
+                    edge: Edge::Child,
+                    target: 99
+                },
+                Problem::AsymmetricEdge {
+                    index: grandchild,
+                    edge: Edge::Child,
+                    target: root
+                },
+                Problem::StrayRoot { index: date },
+                Problem::DanglingAlias {
+                    alias: "gone".to_string(),
+                    index: 42
+                },
+                Problem::UnlistedDate {
+                    date: "2025-01-01".to_string(),
+                    index: date
+                },
+                Problem::Cycle {
+                    edge: Edge::Child,
+                    path: vec![root, child, grandchild]
+                },
+            ]
+        );
+
+        for problem in &problems {
+            assert!(graph.repair(problem));
+        }
+        assert!(graph.validate().is_empty());
+        assert_eq!(graph.get_roots(), &[root]);
+        assert!(graph.get_node(grandchild).metadata.children.is_empty());
+    }
+}
@@ -1,18 +1,24 @@
 use crate::{
-    components::{self, tabs::TabView},
+    components::{self, graph_view::GraphViewComponent, tabs::TabView},
     events::{
-        ActiveNodeOperation, AppEvent, AskPromptType, InternalEvent, NavDirection,
-        OperationalEvent, ViewFilterOperation,
+        ActiveNodeOperation, AppEvent, AskPromptType, HistoryOperation, InternalEvent,
+        NavDirection, OperationalEvent, ViewFilterOperation,
     },
 };
+use crossterm::event::KeyCode;
+use tuecore::graph::journal::Journal;
 use tuecore::graph::Graph;
 
 const STOP_CAPTURING_KEY: Option<AppEvent> = Some(AppEvent::Internal(InternalEvent::StopPrompt));
 
 #[derive(Default)]
 pub struct AppState {
+    // TODO: the current view is kept by the tabs component, and the command line's focus isn't
+    // tracked yet. Drop these `dead_code` allows once they are read.
+    #[allow(dead_code)]
     pub(crate) current_view: TabView,
     pub(crate) should_exit: bool,
+    #[allow(dead_code)]
     pub(crate) cmdline_focused: bool,
     pub(crate) is_capturing_key: Option<AskPromptType>,
 }
@@ -21,6 +27,7 @@ pub struct AppState {
 pub struct App {
     pub(crate) components: components::AppUIComponent,
     pub(crate) state: AppState,
+    pub(crate) journal: Journal,
 }
 
 impl Default for App {
@@ -41,6 +48,55 @@ impl App {
         self.components.graph_view.get_graph()
     }
 
+    pub fn load_journal(&mut self, journal: Journal) {
+        self.journal = journal;
+    }
+
+    pub fn get_journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Runs a change to the graph, recording it in the journal so that it can be undone.
+    fn journaled(&mut self, label: &str, f: impl FnOnce(&mut GraphViewComponent)) {
+        let before = self.components.graph_view.get_graph().clone();
+        f(&mut self.components.graph_view);
+        if let (Some(before), Some(after)) = (before, self.components.graph_view.get_graph()) {
+            self.journal.record(label, &before, after);
+        }
+    }
+
+    /// Materializes the recurring tasks of the dates that came into view. This isn't something the
+    /// user asked for, so it is folded into the last change in the journal rather than being
+    /// undone on its own.
+    fn view_dates(&mut self) {
+        let before = self.components.graph_view.get_graph().clone();
+        self.components.graph_view.view_shown_dates();
+        if let (Some(before), Some(after)) = (before, self.components.graph_view.get_graph()) {
+            self.journal.amend(&before, after);
+        }
+    }
+
+    /// Undoes or redoes the last change, reporting the outcome on the command line. This is
+    /// refused when there is nothing to undo or redo, or when the graph was changed without being
+    /// recorded, see
+    /// [`ErrorType::JournalOutOfSync`](tuecore::graph::errors::ErrorType::JournalOutOfSync).
+    fn step_history(&mut self, op: HistoryOperation) {
+        let Some(graph) = self.components.graph_view.get_graph_mut() else {
+            return;
+        };
+        let (result, verb) = match op {
+            HistoryOperation::Undo => (self.journal.undo(graph, 1), "Undid"),
+            HistoryOperation::Redo => (self.journal.redo(graph, 1), "Redid"),
+        };
+        match result {
+            Ok(labels) => self
+                .components
+                .cmdline
+                .show_message(format!("{verb}: {}", labels.join(", ")), false),
+            Err(err) => self.components.cmdline.show_message(err.to_string(), true),
+        }
+    }
+
     pub fn graph_is_loaded(&self) -> bool {
         self.components.graph_view.graph_is_loaded()
     }
@@ -57,6 +113,7 @@ impl App {
         App {
             components: components::AppUIComponent::new(),
             state: AppState::default(),
+            journal: Journal::new(),
         }
     }
 
@@ -72,14 +129,38 @@ impl App {
     /// The second is "capture" mode where this method would use the `Cmdline` component to process keys
     /// forwarded by event dispatcher, and act upon all operational events received back.
     pub fn process_event(&mut self, event: AppEvent) -> Option<AppEvent> {
+        // Events can follow each other without a render in between
+        self.components.graph_view.sync();
+        self.components.cmdline.clear_message();
         // Handle events during capturing mode
         if let Some(prompt_type) = &self.state.is_capturing_key {
             match event {
                 AppEvent::Internal(ev) => match ev {
                     InternalEvent::ForwardKey(code) => {
-                        return self.components.cmdline.process_input(prompt_type, &code)
+                        if self.components.search_popup.is_visible() {
+                            match code {
+                                KeyCode::Down => {
+                                    self.components.search_popup.select_next();
+                                    return None;
+                                }
+                                KeyCode::Up => {
+                                    self.components.search_popup.select_previous();
+                                    return None;
+                                }
+                                KeyCode::Enter => {
+                                    if let Some(idx) = self.components.search_popup.selected() {
+                                        self.components.graph_view.jump_to(idx);
+                                        self.view_dates();
+                                    }
+                                    return STOP_CAPTURING_KEY;
+                                }
+                                _ => (),
+                            }
+                        }
+                        return self.components.cmdline.process_input(prompt_type, &code);
                     }
                     InternalEvent::StopPrompt => {
+                        self.components.search_popup.hide();
                         self.components.cmdline.hide_prompt();
                         self.state.is_capturing_key = None;
                         return None;
@@ -110,45 +191,56 @@ impl App {
                                         .parse::<u32>()
                                         .unwrap_or(1),
                                 );
+                            self.view_dates();
                             return STOP_CAPTURING_KEY;
                         }
                         _ => (),
                     },
-                    OperationalEvent::OperateActiveNode(op) => match op {
-                        ActiveNodeOperation::AddToParent => {
-                            self.components.graph_view.add_node_to_parent(
+                    OperationalEvent::Navigate(NavDirection::JumpTo) => {
+                        if let Some(graph) = self.components.graph_view.get_graph() {
+                            self.components.search_popup.update(
+                                graph,
                                 self.components.cmdline.get_curr_input(),
-                                false,
+                                self.components.graph_view.shows_archived(),
                             );
+                        }
+                    }
+                    OperationalEvent::OperateActiveNode(op) => match op {
+                        ActiveNodeOperation::AddToParent => {
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("add", |view| view.add_node_to_parent(&input, false));
+                            // Adding to the date view inserts a date
+                            self.components.graph_view.sync();
+                            self.view_dates();
                             return STOP_CAPTURING_KEY;
                         }
                         ActiveNodeOperation::AddToActive => {
-                            self.components.graph_view.add_node_to_active(
-                                self.components.cmdline.get_curr_input(),
-                                false,
-                            );
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("add", |view| view.add_node_to_active(&input, false));
                             return STOP_CAPTURING_KEY;
                         }
                         ActiveNodeOperation::AddPseudoToParent => {
-                            self.components
-                                .graph_view
-                                .add_node_to_active(self.components.cmdline.get_curr_input(), true);
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("add", |view| view.add_node_to_active(&input, true));
                             return STOP_CAPTURING_KEY;
                         }
                         ActiveNodeOperation::AddPseudoToActive => {
-                            self.components
-                                .graph_view
-                                .add_node_to_active(self.components.cmdline.get_curr_input(), true);
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("add", |view| view.add_node_to_active(&input, true));
                             return STOP_CAPTURING_KEY;
                         }
                         ActiveNodeOperation::Rename => {
-                            self.components
-                                .graph_view
-                                .rename_active(self.components.cmdline.get_curr_input());
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("rename", |view| view.rename_active(&input));
+                            return STOP_CAPTURING_KEY;
+                        }
+                        ActiveNodeOperation::CopyTo => {
+                            let input = self.components.cmdline.get_curr_input().to_owned();
+                            self.journaled("cp", |view| view.copy_active_to(&input));
                             return STOP_CAPTURING_KEY;
                         }
                         ActiveNodeOperation::Delete => {
-                            self.components.graph_view.delete_active_node();
+                            self.journaled("rm", |view| view.delete_active_node());
                             // TODO: consider automatically returning this after matching the
                             // OperationalEvent
                             return STOP_CAPTURING_KEY;
@@ -179,6 +271,7 @@ impl App {
                 OperationalEvent::TabChange(direction) => {
                     self.components.tabs.switch_view(&direction)
                 }
+                OperationalEvent::History(op) => self.step_history(op),
 
                 OperationalEvent::Filter(op) => match op {
                     ViewFilterOperation::SetDepth => {
@@ -202,24 +295,51 @@ impl App {
                         self.components.graph_view.jump_prev_filter();
                     }
 
+                    ViewFilterOperation::TogglePrioritySort => {
+                        self.components.graph_view.toggle_priority_sort();
+                    }
+
+                    ViewFilterOperation::ToggleDetails => {
+                        self.components.node_details.toggle();
+                    }
+
                     _ => (),
                 },
 
                 OperationalEvent::Navigate(navigation) => match navigation {
                     NavDirection::Next => self.components.graph_view.select_next(),
                     NavDirection::Previous => self.components.graph_view.select_previous(),
-                    NavDirection::StepIn => self.components.graph_view.step_into(),
-                    NavDirection::StepOut => self.components.graph_view.step_out(),
+                    NavDirection::StepIn => {
+                        self.components.graph_view.step_into();
+                        self.view_dates();
+                    }
+                    NavDirection::StepOut => {
+                        self.components.graph_view.step_out();
+                        self.view_dates();
+                    }
                     NavDirection::First => self.components.graph_view.select_first(),
                     NavDirection::Last => self.components.graph_view.select_last(),
                     NavDirection::ToggleRootView => {
-                        self.components.graph_view.toggle_switch_roots_view()
+                        self.components.graph_view.toggle_switch_roots_view();
+                        self.view_dates();
+                    }
+                    NavDirection::ToRoot => {
+                        self.components.graph_view.switch_view_to_roots();
+                        self.view_dates();
+                    }
+                    NavDirection::JumpTo => {
+                        self.components.search_popup.show();
+                        return Some(AppEvent::Internal(InternalEvent::AskPrompt(
+                            AskPromptType::Continual(ev),
+                            "Jump to: ".to_string(),
+                        )));
                     }
-                    NavDirection::ToRoot => self.components.graph_view.switch_view_to_roots(),
                     _ => (),
                 },
                 OperationalEvent::OperateActiveNode(ref op) => match op {
-                    ActiveNodeOperation::Check => self.components.graph_view.check_active(),
+                    ActiveNodeOperation::Check => {
+                        self.journaled("check", |view| view.check_active())
+                    }
                     ActiveNodeOperation::Rename => {
                         if let Some(node) = self.components.graph_view.get_current_node() {
                             self.components.cmdline.set_curr_input(&node.title);
@@ -230,6 +350,15 @@ impl App {
                             )));
                         }
                     }
+                    ActiveNodeOperation::CopyTo => {
+                        if self.components.graph_view.nodes_count() == 0 {
+                            return None;
+                        }
+                        return Some(AppEvent::Internal(InternalEvent::AskPrompt(
+                            AskPromptType::Input(ev),
+                            "Copy to (empty for a root): ".to_string(),
+                        )));
+                    }
                     ActiveNodeOperation::Delete => {
                         return Some(AppEvent::Internal(InternalEvent::AskPrompt(
                             AskPromptType::Confirmation(ev),
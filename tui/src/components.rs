@@ -1,14 +1,18 @@
 pub mod cmdline;
 pub mod graph_view;
+pub mod node_details;
+pub mod search_popup;
 pub mod statusbar;
 pub mod tabs;
 
 use cmdline::CmdlineComponent;
 use graph_view::GraphViewComponent;
+use node_details::NodeDetailsComponent;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     widgets::Widget,
 };
+use search_popup::SearchPopupComponent;
 use statusbar::StatusBarComponent;
 use tabs::TabComponent;
 
@@ -46,6 +50,8 @@ impl AppLayout {
 pub struct AppUIComponent {
     pub(crate) tabs: TabComponent,
     pub(crate) graph_view: GraphViewComponent,
+    pub(crate) node_details: NodeDetailsComponent,
+    pub(crate) search_popup: SearchPopupComponent,
     pub(crate) status_bar: StatusBarComponent,
     pub(crate) cmdline: CmdlineComponent,
 }
@@ -61,6 +67,8 @@ impl AppUIComponent {
         Self {
             tabs: TabComponent::new(),
             graph_view: GraphViewComponent::new(),
+            node_details: NodeDetailsComponent::new(),
+            search_popup: SearchPopupComponent::new(),
             status_bar: StatusBarComponent::new(),
             cmdline: CmdlineComponent::new(),
         }
@@ -77,10 +85,26 @@ impl Widget for &mut AppUIComponent {
         self.cmdline.render(layout.cmdline, buf);
 
         match self.tabs.curr_view() {
-            tabs::TabView::Tasks => self.graph_view.render(layout.graph_view, buf),
+            tabs::TabView::Tasks => {
+                if self.node_details.is_visible() {
+                    let [graph_area, details_area] = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                        .areas(layout.graph_view);
+                    self.graph_view.render(graph_area, buf);
+                    self.node_details
+                        .set_node(self.graph_view.get_current_node());
+                    self.node_details.render(details_area, buf);
+                } else {
+                    self.graph_view.render(layout.graph_view, buf);
+                }
+            }
             tabs::TabView::Calendar => (),
             tabs::TabView::DateGraph => (),
         }
+        if self.search_popup.is_visible() {
+            self.search_popup.render(layout.graph_view, buf);
+        }
         self.status_bar.render(layout.status_bar);
     }
 }
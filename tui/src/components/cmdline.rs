@@ -10,12 +10,15 @@ use ratatui::{
 use crate::events::{AppEvent, AskPromptType};
 
 const PROMPT_STYLE: Style = Style::new().fg(ratatui::style::Color::Yellow);
+const ERROR_STYLE: Style = Style::new().fg(ratatui::style::Color::Red);
 
 pub struct CmdlineComponent {
     prompt: String,
     input_string: String,
     input_pos: usize,
     shown: bool,
+    /// Shown in place of the prompt until the next event, with whether it reports an error
+    message: Option<(String, bool)>,
 }
 
 impl Default for CmdlineComponent {
@@ -31,6 +34,7 @@ impl CmdlineComponent {
             prompt: String::new(),
             input_pos: 0,
             shown: false,
+            message: None,
         }
     }
 
@@ -89,6 +93,15 @@ impl CmdlineComponent {
         self.shown = false;
     }
 
+    /// Shows a message until the next event, e.g. to report the outcome of an operation.
+    pub fn show_message(&mut self, message: String, is_error: bool) {
+        self.message = Some((message, is_error));
+    }
+
+    pub fn clear_message(&mut self) {
+        self.message = None;
+    }
+
     pub fn get_cursor_pos(&self, area: Rect) -> (u16, u16) {
         (
             area.x + (self.prompt.len() as u16 + self.input_string.len() as u16)
@@ -99,30 +112,24 @@ impl CmdlineComponent {
     /// Operate on string based on key code.
     fn operate_string(&mut self, code: &KeyCode) {
         match code {
-            KeyCode::Right => {
-                if self.input_pos < self.input_string.len() {
-                    self.input_pos += 1;
-                }
+            KeyCode::Right if self.input_pos < self.input_string.len() => {
+                self.input_pos += 1;
             }
-            KeyCode::Left => {
-                if self.input_pos > 0 {
-                    self.input_pos -= 1;
-                }
+            KeyCode::Left if self.input_pos > 0 => {
+                self.input_pos -= 1;
             }
             KeyCode::Char(c) => {
                 self.input_string.insert(self.input_pos, *c);
                 self.input_pos += 1;
             }
-            KeyCode::Backspace => {
-                if !self.input_string.is_empty() && self.input_pos > 0 {
-                    self.input_string.remove(self.input_pos - 1);
-                    self.input_pos -= 1;
-                }
+            KeyCode::Backspace if !self.input_string.is_empty() && self.input_pos > 0 => {
+                self.input_string.remove(self.input_pos - 1);
+                self.input_pos -= 1;
             }
-            KeyCode::Delete => {
-                if !self.input_string.is_empty() && self.input_pos < self.input_string.len() {
-                    self.input_string.remove(self.input_pos);
-                }
+            KeyCode::Delete
+                if !self.input_string.is_empty() && self.input_pos < self.input_string.len() =>
+            {
+                self.input_string.remove(self.input_pos);
             }
             _ => (),
         }
@@ -140,6 +147,9 @@ impl Widget for &mut CmdlineComponent {
                 Span::from(self.input_string.clone()),
             ])
             .render(area, buf);
+        } else if let Some((message, is_error)) = &self.message {
+            let style = if *is_error { ERROR_STYLE } else { Style::new() };
+            Span::styled(message.clone(), style).render(area, buf);
         }
     }
 }
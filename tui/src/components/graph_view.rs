@@ -1,3 +1,7 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
 use parse_datetime::parse_datetime;
 use ratatui::{
     buffer::Buffer,
@@ -7,7 +11,14 @@ use ratatui::{
     widgets::{List, ListItem, ListState, StatefulWidget, Widget},
 };
 use tuecore::graph::{
-    node::{task::TaskState, Node, NodeType},
+    errors::ErrorType,
+    filter::TraverseFilter,
+    node::{
+        task::{Priority, TaskState},
+        Node, NodeType,
+    },
+    observer::Event,
+    query::Query,
     Graph, GraphGetters,
 };
 
@@ -25,9 +36,73 @@ const NODE_IDX_STYLE: Style = Style::new()
     .fg(Color::DarkGray)
     .add_modifier(Modifier::BOLD);
 
+const PRIORITY_LOW_STYLE: Style = Style::new().fg(Color::Blue);
+const PRIORITY_MEDIUM_STYLE: Style = Style::new().fg(Color::Yellow);
+const PRIORITY_HIGH_STYLE: Style = Style::new().fg(Color::LightRed);
+const PRIORITY_URGENT_STYLE: Style = Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
+
 const INVALID_NODE_SELECTION_MSG: &str = "Invalid selected node index found";
 
+/// Nodes matched by the `/` filter. The filter is a query (see [`tuecore::graph::query`]);
+/// input that isn't a valid query is searched for in titles as is.
+struct FilterMatcher {
+    /// Nodes matching the query, or `None` when searching titles for the input as is
+    matches: Option<HashSet<usize>>,
+    /// Lowercased words highlighted in titles. Without a query, titles must contain all of them.
+    words: Vec<String>,
+}
+
+impl FilterMatcher {
+    fn new(graph: &Graph, filter: &str) -> Option<Self> {
+        if filter.trim().is_empty() {
+            return None;
+        }
+        let plain = || Self {
+            matches: None,
+            words: vec![filter.to_lowercase()],
+        };
+        let Ok(query) = filter.parse::<Query>() else {
+            return Some(plain());
+        };
+        let words = query.words().map(str::to_string).collect();
+        match query.eval(graph) {
+            Ok(matches) => Some(Self {
+                matches: Some(matches.into_iter().collect()),
+                words,
+            }),
+            Err(_) => Some(plain()),
+        }
+    }
+
+    /// Returns where in the node's title to highlight as the start and length of the match, if
+    /// the node matches.
+    fn find(&self, node: &Node) -> Option<(usize, usize)> {
+        let title = node.title.to_lowercase();
+        let whole = (0, node.title.len());
+        let mut found = self.words.iter().map(|word| {
+            title.find(word.as_str()).map(|pos| {
+                // Positions only carry over if lowercasing kept the title's length
+                if title.len() == node.title.len() {
+                    (pos, word.len())
+                } else {
+                    whole
+                }
+            })
+        });
+        match &self.matches {
+            // Highlight the whole title when the query has no words to point at
+            Some(matches) if matches.contains(&node.metadata.index) => {
+                Some(found.flatten().next().unwrap_or(whole))
+            }
+            Some(_) => None,
+            // Every word has to be found
+            None => found.try_fold(None, |first, pos| pos.map(|pos| first.or(Some(pos))))?,
+        }
+    }
+}
+
 trait GraphTUI {
+    #[allow(clippy::too_many_arguments)]
     fn get_nodes(
         &self,
         indices: &[usize],
@@ -35,7 +110,8 @@ trait GraphTUI {
         max_depth: u32,
         depth: u32,
         start: Option<usize>,
-        filter: &str,
+        filter: Option<&FilterMatcher>,
+        traverse_filter: &TraverseFilter,
         storage: &mut Vec<NodeInfo>,
     );
 }
@@ -48,7 +124,8 @@ impl GraphTUI for Graph {
         max_depth: u32,
         depth: u32,
         start: Option<usize>,
-        filter: &str,
+        filter: Option<&FilterMatcher>,
+        traverse_filter: &TraverseFilter,
         storage: &mut Vec<NodeInfo>,
     ) {
         // A sentinel value of 0 means infinite depth
@@ -56,7 +133,10 @@ impl GraphTUI for Graph {
             return;
         }
 
-        for i in indices {
+        let mut indices = indices.to_vec();
+        traverse_filter.apply(self, &mut indices);
+
+        for i in &indices {
             if let Some(start) = start {
                 if *i == start {
                     panic!("Graph looped");
@@ -64,12 +144,7 @@ impl GraphTUI for Graph {
             }
 
             let node = self.get_node(*i);
-            let msg_match = node.title.to_lowercase();
-            let pattern_loc = if filter.is_empty() {
-                None
-            } else {
-                msg_match.find(&filter.to_lowercase())
-            };
+            let pattern_loc = filter.and_then(|filter| filter.find(&node));
             let node_info = NodeInfo::new(node.metadata.index, depth, pattern_loc);
             storage.push(node_info);
 
@@ -86,6 +161,7 @@ impl GraphTUI for Graph {
                 depth + 1,
                 start,
                 filter,
+                traverse_filter,
                 storage,
             )
         }
@@ -95,6 +171,7 @@ impl GraphTUI for Graph {
 trait NodeTUIDisplay {
     fn print_tree_indent(depth: u32, multi_parents: bool) -> Option<Span<'static>>;
     fn get_status(&self) -> Span<'static>;
+    fn get_priority(&self) -> Option<Span<'static>>;
 }
 impl NodeTUIDisplay for Node {
     fn print_tree_indent(depth: u32, multi_parents: bool) -> Option<Span<'static>> {
@@ -117,12 +194,26 @@ impl NodeTUIDisplay for Node {
             NodeType::Task(ref data) => match data.state {
                 TaskState::None => Span::raw(" "),
                 TaskState::Partial => Span::raw("~"),
+                TaskState::InProgress => Span::raw(">"),
+                TaskState::Blocked => Span::raw("!"),
+                TaskState::Waiting => Span::raw("?"),
                 TaskState::Done => Span::raw("x"),
+                TaskState::Cancelled => Span::raw("-"),
             },
             NodeType::Pseudo => Span::raw("+"),
             NodeType::Date(_) => Span::raw("#"),
         }
     }
+
+    fn get_priority(&self) -> Option<Span<'static>> {
+        match self.data.as_task()?.priority {
+            Priority::None => None,
+            Priority::Low => Some(Span::styled("! ", PRIORITY_LOW_STYLE)),
+            Priority::Medium => Some(Span::styled("!! ", PRIORITY_MEDIUM_STYLE)),
+            Priority::High => Some(Span::styled("!!! ", PRIORITY_HIGH_STYLE)),
+            Priority::Urgent => Some(Span::styled("!!!! ", PRIORITY_URGENT_STYLE)),
+        }
+    }
 }
 
 /// Get a `Line` from a node message, with its pattern highlighted.
@@ -153,52 +244,42 @@ fn list_item_from_node(
     area: Rect,
     value: Node,
     depth: u32,
-    filter_pos: Option<usize>,
-    pattern_len: usize,
+    filter_match: Option<(usize, usize)>,
     is_selected: bool,
 ) -> ListItem<'static> {
     let indent = Node::print_tree_indent(depth, value.metadata.parents.len() > 1);
     let status = value.get_status();
     let statusbox_left = Span::styled("[", GRAPH_STATUSBOX_STYLE);
     let statusbox_right = Span::styled("] ", GRAPH_STATUSBOX_STYLE);
-    let spans;
+    let mut spans = Vec::new();
 
     // TODO: Are there Ratatui features that allow this?
     // let space = Span::raw(" ".to_string().repeat();
     // let idx = Span::styled(value.index.to_string(), NODE_IDX_STYLE);
 
-    // TODO: refactor lol what is this
     if let Some(indent) = indent {
-        if let Some(pos) = filter_pos {
-            let (left, mid, right) =
-                highlight_node_message(&value.title, pos, pattern_len, is_selected);
-            spans = vec![
-                indent,
-                statusbox_left,
-                status,
-                statusbox_right,
-                left,
-                mid,
-                right,
-            ];
-        } else {
-            let message = Span::raw(value.title.to_owned());
-            spans = vec![indent, statusbox_left, status, statusbox_right, message];
-        }
-    } else if let Some(pos) = filter_pos {
+        spans.push(indent);
+    }
+    spans.extend([statusbox_left, status, statusbox_right]);
+    // Right after the status box
+    if let Some(priority) = value.get_priority() {
+        spans.push(priority);
+    }
+    if let Some((pos, pattern_len)) = filter_match {
         let (left, mid, right) =
             highlight_node_message(&value.title, pos, pattern_len, is_selected);
-        spans = vec![statusbox_left, status, statusbox_right, left, mid, right];
+        spans.extend([left, mid, right]);
     } else {
-        let message = Span::raw(value.title.to_owned());
-        spans = vec![statusbox_left, status, statusbox_right, message];
+        spans.push(Span::raw(value.title.to_owned()));
     }
+
     // Insert the index
     // FIXME: Why so unelegant
     let mut line = Line::from(spans);
-    let space = " "
-        .to_string()
-        .repeat(area.width as usize - line.width() - value.metadata.index.to_string().len() - 1);
+    let space = " ".to_string().repeat(
+        (area.width as usize)
+            .saturating_sub(line.width() + value.metadata.index.to_string().len() + 1),
+    );
     let space_span = Span::raw(space);
     line.spans.push(space_span);
     let idx = Span::styled(value.metadata.index.to_string(), NODE_IDX_STYLE);
@@ -229,9 +310,16 @@ pub struct GraphViewComponent {
     show_archived: bool,
     filter: String,
 
+    /// Ordering and filtering applied to children on every level of the current view.
+    traverse_filter: TraverseFilter,
+
     /// Vector of nodes that match current filter pattern. Consists of node indices (`list_state`'s
     /// index, not the real node index)
     filtered_nodes: Vec<usize>,
+
+    /// Set by the graph's observer whenever the graph changes, so that the view is refreshed
+    /// before it is next rendered.
+    changed: Arc<AtomicBool>,
 }
 
 /// The minimum amount of information needed to be stored locally to enhance the efficiency of nodes rendering.
@@ -243,11 +331,12 @@ struct NodeInfo {
     depth: u32,
 
     /// Where the pattern match (if node matches the set filter)
-    pattern_loc: Option<usize>,
+    /// Where the pattern match starts and how long it is
+    pattern_loc: Option<(usize, usize)>,
 }
 
 impl NodeInfo {
-    fn new(node_idx: usize, depth: u32, pattern_loc: Option<usize>) -> Self {
+    fn new(node_idx: usize, depth: u32, pattern_loc: Option<(usize, usize)>) -> Self {
         Self {
             node_idx,
             depth,
@@ -278,14 +367,18 @@ impl GraphViewComponent {
             selection_idx_path: Vec::new(),
             show_date_graphs: false,
             filter: String::new(),
+            traverse_filter: TraverseFilter::default(),
             filtered_nodes: Vec::new(),
+            changed: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    // Refresh list of nodes to render. Always call after graph is manipulated.
+    // Refresh list of nodes to render. Changes to the graph are picked up on their own (see
+    // `sync`); call after changing what the view shows.
     pub fn update_nodes(&mut self) {
         self.nodes.clear();
         if let Some(graph) = &self.graph {
+            let matcher = FilterMatcher::new(graph, &self.filter);
             match self.current_node {
                 NodeLoc::Roots => {
                     if self.show_date_graphs {
@@ -296,7 +389,8 @@ impl GraphViewComponent {
                             self.max_depth,
                             1,
                             None,
-                            &self.filter,
+                            matcher.as_ref(),
+                            &self.traverse_filter,
                             &mut self.nodes,
                         )
                     } else {
@@ -307,20 +401,14 @@ impl GraphViewComponent {
                             self.max_depth,
                             1,
                             None,
-                            &self.filter,
+                            matcher.as_ref(),
+                            &self.traverse_filter,
                             &mut self.nodes,
                         );
                     }
                 }
                 NodeLoc::Idx(idx) => {
-                    // TODO: move this whole pattern matching shenanigans somewhere else for less
-                    // duplication
-                    let message = graph.get_node(idx).title.to_lowercase();
-                    let pattern_loc = if message.is_empty() {
-                        None
-                    } else {
-                        message.find(&self.filter)
-                    };
+                    let pattern_loc = matcher.as_ref().and_then(|m| m.find(&graph.get_node(idx)));
                     self.nodes.push(NodeInfo::new(idx, 0, pattern_loc)); // the parent node
                     GraphTUI::get_nodes(
                         graph,
@@ -329,7 +417,8 @@ impl GraphViewComponent {
                         self.max_depth,
                         1,
                         None,
-                        &self.filter,
+                        matcher.as_ref(),
+                        &self.traverse_filter,
                         &mut self.nodes,
                     );
                 }
@@ -383,7 +472,9 @@ impl GraphViewComponent {
         None
     }
 
-    pub fn load_graph(&mut self, graph: Graph) {
+    pub fn load_graph(&mut self, mut graph: Graph) {
+        let changed = Arc::clone(&self.changed);
+        graph.add_observer(move |_: &Event| changed.store(true, Ordering::Relaxed));
         self.graph = Some(graph);
         self.update_nodes();
     }
@@ -392,10 +483,63 @@ impl GraphViewComponent {
         &self.graph
     }
 
+    pub fn get_graph_mut(&mut self) -> Option<&mut Graph> {
+        self.graph.as_mut()
+    }
+
+    /// Refreshes the view if the graph changed since it was last refreshed.
+    pub fn sync(&mut self) {
+        if self.changed.swap(false, Ordering::Relaxed) {
+            self.refresh();
+        }
+    }
+
+    /// Refresh after the graph was changed. Steps out of nodes that no longer exist.
+    fn refresh(&mut self) {
+        if let Some(graph) = &self.graph {
+            if let Some(pos) = self
+                .path
+                .iter()
+                .position(|idx| graph.get_node_checked(*idx).is_none())
+            {
+                self.path.truncate(pos);
+                self.selection_idx_path.truncate(pos);
+                self.current_node = match self.path.last() {
+                    Some(idx) => NodeLoc::Idx(*idx),
+                    None => NodeLoc::Roots,
+                };
+            }
+        }
+        self.update_nodes();
+
+        if self
+            .list_state
+            .selected()
+            .is_some_and(|idx| idx >= self.nodes.len())
+        {
+            self.list_state.select(self.nodes.len().checked_sub(1));
+        }
+    }
+
+    /// Materializes the recurrences pending on the date nodes being shown. Recurring blueprints
+    /// are left pending for tuecli, which stores the blueprints.
+    pub fn view_shown_dates(&mut self) {
+        self.update_nodes();
+        if let Some(graph) = &mut self.graph {
+            let shown: Vec<usize> = self.nodes.iter().map(|node| node.node_idx).collect();
+            // TODO: error handling?
+            let _ = graph.view_dates(&shown, 1, &mut |_, _, _| Ok::<_, ErrorType>(false));
+        }
+    }
+
     pub fn graph_is_loaded(&self) -> bool {
         self.graph.is_some()
     }
 
+    pub fn shows_archived(&self) -> bool {
+        self.show_archived
+    }
+
     pub fn is_date_view(&self) -> bool {
         self.show_date_graphs
     }
@@ -425,6 +569,12 @@ impl GraphViewComponent {
         self.update_nodes();
     }
 
+    /// Toggle between manual ordering and ordering children by priority
+    pub fn toggle_priority_sort(&mut self) {
+        self.traverse_filter.sort_by_priority = !self.traverse_filter.sort_by_priority;
+        self.update_nodes();
+    }
+
     /// Go to next node that matches filter
     // TODO: why not just store everything beforehand?
     pub fn jump_next_filter(&mut self) {
@@ -463,7 +613,6 @@ impl GraphViewComponent {
         if let Some(graph) = &mut self.graph {
             if let Some(idx) = self.list_state.selected() {
                 let _ = graph.remove(idx);
-                self.update_nodes()
             }
         }
     }
@@ -549,6 +698,49 @@ impl GraphViewComponent {
         }
     }
 
+    /// Switch to the view a node is in, reached through its first parents, and select it.
+    pub fn jump_to(&mut self, node_idx: usize) {
+        let Some(graph) = &self.graph else {
+            return;
+        };
+        if graph.get_node_checked(node_idx).is_none() {
+            return;
+        }
+        let ancestors = graph.ancestor_path(node_idx);
+        let top = ancestors.first().copied().unwrap_or(node_idx);
+        self.show_date_graphs = graph.get_node(top).data.is_date();
+
+        self.switch_to_root();
+        self.selection_idx_path.clear();
+        self.update_nodes();
+        for ancestor in ancestors {
+            self.select_node(ancestor);
+            self.current_node = NodeLoc::Idx(ancestor);
+            self.path.push(ancestor);
+            self.selection_idx_path
+                .push(self.list_state.selected().unwrap_or(0));
+            self.update_nodes();
+        }
+        self.select_node(node_idx);
+    }
+
+    /// Select where a node is listed in the current view, if it is
+    fn select_node(&mut self, node_idx: usize) {
+        // Skip the parent node at the top of the list
+        let skip = match self.current_node {
+            NodeLoc::Idx(_) => 1,
+            NodeLoc::Roots => 0,
+        };
+        if let Some(pos) = self
+            .nodes
+            .iter()
+            .skip(skip)
+            .position(|info| info.node_idx == node_idx)
+        {
+            self.list_state.select(Some(pos + skip));
+        }
+    }
+
     /// Switch to view of root nodes
     pub fn switch_view_to_roots(&mut self) {
         self.switch_to_root();
@@ -561,16 +753,17 @@ impl GraphViewComponent {
 
     fn modify_task_status(graph: &mut Graph, node_idx: usize, curr_state: TaskState) {
         match curr_state {
-            TaskState::Done => {
+            TaskState::Done | TaskState::Cancelled => {
                 // TODO: error handling?
                 // TODO: this gets the node_idx converted to string, then the internal function
                 // converts it back into a usize. nahh.
                 let _ = graph.set_task_state(node_idx, TaskState::None, true);
             }
-            TaskState::None => {
-                let _ = graph.set_task_state(node_idx, TaskState::Done, true);
-            }
-            TaskState::Partial => {
+            TaskState::None
+            | TaskState::Partial
+            | TaskState::InProgress
+            | TaskState::Blocked
+            | TaskState::Waiting => {
                 let _ = graph.set_task_state(node_idx, TaskState::Done, true);
             }
         };
@@ -584,7 +777,6 @@ impl GraphViewComponent {
                 .expect(INVALID_NODE_SELECTION_MSG);
             let node = graph.get_node(self.nodes[idx].node_idx);
             let _ = graph.rename_node(node.metadata.index, new_message.to_owned());
-            self.update_nodes();
         }
     }
 
@@ -597,7 +789,26 @@ impl GraphViewComponent {
 
             let node_idx = self.nodes[idx].node_idx;
             let _ = graph.insert_child(message.to_string(), node_idx, pseudo);
-            self.update_nodes();
+        }
+    }
+
+    /// Copies the active node and everything below it under `target`, or to the roots if
+    /// `target` is empty.
+    pub fn copy_active_to(&mut self, target: &str) {
+        if let Some(graph) = &mut self.graph {
+            let idx = self
+                .list_state
+                .selected()
+                .expect(INVALID_NODE_SELECTION_MSG);
+
+            let node_idx = self.nodes[idx].node_idx;
+            if target.trim().is_empty() {
+                let subgraph = graph.extract(node_idx);
+                let _ = graph.graft(&subgraph, None, false);
+            // TODO: warn when the target is invalid
+            } else if let Ok(target) = graph.get_index(target.trim()) {
+                let _ = graph.copy_subgraph(node_idx, target);
+            }
         }
     }
 
@@ -619,7 +830,6 @@ impl GraphViewComponent {
                 }
             }
         }
-        self.update_nodes();
     }
 
     pub fn check_active(&mut self) {
@@ -637,7 +847,6 @@ impl GraphViewComponent {
                     node.data.as_task().unwrap().state,
                 )
             };
-            self.update_nodes();
         }
     }
 }
@@ -647,6 +856,7 @@ impl Widget for &mut GraphViewComponent {
     where
         Self: Sized,
     {
+        self.sync();
         if self.nodes.is_empty() {
             Line::from(" Graph is empty.").render(area, buf)
         }
@@ -668,7 +878,6 @@ impl Widget for &mut GraphViewComponent {
                         graph.get_node(node_info.node_idx),
                         node_info.depth,
                         node_info.pattern_loc,
-                        self.filter.len(),
                         selected_idx.is_some() && node_info.node_idx == active_node_idx,
                     )
                 })
@@ -682,3 +891,53 @@ impl Widget for &mut GraphViewComponent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(graph: &Graph, filter: &str) -> Vec<String> {
+        let matcher = FilterMatcher::new(graph, filter).unwrap();
+        graph
+            .node_ids()
+            .into_iter()
+            .map(|index| graph.get_node(index))
+            .filter(|node| matcher.find(node).is_some())
+            .map(|node| node.title)
+            .collect()
+    }
+
+    #[test]
+    fn filter_queries() {
+        let mut graph = Graph::new();
+        for title in ["deploy website", "deploy draft", "website draft", "other"] {
+            graph.insert_root(title.to_string(), false);
+        }
+
+        assert_eq!(matched(&graph, "deploy website"), ["deploy website"]);
+        assert_eq!(matched(&graph, "website deploy"), ["deploy website"]);
+        assert_eq!(matched(&graph, "-draft"), ["deploy website", "other"]);
+        assert_eq!(matched(&graph, "deploy -draft"), ["deploy website"]);
+        assert!(matched(&graph, "deploy missing").is_empty());
+    }
+
+    #[test]
+    fn filter_highlights() {
+        let mut graph = Graph::new();
+        let index = graph.insert_root("Deploy the website".to_string(), false);
+        let node = graph.get_node(index);
+
+        let matcher = FilterMatcher::new(&graph, "website deploy").unwrap();
+        assert_eq!(matcher.find(&node), Some((11, 7)));
+        let matcher = FilterMatcher::new(&graph, "-draft").unwrap();
+        assert_eq!(matcher.find(&node), Some((0, node.title.len())));
+
+        // Titles are searched for every word when the input isn't a query
+        let plain = |words: &[&str]| FilterMatcher {
+            matches: None,
+            words: words.iter().map(|word| word.to_string()).collect(),
+        };
+        assert_eq!(plain(&["website", "deploy"]).find(&node), Some((11, 7)));
+        assert_eq!(plain(&["deploy", "draft"]).find(&node), None);
+    }
+}
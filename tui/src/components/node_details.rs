@@ -0,0 +1,142 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+use tuecore::graph::node::{
+    task::{Priority, TaskState},
+    Node, NodeType,
+};
+
+const TITLE_STYLE: Style = Style::new().add_modifier(Modifier::BOLD);
+const FIELD_STYLE: Style = Style::new().fg(Color::Blue);
+const TIMESTAMP_FMT: &str = "%Y-%m-%d %H:%M";
+const NO_NOTE_STYLE: Style = Style::new()
+    .fg(Color::DarkGray)
+    .add_modifier(Modifier::ITALIC);
+
+/// Side panel showing the details and note of the active node.
+pub struct NodeDetailsComponent {
+    visible: bool,
+    node: Option<Node>,
+}
+
+impl Default for NodeDetailsComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeDetailsComponent {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            node: None,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Set which node to display. Call before rendering.
+    pub fn set_node(&mut self, node: Option<Node>) {
+        self.node = node;
+    }
+
+    fn field(name: &str, value: String) -> Line<'static> {
+        Line::from(vec![
+            Span::styled(format!("{name}: "), FIELD_STYLE),
+            Span::raw(value),
+        ])
+    }
+
+    fn lines(node: &Node) -> Vec<Line<'static>> {
+        let mut lines = vec![Line::styled(node.title.clone(), TITLE_STYLE)];
+
+        let status = match &node.data {
+            NodeType::Task(data) => match data.state {
+                TaskState::None => "Not done",
+                TaskState::Partial => "Partially done",
+                TaskState::InProgress => "In progress",
+                TaskState::Blocked => "Blocked",
+                TaskState::Waiting => "Waiting",
+                TaskState::Done => "Done",
+                TaskState::Cancelled => "Cancelled",
+            }
+            .to_string(),
+            NodeType::Date(data) => format!("Date ({})", data.date),
+            NodeType::Pseudo => "Pseudo".to_string(),
+        };
+        lines.push(Self::field("Status", status));
+
+        if let Some(data) = node.data.as_task() {
+            if data.priority != Priority::None {
+                lines.push(Self::field("Priority", format!("{:?}", data.priority)));
+            }
+            if let Some(due) = data.due {
+                let due = match due.time {
+                    Some(time) => format!("{} {}", due.date, time.format("%H:%M")),
+                    None => due.date.to_string(),
+                };
+                lines.push(Self::field("Due", due));
+            }
+        }
+        if !node.metadata.aliases.is_empty() {
+            lines.push(Self::field("Aliases", node.metadata.aliases.join(", ")));
+        }
+        if !node.metadata.tags.is_empty() {
+            let tags = node
+                .metadata
+                .tags
+                .iter()
+                .map(|tag| format!("#{tag}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            lines.push(Self::field("Tags", tags));
+        }
+
+        if let Some(created) = node.metadata.created {
+            lines.push(Self::field(
+                "Created",
+                created.format(TIMESTAMP_FMT).to_string(),
+            ));
+        }
+        if let Some(completed) = node.metadata.completed {
+            lines.push(Self::field(
+                "Finished",
+                completed.format(TIMESTAMP_FMT).to_string(),
+            ));
+        }
+
+        lines.push(Line::raw(""));
+        match node.note {
+            Some(ref note) => lines.extend(note.lines().map(|line| Line::raw(line.to_string()))),
+            None => lines.push(Line::styled("No note.", NO_NOTE_STYLE)),
+        }
+        lines
+    }
+}
+
+impl Widget for &mut NodeDetailsComponent {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let block = Block::bordered().title(" Details ");
+        let lines = match &self.node {
+            Some(node) => NodeDetailsComponent::lines(node),
+            None => vec![Line::styled("No node selected.", NO_NOTE_STYLE)],
+        };
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,125 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{palette::tailwind::SLATE, Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, List, ListItem, ListState, StatefulWidget, Widget},
+};
+use tuecore::graph::{path, Graph, GraphGetters};
+
+const SELECTED_STYLE: Style = Style::new().bg(SLATE.c800).add_modifier(Modifier::BOLD);
+const PATH_STYLE: Style = Style::new().fg(Color::DarkGray);
+const INDEX_STYLE: Style = Style::new().fg(Color::LightBlue);
+
+/// A search hit, ready to be listed
+struct Entry {
+    node_idx: usize,
+    path: String,
+    title: String,
+}
+
+/// Popup searching the whole graph for a node to jump to.
+pub struct SearchPopupComponent {
+    visible: bool,
+    entries: Vec<Entry>,
+    list_state: ListState,
+}
+
+impl Default for SearchPopupComponent {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchPopupComponent {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            entries: Vec::new(),
+            list_state: ListState::default(),
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn show(&mut self) {
+        self.entries.clear();
+        self.list_state.select(None);
+        self.visible = true;
+    }
+
+    pub fn hide(&mut self) {
+        self.visible = false;
+    }
+
+    /// Search the graph again, e.g. after the search text changed. The best hit gets selected.
+    pub fn update(&mut self, graph: &Graph, text: &str, show_archived: bool) {
+        self.entries = graph
+            .search(text)
+            .into_iter()
+            .map(|hit| graph.get_node(hit.index))
+            .filter(|node| show_archived || !node.metadata.archived)
+            .map(|node| Entry {
+                node_idx: node.metadata.index,
+                path: graph
+                    .ancestor_path(node.metadata.index)
+                    .last()
+                    .map(|parent| format!("{}/", graph.path_of(*parent)))
+                    .unwrap_or_default(),
+                title: path::name_of(&node),
+            })
+            .collect();
+        self.list_state
+            .select((!self.entries.is_empty()).then_some(0));
+    }
+
+    pub fn select_next(&mut self) {
+        self.list_state.select_next();
+    }
+
+    pub fn select_previous(&mut self) {
+        self.list_state.select_previous();
+    }
+
+    /// Index of the node to jump to
+    pub fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|pos| self.entries.get(pos))
+            .map(|entry| entry.node_idx)
+    }
+}
+
+impl Widget for &mut SearchPopupComponent {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let [area] = Layout::horizontal([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                ListItem::from(Line::from(vec![
+                    Span::styled(entry.path.clone(), PATH_STYLE),
+                    Span::raw(entry.title.clone()),
+                    Span::styled(format!(" ({})", entry.node_idx), INDEX_STYLE),
+                ]))
+            })
+            .collect();
+
+        Clear.render(area, buf);
+        let list = List::new(items)
+            .block(Block::bordered().title(" Jump to "))
+            .highlight_style(SELECTED_STYLE);
+        StatefulWidget::render(list, area, buf, &mut self.list_state);
+    }
+}
@@ -2,12 +2,16 @@ use ratatui::layout::Rect;
 
 use crate::events::AppEvent;
 
+// TODO: the status bar is still a work in progress, and nothing builds its items yet. Drop these
+// `dead_code` allows once they are wired up.
+#[allow(dead_code)]
 enum TabView {
     Tasks,
     DateGraph,
     Calendar,
 }
 
+#[allow(dead_code)]
 #[derive(PartialEq)]
 enum RefreshInterval {
     /// When graph is updated, e.g by switching parent, changing nesting depth, or deleting a node.
@@ -20,6 +24,7 @@ pub struct StatusBarItem {
 }
 
 impl StatusBarItem {
+    #[allow(dead_code)]
     fn new(component: Box<dyn StatusBarItemUI>) -> Self {
         Self {
             refresh_interval: component.get_refresh_interval(),
@@ -28,6 +33,7 @@ impl StatusBarItem {
     }
 }
 
+#[allow(dead_code)]
 trait StatusBarItemUI {
     fn init(&self);
     fn render(&self);
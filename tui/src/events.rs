@@ -1,4 +1,4 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind};
 
 use crate::{app::App, components::tabs::TabView};
 
@@ -39,11 +39,18 @@ pub enum ActiveNodeOperation {
     UnlinkFrom,        // D
     Check,             // C
     AddPseudoToParent, // U
-    AddPseudoToActive, // u
+    AddPseudoToActive, // y (was u, which now undoes)
     AddToParent,       // A
     AddToActive,       // a
 }
 
+/// Undoing and redoing changes to the graph.
+#[derive(Clone, Copy)]
+pub enum HistoryOperation {
+    Undo, // u
+    Redo, // Ctrl-R
+}
+
 /// Operations done with selected nodes.
 #[derive(Clone, Copy)]
 pub enum SelectedNodeOperation {
@@ -58,9 +65,11 @@ pub enum SelectedNodeOperation {
 /// Node filtering operations.
 #[derive(Clone, Copy)]
 pub enum ViewFilterOperation {
-    Filter,         // /
-    SetDepth,       // *
-    ToggleArchived, // .
+    Filter,             // /
+    SetDepth,           // *
+    ToggleArchived,     // .
+    TogglePrioritySort, // o
+    ToggleDetails,      // i
     JumpNext,
     JumpPrev,
 }
@@ -91,6 +100,7 @@ pub enum InternalEvent {
 pub enum OperationalEvent {
     Filter(ViewFilterOperation),
     Help, // H or F1
+    History(HistoryOperation),
     Navigate(NavDirection),
     OperateActiveNode(ActiveNodeOperation),
     OperateSelected(SelectedNodeOperation),
@@ -218,10 +228,17 @@ pub fn process_key(app: &App, key_event: KeyEvent) -> Option<AppEvent> {
                 KeyCode::Char('U') => Some(AppEvent::Operational(
                     OperationalEvent::OperateActiveNode(ActiveNodeOperation::AddPseudoToParent),
                 )),
-                KeyCode::Char('u') => Some(AppEvent::Operational(
+                KeyCode::Char('y') => Some(AppEvent::Operational(
                     OperationalEvent::OperateActiveNode(ActiveNodeOperation::AddPseudoToActive),
                 )),
 
+                KeyCode::Char('u') => Some(AppEvent::Operational(OperationalEvent::History(
+                    HistoryOperation::Undo,
+                ))),
+                KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => Some(
+                    AppEvent::Operational(OperationalEvent::History(HistoryOperation::Redo)),
+                ),
+
                 KeyCode::Char('C') => Some(AppEvent::Operational(
                     OperationalEvent::OperateActiveNode(ActiveNodeOperation::Check),
                 )),
@@ -248,6 +265,12 @@ pub fn process_key(app: &App, key_event: KeyEvent) -> Option<AppEvent> {
                 KeyCode::Char('.') => Some(AppEvent::Operational(OperationalEvent::Filter(
                     ViewFilterOperation::ToggleArchived,
                 ))),
+                KeyCode::Char('o') => Some(AppEvent::Operational(OperationalEvent::Filter(
+                    ViewFilterOperation::TogglePrioritySort,
+                ))),
+                KeyCode::Char('i') => Some(AppEvent::Operational(OperationalEvent::Filter(
+                    ViewFilterOperation::ToggleDetails,
+                ))),
                 KeyCode::Char('n') => Some(AppEvent::Operational(OperationalEvent::Filter(
                     ViewFilterOperation::JumpNext,
                 ))),
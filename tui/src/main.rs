@@ -77,10 +77,17 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     };
 
+    let journal = if local {
+        doc::load_local_journal(PathBuf::from(args.local.clone().unwrap()))
+    } else {
+        doc::load_global_journal()?
+    };
+
     let mut stderr = io::stderr();
     app_init(&mut stderr)?;
     let mut app = App::new();
     app.load_graph(graph);
+    app.load_journal(journal);
 
     let backend = CrosstermBackend::new(stderr);
     let mut terminal = Terminal::new(backend)?;
@@ -100,13 +107,15 @@ fn main() -> Result<(), Box<dyn Error>> {
     if local {
         doc::save_local(
             // Default to current directory if --local is not specified
-            PathBuf::from(args.local.unwrap()),
+            PathBuf::from(args.local.clone().unwrap()),
             &doc::Doc::new(app.get_graph().as_ref().expect("Failed to get graph")),
         )?;
+        doc::save_local_journal(PathBuf::from(args.local.unwrap()), app.get_journal())?;
     } else {
         doc::save_global(&doc::Doc::new(
             app.get_graph().as_ref().expect("Failed to get graph"),
         ))?;
+        doc::save_global_journal(app.get_journal())?;
     }
 
     Ok(())